@@ -1,11 +1,31 @@
 use rocket::FromForm;
 
+mod macros;
 pub mod server;
+pub mod signature;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+// Re-exported for the `declare_*_manager!` macros, so plugins don't need to depend on the same version of toml.
+#[doc(hidden)]
+pub use toml;
+
+// Re-exported so plugins implementing `serialize_value` build their values with the same version of serde_json
+// as the server.
+pub use serde_json;
 
 use serde::{Serialize, Deserialize};
 
 
 #[derive(FromForm, Serialize, Deserialize)]
 pub struct UploadedData {
-    pub data: Vec<u8>
+    pub data: Vec<u8>,
+    /// The secret the data source signs its updates with, sent along with its registration. Never passed on
+    /// to the manager, see [`signature`].
+    #[serde(default)]
+    pub signing_secret: Option<String>,
+    /// The capabilities the data source needs its manager to have, by their names in
+    /// [`server::capabilities::NAMES`]. The data source isn't registered if its manager lacks any of them.
+    #[serde(default)]
+    pub requires: Vec<String>
 }
@@ -0,0 +1,230 @@
+/// Generates the entry points of a plugin providing a single data source manager, used by the
+/// `declare_*_manager!` macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __declare_manager {
+    ($create_func:ident, $manager_type:ident, $create_type:ident, $manager:ty, $constructor:expr) => {
+        #[no_mangle]
+        pub extern "C" fn $create_func(
+            config: Box<Option<$crate::toml::map::Map<String, $crate::toml::Value>>>
+        ) -> $crate::server::FFIResult<$crate::server::$manager_type> {
+            let constructor: fn(Option<$crate::toml::map::Map<String, $crate::toml::Value>>) -> $crate::server::Result<$manager> = $constructor;
+
+            // Panics can't unwind out of an extern "C" function, so they're caught before they reach the server.
+            let manager = ::std::panic::catch_unwind(move || constructor(*config))
+                .unwrap_or(Err($crate::server::DataSourceManagerError::Panicked));
+
+            Box::new(
+                manager.map(|manager| Box::new($crate::server::CatchPanics(manager)) as Box<$crate::server::$manager_type>)
+            )
+        }
+
+        // Fails to compile if the generated function doesn't match what the server expects.
+        const _: $crate::server::$create_type = $create_func;
+
+        #[no_mangle]
+        pub extern "C" fn florust_abi_version() -> u32 {
+            $crate::server::FLORUST_ABI_VERSION
+        }
+
+        const _: $crate::server::FlorustAbiVersion = florust_abi_version;
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`IIntegerDataSourceManager`](crate::server::IIntegerDataSourceManager).
+///
+/// Generates the `create_iinteger_data_source_manager` function the server uses to create the manager by
+/// default, along with the `florust_abi_version` function every plugin must export. The manager is created
+/// using [`Default`], or using the given constructor, which is passed the extra sections of the plugin's
+/// config:
+///
+/// ```text
+/// declare_iinteger_manager!(MyManager);
+/// declare_iinteger_manager!(MyManager, |config| MyManager::from_config(config));
+/// ```
+///
+/// As every plugin exports a single `florust_abi_version` function, only one `declare_*_manager!` macro can be
+/// used per plugin.
+#[macro_export]
+macro_rules! declare_iinteger_manager {
+    ($manager:ty) => {
+        $crate::declare_iinteger_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_iinteger_data_source_manager,
+            IIntegerDataSourceManager,
+            CreateIIntegerDataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`UIntegerDataSourceManager`](crate::server::UIntegerDataSourceManager).
+/// See [`declare_iinteger_manager`] for details.
+#[macro_export]
+macro_rules! declare_uinteger_manager {
+    ($manager:ty) => {
+        $crate::declare_uinteger_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_uinteger_data_source_manager,
+            UIntegerDataSourceManager,
+            CreateUIntegerDataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`FloatDataSourceManager`](crate::server::FloatDataSourceManager).
+/// See [`declare_iinteger_manager`] for details.
+///
+/// A plugin whose manager parses big endian floats, created by the function the macro generates the same way
+/// the server creates it:
+///
+/// ```
+/// use florust_common::{declare_float_manager, server::{self, DataSourceManager, DataSourceManagerError, ParserDataSourceManager, Result}};
+///
+/// type Parser = fn(&[u8]) -> Result<f64>;
+///
+/// fn parse(data: &[u8]) -> Result<f64> {
+///     let bytes = data.try_into().map_err(|_| DataSourceManagerError::InvalidData("expected 8 bytes".to_string()))?;
+///     Ok(f64::from_be_bytes(bytes))
+/// }
+///
+/// declare_float_manager!(ParserDataSourceManager<f64, Parser>, |_| Ok(server::from_parser("float", parse as Parser)));
+///
+/// fn main() {
+///     let Ok(manager) = *create_float_data_source_manager(Box::new(None)) else {
+///         panic!("the manager should be created");
+///     };
+///     assert_eq!(manager.manager_id(), "float");
+///     assert_eq!(florust_abi_version(), server::FLORUST_ABI_VERSION);
+/// }
+/// ```
+#[macro_export]
+macro_rules! declare_float_manager {
+    ($manager:ty) => {
+        $crate::declare_float_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_float_data_source_manager,
+            FloatDataSourceManager,
+            CreateFloatDataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`Float32DataSourceManager`](crate::server::Float32DataSourceManager).
+/// See [`declare_iinteger_manager`] for details.
+#[macro_export]
+macro_rules! declare_float32_manager {
+    ($manager:ty) => {
+        $crate::declare_float32_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_float32_data_source_manager,
+            Float32DataSourceManager,
+            CreateFloat32DataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`BooleanDataSourceManager`](crate::server::BooleanDataSourceManager).
+/// See [`declare_iinteger_manager`] for details.
+#[macro_export]
+macro_rules! declare_boolean_manager {
+    ($manager:ty) => {
+        $crate::declare_boolean_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_boolean_data_source_manager,
+            BooleanDataSourceManager,
+            CreateBooleanDataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`StringDataSourceManager`](crate::server::StringDataSourceManager).
+/// See [`declare_iinteger_manager`] for details.
+#[macro_export]
+macro_rules! declare_string_manager {
+    ($manager:ty) => {
+        $crate::declare_string_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_string_data_source_manager,
+            StringDataSourceManager,
+            CreateStringDataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`FloatVecDataSourceManager`](crate::server::FloatVecDataSourceManager).
+/// See [`declare_iinteger_manager`] for details.
+#[macro_export]
+macro_rules! declare_float_vec_manager {
+    ($manager:ty) => {
+        $crate::declare_float_vec_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_float_vec_data_source_manager,
+            FloatVecDataSourceManager,
+            CreateFloatVecDataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`I128DataSourceManager`](crate::server::I128DataSourceManager).
+/// See [`declare_iinteger_manager`] for details.
+#[macro_export]
+macro_rules! declare_i128_manager {
+    ($manager:ty) => {
+        $crate::declare_i128_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_i128_data_source_manager,
+            I128DataSourceManager,
+            CreateI128DataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
+
+/// Declares the entry points of a plugin providing a single [`U128DataSourceManager`](crate::server::U128DataSourceManager).
+/// See [`declare_iinteger_manager`] for details.
+#[macro_export]
+macro_rules! declare_u128_manager {
+    ($manager:ty) => {
+        $crate::declare_u128_manager!($manager, |_| Ok(<$manager as Default>::default()));
+    };
+    ($manager:ty, $constructor:expr) => {
+        $crate::__declare_manager!(
+            create_u128_data_source_manager,
+            U128DataSourceManager,
+            CreateU128DataSourceManager,
+            $manager,
+            $constructor
+        );
+    };
+}
@@ -1,6 +1,7 @@
-use std::result;
+use std::{fmt, future::Future, marker::PhantomData, panic::{self, AssertUnwindSafe}, result, time::Duration};
 
-use rocket::async_trait;
+use chrono::{DateTime, Utc};
+use rocket::{async_trait, futures::FutureExt, tokio::io::{AsyncRead, AsyncReadExt}};
 use serde::{Serialize, Deserialize};
 use thiserror::Error;
 
@@ -12,16 +13,71 @@ pub enum FlorustServerPluginError {
     DataSourceDoesntExist(String),
     #[error("Attempted to deregister data source ID ({0}), but it already was deregistered")]
     DataSourceAlreadyDeregistered(String),
-    #[error("Attempted to access data source manager ({0}), but manager doesn't exist")]
+    #[error("Attempted to access data source manager ({0}), but no loaded plugin provides a manager with this ID")]
     DataSourceManagerDoesntExist(String),
     #[error("Data source manager failed with error: {0}")]
     DataSourceManager(DataSourceManagerError),
+    #[error("Failed to load plugin ({path}): {reason}")]
+    PluginLoadFailed {
+        path: String,
+        reason: String
+    },
+    #[error("Plugin library ({0}) doesn't export florust_abi_version, it was likely compiled against an older florust_common")]
+    AbiVersionMissing(String),
+    #[error("Plugin library ({path}) was compiled against ABI version {found}, but the server expects ABI version {expected}")]
+    AbiVersionMismatch {
+        path: String,
+        expected: u32,
+        found: u32
+    },
+    #[error("Manager ({manager_id}) produces values of type {actual}, but values of type {expected} were expected")]
+    ManagerTypeMismatch {
+        manager_id: String,
+        expected: String,
+        actual: String
+    },
+    #[error("Manager ({manager_id}) panicked, the manager may be left in an inconsistent state")]
+    PluginPanicked {
+        manager_id: String,
+        source_id: Option<String>
+    },
+    #[error("Manager ({manager_id}) couldn't be created, its plugin's config is likely invalid: {detail}")]
+    ConfigInvalid {
+        manager_id: String,
+        detail: String
+    },
+    #[error("Manager ({manager_id}) depends on manager ({dependency}), which isn't loaded")]
+    DependencyMissing {
+        manager_id: String,
+        dependency: String
+    },
+    #[error("Manager ({manager_id}) depends on itself through a cycle of dependencies ({cycle})")]
+    DependencyCycle {
+        manager_id: String,
+        cycle: String
+    },
 }
 
-#[derive(Serialize, Deserialize, Error, Debug)]
+#[derive(Serialize, Deserialize, Error, Debug, Clone)]
 pub enum DataSourceManagerError {
     #[error("DataSourceManager was given invalid data: {0}")]
-    InvalidData(String)
+    InvalidData(String),
+    #[error("DataSourceManager was given a value ({value}) outside of the accepted range [{min}, {max}]")]
+    OutOfRange {
+        value: String,
+        min: String,
+        max: String
+    },
+    #[error("DataSourceManager took too long to respond")]
+    Timeout,
+    #[error("DataSourceManager panicked")]
+    Panicked,
+    #[error("DataSourceManager doesn't support {0}")]
+    Unsupported(String),
+    #[error("DataSourceManager rejected the data source's registration: {0}")]
+    RegistrationRejected(String),
+    #[error("DataSourceManager already has the most data sources it can handle registered ({0})")]
+    AtCapacity(usize)
 }
 
 /// A specialized [`Result`](result::Result) type for [`DataSourceManager`] operations.
@@ -31,6 +87,123 @@ pub enum DataSourceManagerError {
 /// crate.
 pub type Result<T> = result::Result<T, DataSourceManagerError>;
 
+/// A value produced by a [`DataSourceManager`], along with the time at which it was measured.
+///
+/// `measured_at` should be set by managers whose data sources report when a measurement was taken, like
+/// sensors that buffer readings while offline and flush them in bulk later. If it is `None` Florust will
+/// use the time at which the data was received instead.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TimestampedData<T> {
+    pub value: T,
+    pub measured_at: Option<DateTime<Utc>>
+}
+
+/// Formats the values managers produce as text, used by the default implementation of
+/// [`DataSourceManager::value_to_string`]. Scalars are formatted using [`Display`](fmt::Display), and vectors
+/// using [`Debug`](fmt::Debug), like `[1.0, 2.5]`.
+pub trait FormatValue {
+    fn format_value(&self) -> String;
+}
+
+macro_rules! format_value_display {
+    ($($value_type:ty),*) => {
+        $(
+            impl FormatValue for $value_type {
+                fn format_value(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+format_value_display!(i64, u64, i128, u128, f64, f32, bool, String);
+
+impl<T: fmt::Debug> FormatValue for Vec<T> {
+    fn format_value(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Flags describing which optional [`DataSourceManager`] methods a manager implements, as returned by
+/// [`DataSourceManager::capabilities`]. Florust skips calling optional methods that a manager doesn't list,
+/// and reports every manager's capabilities to frontends.
+pub mod capabilities {
+    /// The manager implements [`validate_registration`](super::DataSourceManager::validate_registration).
+    pub const VALIDATE_REGISTRATION: u32 = 1 << 0;
+    /// The manager implements [`validate_data`](super::DataSourceManager::validate_data).
+    pub const VALIDATE_DATA: u32 = 1 << 1;
+    /// The manager implements [`update_data_with_context`](super::DataSourceManager::update_data_with_context).
+    pub const CONTEXT: u32 = 1 << 2;
+    /// The manager implements [`update_data_timestamped`](super::DataSourceManager::update_data_timestamped).
+    pub const TIMESTAMPS: u32 = 1 << 3;
+    /// The manager implements the namespaced methods, like
+    /// [`update_data_in_namespace`](super::DataSourceManager::update_data_in_namespace).
+    pub const NAMESPACES: u32 = 1 << 4;
+    /// The manager implements [`reconfigure`](super::DataSourceManager::reconfigure).
+    pub const RECONFIGURE: u32 = 1 << 5;
+    /// The manager implements [`shutdown`](super::DataSourceManager::shutdown).
+    pub const SHUTDOWN: u32 = 1 << 6;
+    /// The manager implements [`batch_update_data`](super::DataSourceManager::batch_update_data).
+    pub const BATCH: u32 = 1 << 7;
+    /// The manager implements [`reset`](super::DataSourceManager::reset).
+    pub const RESET: u32 = 1 << 8;
+    /// The manager implements [`update_data_traced`](super::DataSourceManager::update_data_traced).
+    pub const TRACING: u32 = 1 << 9;
+    /// The manager implements [`update_data_stream`](super::DataSourceManager::update_data_stream).
+    pub const STREAM: u32 = 1 << 10;
+    /// The manager implements [`aggregate_sources`](super::DataSourceManager::aggregate_sources).
+    pub const AGGREGATE_SOURCES: u32 = 1 << 11;
+    /// The manager implements [`on_error`](super::DataSourceManager::on_error).
+    pub const ON_ERROR: u32 = 1 << 12;
+    /// The manager implements [`initial_value`](super::DataSourceManager::initial_value).
+    pub const INITIAL_VALUE: u32 = 1 << 13;
+    /// The manager implements [`serialize_value`](super::DataSourceManager::serialize_value).
+    pub const SERIALIZE_VALUE: u32 = 1 << 14;
+
+    /// The capabilities whose methods Florust never calls on managers that don't list them in
+    /// [`capabilities`](super::DataSourceManager::capabilities). The methods of the other capabilities are called
+    /// either way, as their default implementations fall back to the required methods.
+    pub const GATED: u32 = VALIDATE_REGISTRATION
+        | VALIDATE_DATA
+        | RECONFIGURE
+        | SHUTDOWN
+        | RESET
+        | TRACING
+        | STREAM
+        | AGGREGATE_SOURCES
+        | ON_ERROR
+        | INITIAL_VALUE
+        | SERIALIZE_VALUE;
+
+    /// Every capability, paired with the name it's reported under.
+    pub const NAMES: [(u32, &str); 15] = [
+        (VALIDATE_REGISTRATION, "validate_registration"),
+        (VALIDATE_DATA, "validate_data"),
+        (CONTEXT, "context"),
+        (TIMESTAMPS, "timestamps"),
+        (NAMESPACES, "namespaces"),
+        (RECONFIGURE, "reconfigure"),
+        (SHUTDOWN, "shutdown"),
+        (BATCH, "batch"),
+        (RESET, "reset"),
+        (TRACING, "tracing"),
+        (STREAM, "stream"),
+        (AGGREGATE_SOURCES, "aggregate_sources"),
+        (ON_ERROR, "on_error"),
+        (INITIAL_VALUE, "initial_value"),
+        (SERIALIZE_VALUE, "serialize_value"),
+    ];
+
+    /// Returns the names of every capability set in `capabilities`.
+    pub fn names(capabilities: u32) -> Vec<&'static str> {
+        NAMES.iter()
+            .filter(|(capability, _)| capabilities & capability != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
 /// A trait defining a base data source manager. This is a base type that is used the specialized
 /// managers below. This type simply serves as a template to define the functionality that all specialized
 /// data manager types share.
@@ -39,6 +212,111 @@ pub trait DataSourceManager<T>: Sync + Send {
     /// Returns the id associated with the data manager.
     fn manager_id(&self) -> &'static str;
 
+    /// Returns the unit of the values produced by the data manager, like `"°C"`, if it has one.
+    ///
+    /// This is purely informational, and is used by frontends to label the data the manager produces.
+    fn unit(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns a human readable description of what the data manager measures, if it has one.
+    ///
+    /// This is purely informational, and is used by frontends to describe the data the manager produces.
+    fn description(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns which of the optional methods the data manager implements, as a combination of the flags in
+    /// [`capabilities`], like `capabilities::VALIDATE_DATA | capabilities::SHUTDOWN`.
+    ///
+    /// Florust doesn't call the methods of the capabilities in [`capabilities::GATED`] unless they're listed
+    /// here, so managers implementing them must override this method. Returns no capabilities by default.
+    fn capabilities(&self) -> u32 {
+        0
+    }
+
+    /// Returns a JSON Schema that the data data sources register with must match, if the data manager expects
+    /// registration data in a specific JSON shape.
+    ///
+    /// Florust validates registration data against the schema before the data manager is called, rejecting
+    /// registrations that don't match with an error listing every part of the data that doesn't, so managers
+    /// don't have to validate it themselves. Registrations without data are validated as `null`. Returns `None`
+    /// by default, in which case registration data isn't validated.
+    fn registration_schema(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the content type of the updates the data manager parses, like `"application/json"` or
+    /// `"application/octet-stream"`, if it only parses one.
+    ///
+    /// Florust rejects streamed updates sent with a different `Content-Type` before they reach the data manager,
+    /// and reports the content type to clients, so they know what to send. Updates uploaded as JSON or a form
+    /// are sent with the content type of the upload rather than the data inside it, so they aren't checked.
+    /// Returns `None` by default, in which case updates of any content type are accepted.
+    fn expected_content_type(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns how often data sources are expected to send updates, like every 10 seconds for a sensor sampled
+    /// on a fixed schedule.
+    ///
+    /// Florust reports the interval to clients, so dashboards can tell gaps in a time series apart from data
+    /// sources that update rarely, and reports data sources that haven't sent an update in three times the
+    /// interval as stale. Returns `None` by default, in which case data sources are never reported as stale.
+    fn expected_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns how many data sources may be registered with the data manager at once, like the number of
+    /// channels of the hardware it reads from.
+    ///
+    /// Florust rejects registrations once this many data sources are registered with
+    /// [`DataSourceManagerError::AtCapacity`], without calling the manager, until one of them deregisters.
+    /// Returns `None` by default, in which case any number of data sources may register.
+    fn max_sources(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the canonical form of the data source id `id`, like `id` with its whitespace trimmed and in
+    /// lowercase.
+    ///
+    /// Florust normalizes every id it's given before using it, so data sources that report their id
+    /// inconsistently, like `Kitchen` and `kitchen `, are treated as the same data source. Every other method is
+    /// only ever given normalized ids. It is perfectly acceptable to leave this implementation as is, it returns
+    /// `id` unchanged by default.
+    fn normalize_id(&self, id: &str) -> String {
+        id.to_string()
+    }
+
+    /// Returns the textual form of a value produced by the data manager, like a float with a fixed number of
+    /// decimal places.
+    ///
+    /// Florust uses this wherever values are shown as text, like exports and log lines, so managers control
+    /// how their data appears instead of the server guessing its precision. It is perfectly acceptable to leave
+    /// this implementation as is, it formats the value using [`FormatValue`] by default.
+    fn value_to_string(&self, value: &T) -> String
+    where
+        T: FormatValue
+    {
+        value.format_value()
+    }
+
+    /// Returns the JSON form of a value produced by the data manager, like a float rounded to a fixed number of
+    /// decimal places, or a record with its fields renamed.
+    ///
+    /// Florust sends values in this form from the endpoints that read them back, like `/latest` and `/history`,
+    /// in place of its own encoding of them, so managers control how their data appears on the wire. Values
+    /// that fail to serialize are sent in Florust's own encoding instead. It is perfectly acceptable to leave
+    /// this implementation as is, it serializes the value using `serde_json` by default.
+    ///
+    /// Returns the JSON form of the value, or a [`DataSourceManagerError`] in case of an error.
+    fn serialize_value(&self, value: &T) -> Result<serde_json::Value>
+    where
+        T: Serialize
+    {
+        serde_json::to_value(value).map_err(|err| DataSourceManagerError::InvalidData(err.to_string()))
+    }
+
     /// Called when a new data source registers itself to the id belonging to the data source manager.
     /// 
     /// Florust will handle keeping track of what data sources are registered to your data source manager's
@@ -59,9 +337,26 @@ pub trait DataSourceManager<T>: Sync + Send {
     /// registered to them, as such, it is perfectly acceptable to leave this implementation as a stub that
     /// just immediately returns `Ok(())`
     /// 
+    /// If the provided data is malformed, [`DataSourceManagerError::RegistrationRejected`] should be returned.
+    /// If any error is returned the data source is not registered.
+    /// 
     /// Returns the unit type if no errors occurred, or a [`DataSourceManagerError`] in case of an error.
     async fn register_with_data(&self, id: String, data: &[u8]) -> Result<()>;
 
+    /// Called when a data source wants to know whether its registration would be accepted, without actually
+    /// registering. Also called immediately before a data source registers, with the same data it registers
+    /// with.
+    ///
+    /// This method must not have any side effects. It only exists for data source managers that reject some
+    /// registrations, so that data sources can find out without registering. It is perfectly acceptable to
+    /// leave this implementation as is, it immediately returns `Ok(())` by default.
+    ///
+    /// Returns the unit type if the registration would be accepted, or a [`DataSourceManagerError`] if it
+    /// wouldn't, usually [`DataSourceManagerError::RegistrationRejected`].
+    async fn validate_registration(&self, _id: &str, _data: Option<&[u8]>) -> Result<()> {
+        Ok(())
+    }
+
     /// Called when a data source requests to be deregistered from the data source manager.
     /// 
     /// Florust will handle keeping track of what data sources are registered to your data source manager's
@@ -93,25 +388,587 @@ pub trait DataSourceManager<T>: Sync + Send {
     /// 
     /// Returns the value parsed from the data, or a [`DataSourceManagerError`] in case of an error.
     async fn update_data(&self, id: &str, data: &[u8]) -> Result<T>;
+
+    /// Called when a data source has posted an update, immediately before the update is parsed. Provides the
+    /// raw data that the data source has sent to the Florust server.
+    ///
+    /// This method only exists for data source managers that want to perform cheap sanity checks on data
+    /// before it is parsed. If this method returns an error the update is rejected, and the error is returned
+    /// to the data source without the data ever being parsed. It is perfectly acceptable to leave this
+    /// implementation as is, it immediately returns `Ok(())` by default.
+    ///
+    /// Returns the unit type if the data is valid, or a [`DataSourceManagerError`] if it isn't.
+    async fn validate_data(&self, _id: &str, _data: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a data source has posted an update. Provides the raw data that the data source has sent
+    /// to the Florust server, along with the data the data source provided when it registered, if it provided
+    /// any.
+    ///
+    /// This method only needs to be implemented by data source managers that need the registration data to
+    /// parse an update, like managers that compute calibrated values from calibration info provided during
+    /// registration. It allows such managers to be written without keeping track of the registration data
+    /// themselves. The default implementation calls [`update_data`](DataSourceManager::update_data).
+    ///
+    /// Returns the value parsed from the data, or a [`DataSourceManagerError`] in case of an error.
+    async fn update_data_with_context(&self, id: &str, data: &[u8], _registration_data: Option<&[u8]>) -> Result<T>
+    where
+        T: Send
+    {
+        self.update_data(id, data).await
+    }
+
+    /// Called when a data source has posted an update. Provides the raw data that the data source has sent
+    /// to the Florust server, along with the data the data source provided when it registered, if it provided
+    /// any. Its default implementation calls
+    /// [`update_data_with_context`](DataSourceManager::update_data_with_context) and leaves the measurement
+    /// time unset.
+    ///
+    /// This method only needs to be implemented by data source managers that can determine when the data
+    /// they're given was measured, usually because the data source includes that information in its update.
+    ///
+    /// Returns the value parsed from the data along with when it was measured, or a [`DataSourceManagerError`]
+    /// in case of an error.
+    async fn update_data_timestamped(&self, id: &str, data: &[u8], registration_data: Option<&[u8]>) -> Result<TimestampedData<T>>
+    where
+        T: Send
+    {
+        Ok(TimestampedData {
+            value: self.update_data_with_context(id, data, registration_data).await?,
+            measured_at: None
+        })
+    }
+
+    /// Called when a new data source registers itself to the id belonging to the data source manager, along
+    /// with the namespace the data source registered under. Data sources that registered without a namespace
+    /// belong to the empty namespace. This is the method Florust calls when a data source registers, its
+    /// default implementation ignores the namespace and calls [`register`](DataSourceManager::register), or
+    /// [`register_with_data`](DataSourceManager::register_with_data) if the data source provided additional info.
+    ///
+    /// Data sources in different namespaces may share the same id. This method only needs to be implemented by
+    /// data source managers that keep track of their data sources themselves, and need to tell them apart.
+    ///
+    /// Returns the unit type if no errors occurred, or a [`DataSourceManagerError`] in case of an error.
+    async fn register_in_namespace(&self, _namespace: &str, id: String, data: Option<&[u8]>) -> Result<()> {
+        match data {
+            Some(data) => self.register_with_data(id, data).await,
+            None => self.register(id).await
+        }
+    }
+
+    /// Called when a data source requests to be deregistered from the data source manager, along with the
+    /// namespace the data source registered under. This is the method Florust calls when a data source
+    /// deregisters, its default implementation ignores the namespace and calls
+    /// [`deregister`](DataSourceManager::deregister), or
+    /// [`deregister_with_data`](DataSourceManager::deregister_with_data) if the data source provided additional
+    /// info.
+    ///
+    /// Returns the unit type if no errors occurred, or a [`DataSourceManagerError`] in case of an error.
+    async fn deregister_in_namespace(&self, _namespace: &str, id: &str, data: Option<&[u8]>) -> Result<()> {
+        match data {
+            Some(data) => self.deregister_with_data(id, data).await,
+            None => self.deregister(id).await
+        }
+    }
+
+    /// Called when a data source has posted an update, along with the namespace the data source registered
+    /// under, the data it registered with, and the W3C `traceparent` header the update was sent with, like
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`, if it was sent with a valid one. Florust calls
+    /// this method instead of [`update_data_in_namespace`](DataSourceManager::update_data_in_namespace) for
+    /// managers that list [`capabilities::TRACING`], its default implementation ignores the header and calls
+    /// [`update_data_in_namespace`](DataSourceManager::update_data_in_namespace).
+    ///
+    /// This method only needs to be implemented by data source managers that forward data downstream, and want
+    /// to continue the trace of the request that delivered it.
+    ///
+    /// Returns the value parsed from the data along with when it was measured, or a [`DataSourceManagerError`]
+    /// in case of an error.
+    async fn update_data_traced(
+        &self,
+        namespace: &str,
+        id: &str,
+        data: &[u8],
+        registration_data: Option<&[u8]>,
+        _trace_id: Option<&str>
+    ) -> Result<TimestampedData<T>>
+    where
+        T: Send
+    {
+        self.update_data_in_namespace(namespace, id, data, registration_data).await
+    }
+
+    /// Called when a data source has streamed an update, with a reader the update is read from as it arrives,
+    /// rather than once all of it has, along with the namespace the data source registered under and the data it
+    /// registered with. Florust only calls this method for managers that list [`capabilities::STREAM`], its
+    /// default implementation reads the whole update and calls
+    /// [`update_data_in_namespace`](DataSourceManager::update_data_in_namespace).
+    ///
+    /// This method only needs to be implemented by data source managers that parse large updates incrementally,
+    /// like blocks of samples, so the whole update never has to be held in memory. Reading fails once the update
+    /// exceeds the manager's upload size limit.
+    ///
+    /// Returns the value parsed from the data along with when it was measured, or a [`DataSourceManagerError`]
+    /// in case of an error.
+    async fn update_data_stream(
+        &self,
+        namespace: &str,
+        id: &str,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        registration_data: Option<&[u8]>
+    ) -> Result<TimestampedData<T>>
+    where
+        T: Send
+    {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await
+            .map_err(|err| DataSourceManagerError::InvalidData(format!("update couldn't be read: {}", err)))?;
+        self.update_data_in_namespace(namespace, id, &data, registration_data).await
+    }
+
+    /// Called when a data source has posted an update, along with the namespace the data source registered
+    /// under. This is the method Florust calls when receiving an update, its default implementation ignores the
+    /// namespace and calls [`update_data_timestamped`](DataSourceManager::update_data_timestamped).
+    ///
+    /// Returns the value parsed from the data along with when it was measured, or a [`DataSourceManagerError`]
+    /// in case of an error.
+    async fn update_data_in_namespace(
+        &self,
+        _namespace: &str,
+        id: &str,
+        data: &[u8],
+        registration_data: Option<&[u8]>
+    ) -> Result<TimestampedData<T>>
+    where
+        T: Send
+    {
+        self.update_data_timestamped(id, data, registration_data).await
+    }
+
+    /// Called when the config file of the plugin that provides this data source manager has changed. Provides
+    /// the config sections other than the `plugin` section, the same way they're provided when the manager is
+    /// created.
+    ///
+    /// This method only exists for data source managers that want to pick up changes to their config, like new
+    /// thresholds, without the server restarting and dropping all their registered data sources. It is perfectly
+    /// acceptable to leave this implementation as is, it ignores the new config and returns `Ok(())` by default.
+    ///
+    /// Returns the unit type if the new config was applied, or a [`DataSourceManagerError`] in case of an error.
+    async fn reconfigure(&self, _config: Option<toml::map::Map<String, toml::Value>>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the Florust server is shutting down, before the plugin providing this data source manager
+    /// is unloaded.
+    ///
+    /// This method only exists for data source managers that need to clean up before the server exits, like
+    /// managers that buffer writes and need to flush them, or that hold files open. Florust shuts down all
+    /// managers concurrently and waits for all of them to finish before exiting. It is perfectly acceptable to
+    /// leave this implementation as is, it immediately returns `Ok(())` by default.
+    ///
+    /// Returns the unit type if no errors occurred, or a [`DataSourceManagerError`] in case of an error.
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a data source has posted a batch of updates. Provides the raw data that the data source
+    /// has sent to the Florust server.
+    ///
+    /// This method only needs to be implemented by data source managers whose data sources send many values
+    /// in a single buffer, such as high frequency sources. The default implementation calls
+    /// [`update_data`](DataSourceManager::update_data) once and returns its value as the only value in the batch.
+    ///
+    /// Returns the values parsed from the data, in the order they should be logged, or a [`DataSourceManagerError`]
+    /// in case of an error.
+    async fn batch_update_data(&self, id: &str, data: &[u8]) -> Result<Vec<T>>
+    where
+        T: Send
+    {
+        Ok(vec![self.update_data(id, data).await?])
+    }
+
+    /// Called when a registered data source is asked to reset, like when a counter rolls over at the end
+    /// of a day.
+    ///
+    /// This method only exists for data source managers that keep running state per data source, like
+    /// counters or integrators, which needs to be zeroed without the data source deregistering and dropping
+    /// its history. Florust only calls this method for data sources that are registered. It is perfectly
+    /// acceptable to leave this implementation as is, it immediately returns `Ok(())` by default.
+    ///
+    /// Returns the unit type if the data source was reset, or a [`DataSourceManagerError`] in case of an error.
+    async fn reset(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called to combine the latest values of several data sources into a single value, like the average
+    /// temperature of a fleet of sensors, which Florust serves as if it came from a virtual data source.
+    ///
+    /// `values` holds the latest value of every registered data source in a namespace that has logged one,
+    /// paired with its id, and is never empty. What combining means is up to the manager, which is why there's
+    /// no sensible default, it immediately returns [`DataSourceManagerError::Unsupported`] by default.
+    ///
+    /// Returns the combined value, or a [`DataSourceManagerError`] in case of an error.
+    async fn aggregate_sources(&self, _values: &[(String, T)]) -> Result<T>
+    where
+        T: Send + Sync
+    {
+        Err(DataSourceManagerError::Unsupported("aggregating data sources".to_string()))
+    }
+
+    /// Called whenever registering, deregistering, or updating the data source `id` fails, with the error it
+    /// failed with. This includes the errors the server reports on the manager's behalf, like
+    /// [`DataSourceManagerError::Timeout`] and [`DataSourceManagerError::Panicked`].
+    ///
+    /// This method only exists for data source managers that want to react to their own errors, like counting
+    /// them or recovering a connection that broke. Florust handles the error the same way whether or not the
+    /// manager observes it, but waits for this method before responding to the request that failed, so it
+    /// should return quickly. It is perfectly acceptable to leave this implementation as is, it does nothing by
+    /// default.
+    async fn on_error(&self, _id: &str, _error: &DataSourceManagerError) {}
+
+    /// Called right after the data source `id` registered, along with the data it registered with, if any, to
+    /// get the value it starts out with, like a relay that's off until told otherwise.
+    ///
+    /// This method only exists for data source managers whose data sources have a meaningful reading before
+    /// they send their first update. Florust logs the value as the data source's first value, so reads right
+    /// after registering don't come back empty. A failure is reported to the server, but leaves the data source
+    /// registered without a value. It is perfectly acceptable to leave this implementation as is, it
+    /// immediately returns `Ok(None)` by default, in which case data sources start out without a value.
+    ///
+    /// Returns the value the data source starts out with, if it has one, or a [`DataSourceManagerError`] in case
+    /// of an error.
+    async fn initial_value(&self, _id: &str, _data: Option<&[u8]>) -> Result<Option<T>>
+    where
+        T: Send
+    {
+        Ok(None)
+    }
+}
+
+/// A [`DataSourceManager`] that parses updates using a closure, and doesn't need to do anything when data
+/// sources register or deregister. Created using [`from_parser`].
+pub struct ParserDataSourceManager<T, F> {
+    id: &'static str,
+    parser: F,
+    _value: PhantomData<fn() -> T>
+}
+
+#[async_trait]
+impl<T, F> DataSourceManager<T> for ParserDataSourceManager<T, F>
+where
+    T: Send,
+    F: Fn(&[u8]) -> Result<T> + Send + Sync
+{
+    fn manager_id(&self) -> &'static str {
+        self.id
+    }
+
+    async fn register(&self, _id: String) -> Result<()> {
+        Ok(())
+    }
+
+    async fn register_with_data(&self, _id: String, _data: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    async fn deregister(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn deregister_with_data(&self, _id: &str, _data: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    async fn update_data(&self, _id: &str, data: &[u8]) -> Result<T> {
+        (self.parser)(data)
+    }
+}
+
+/// Creates a [`DataSourceManager`] with the id `id` that parses every update using `parser`.
+///
+/// This is meant for simple plugins that only need to turn the data they're given into a value, and removes
+/// the need to implement the registration and deregistration methods as stubs.
+pub fn from_parser<T, F>(id: &'static str, parser: F) -> ParserDataSourceManager<T, F>
+where
+    T: Send,
+    F: Fn(&[u8]) -> Result<T> + Send + Sync
+{
+    ParserDataSourceManager {
+        id,
+        parser,
+        _value: PhantomData
+    }
+}
+
+/// A [`DataSourceManager`] that forwards every call to `M`, turning panics into
+/// [`DataSourceManagerError::Panicked`]. Created by the `declare_*_manager!` macros.
+///
+/// Plugins link their own copy of the standard library, so a panic unwinding out of a plugin can't be caught
+/// by the server and aborts it. Panics have to be caught inside the plugin instead, which is what this does.
+/// Plugins that don't use the macros should wrap their managers in it before returning them.
+pub struct CatchPanics<M>(pub M);
+
+/// Awaits `call`, returning [`DataSourceManagerError::Panicked`] if it panics.
+async fn catch_panics<T>(call: impl Future<Output = Result<T>>) -> Result<T> {
+    AssertUnwindSafe(call).catch_unwind().await
+        .unwrap_or(Err(DataSourceManagerError::Panicked))
 }
 
-/// One of three specialized types of [`DataSourceManager`] that is responsible for producing data of
-/// type [`i64`] from data provided by a data source.
+#[async_trait]
+impl<T, M> DataSourceManager<T> for CatchPanics<M>
+where
+    T: Send,
+    M: DataSourceManager<T>
+{
+    fn manager_id(&self) -> &'static str {
+        self.0.manager_id()
+    }
+
+    fn unit(&self) -> Option<&'static str> {
+        self.0.unit()
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        self.0.description()
+    }
+
+    fn capabilities(&self) -> u32 {
+        self.0.capabilities()
+    }
+
+    fn registration_schema(&self) -> Option<&'static str> {
+        // Registration data isn't validated if getting the schema panics.
+        panic::catch_unwind(AssertUnwindSafe(|| self.0.registration_schema())).unwrap_or(None)
+    }
+
+    fn expected_content_type(&self) -> Option<&'static str> {
+        // Updates of any content type are accepted if getting the content type panics.
+        panic::catch_unwind(AssertUnwindSafe(|| self.0.expected_content_type())).unwrap_or(None)
+    }
+
+    fn expected_interval(&self) -> Option<Duration> {
+        // Data sources are never reported as stale if getting the interval panics.
+        panic::catch_unwind(AssertUnwindSafe(|| self.0.expected_interval())).unwrap_or(None)
+    }
+
+    fn max_sources(&self) -> Option<usize> {
+        // Any number of data sources may register if getting the limit panics.
+        panic::catch_unwind(AssertUnwindSafe(|| self.0.max_sources())).unwrap_or(None)
+    }
+
+    fn normalize_id(&self, id: &str) -> String {
+        // Leaving the id as is keeps the data source usable if normalizing it panics.
+        panic::catch_unwind(AssertUnwindSafe(|| self.0.normalize_id(id)))
+            .unwrap_or_else(|_| id.to_string())
+    }
+
+    fn value_to_string(&self, value: &T) -> String
+    where
+        T: FormatValue
+    {
+        // The default formatting is used if the manager's own formatting panics.
+        panic::catch_unwind(AssertUnwindSafe(|| self.0.value_to_string(value)))
+            .unwrap_or_else(|_| value.format_value())
+    }
+
+    fn serialize_value(&self, value: &T) -> Result<serde_json::Value>
+    where
+        T: Serialize
+    {
+        panic::catch_unwind(AssertUnwindSafe(|| self.0.serialize_value(value)))
+            .unwrap_or(Err(DataSourceManagerError::Panicked))
+    }
+
+    async fn register(&self, id: String) -> Result<()> {
+        catch_panics(self.0.register(id)).await
+    }
+
+    async fn register_with_data(&self, id: String, data: &[u8]) -> Result<()> {
+        catch_panics(self.0.register_with_data(id, data)).await
+    }
+
+    async fn validate_registration(&self, id: &str, data: Option<&[u8]>) -> Result<()> {
+        catch_panics(self.0.validate_registration(id, data)).await
+    }
+
+    async fn deregister(&self, id: &str) -> Result<()> {
+        catch_panics(self.0.deregister(id)).await
+    }
+
+    async fn deregister_with_data(&self, id: &str, data: &[u8]) -> Result<()> {
+        catch_panics(self.0.deregister_with_data(id, data)).await
+    }
+
+    async fn update_data(&self, id: &str, data: &[u8]) -> Result<T> {
+        catch_panics(self.0.update_data(id, data)).await
+    }
+
+    async fn validate_data(&self, id: &str, data: &[u8]) -> Result<()> {
+        catch_panics(self.0.validate_data(id, data)).await
+    }
+
+    async fn update_data_traced(
+        &self,
+        namespace: &str,
+        id: &str,
+        data: &[u8],
+        registration_data: Option<&[u8]>,
+        trace_id: Option<&str>
+    ) -> Result<TimestampedData<T>> {
+        catch_panics(self.0.update_data_traced(namespace, id, data, registration_data, trace_id)).await
+    }
+
+    async fn update_data_stream(
+        &self,
+        namespace: &str,
+        id: &str,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        registration_data: Option<&[u8]>
+    ) -> Result<TimestampedData<T>> {
+        catch_panics(self.0.update_data_stream(namespace, id, reader, registration_data)).await
+    }
+
+    async fn update_data_with_context(&self, id: &str, data: &[u8], registration_data: Option<&[u8]>) -> Result<T> {
+        catch_panics(self.0.update_data_with_context(id, data, registration_data)).await
+    }
+
+    async fn update_data_timestamped(&self, id: &str, data: &[u8], registration_data: Option<&[u8]>) -> Result<TimestampedData<T>> {
+        catch_panics(self.0.update_data_timestamped(id, data, registration_data)).await
+    }
+
+    async fn register_in_namespace(&self, namespace: &str, id: String, data: Option<&[u8]>) -> Result<()> {
+        catch_panics(self.0.register_in_namespace(namespace, id, data)).await
+    }
+
+    async fn deregister_in_namespace(&self, namespace: &str, id: &str, data: Option<&[u8]>) -> Result<()> {
+        catch_panics(self.0.deregister_in_namespace(namespace, id, data)).await
+    }
+
+    async fn update_data_in_namespace(
+        &self,
+        namespace: &str,
+        id: &str,
+        data: &[u8],
+        registration_data: Option<&[u8]>
+    ) -> Result<TimestampedData<T>> {
+        catch_panics(self.0.update_data_in_namespace(namespace, id, data, registration_data)).await
+    }
+
+    async fn reconfigure(&self, config: Option<toml::map::Map<String, toml::Value>>) -> Result<()> {
+        catch_panics(self.0.reconfigure(config)).await
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        catch_panics(self.0.shutdown()).await
+    }
+
+    async fn batch_update_data(&self, id: &str, data: &[u8]) -> Result<Vec<T>> {
+        catch_panics(self.0.batch_update_data(id, data)).await
+    }
+
+    async fn reset(&self, id: &str) -> Result<()> {
+        catch_panics(self.0.reset(id)).await
+    }
+
+    async fn aggregate_sources(&self, values: &[(String, T)]) -> Result<T>
+    where
+        T: Send + Sync
+    {
+        catch_panics(self.0.aggregate_sources(values)).await
+    }
+
+    async fn on_error(&self, id: &str, error: &DataSourceManagerError) {
+        // There's no way to report a panic from here, the server carries on the same way either way.
+        let _ = catch_panics(async {
+            self.0.on_error(id, error).await;
+            Ok(())
+        }).await;
+    }
+
+    async fn initial_value(&self, id: &str, data: Option<&[u8]>) -> Result<Option<T>>
+    where
+        T: Send
+    {
+        catch_panics(self.0.initial_value(id, data)).await
+    }
+}
+
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`i64`]
+/// from data provided by a data source.
 pub type IIntegerDataSourceManager = dyn DataSourceManager<i64>;
 
-/// One of three specialized types of [`DataSourceManager`] that is responsible for producing data of
-/// type [`u64`] from data provided by a data source.
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`u64`]
+/// from data provided by a data source.
 pub type UIntegerDataSourceManager = dyn DataSourceManager<u64>;
 
-/// One of three specialized types of [`DataSourceManager`] that is responsible for producing data of
-/// type [`f64`] from data provided by a data source.
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`f64`]
+/// from data provided by a data source.
 pub type FloatDataSourceManager = dyn DataSourceManager<f64>;
 
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`f32`]
+/// from data provided by a data source. Values are stored and reported with single precision, which halves
+/// the memory needed to log them compared to a [`FloatDataSourceManager`].
+pub type Float32DataSourceManager = dyn DataSourceManager<f32>;
+
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`bool`]
+/// from data provided by a data source. Useful for data sources reporting binary states, like whether
+/// a door is open or a pump is on.
+pub type BooleanDataSourceManager = dyn DataSourceManager<bool>;
+
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`String`]
+/// from data provided by a data source. Useful for data sources reporting short textual statuses, like
+/// firmware versions or mode names.
+pub type StringDataSourceManager = dyn DataSourceManager<String>;
+
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`Vec<f64>`]
+/// from data provided by a data source. Useful for data sources reporting several readings in a single
+/// update, like every channel of a multi-channel sensor, without splitting them across data sources.
+pub type FloatVecDataSourceManager = dyn DataSourceManager<Vec<f64>>;
+
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`i128`]
+/// from data provided by a data source. Useful for values that can overflow 64 bits, like nanosecond counters.
+pub type I128DataSourceManager = dyn DataSourceManager<i128>;
+
+/// A specialized type of [`DataSourceManager`] that is responsible for producing data of type [`u128`]
+/// from data provided by a data source. Useful for values that can overflow 64 bits, like monotonic ids.
+pub type U128DataSourceManager = dyn DataSourceManager<u128>;
+
+/// The version of the ABI between the Florust server and its plugins. This is bumped whenever a change is made
+/// that would cause a plugin compiled against an older version of this crate to misbehave when loaded, like
+/// changes to the layout of [`DataSourceManager`].
+pub const FLORUST_ABI_VERSION: u32 = 27;
+
+/// A function that returns the ABI version a plugin was compiled against. Every plugin must export a function
+/// of this type named `florust_abi_version` which returns [`FLORUST_ABI_VERSION`], plugins that don't, or whose
+/// version doesn't match the server's, are not loaded.
+pub type FlorustAbiVersion = unsafe extern "C" fn() -> u32;
+
+/// A function that returns the default values of a plugin's config. Plugins may export a function of this type
+/// named `florust_default_config`, the server then merges the config it passes to the plugin's create
+/// functions and to [`DataSourceManager::reconfigure`] over these values, so users only need to set the values
+/// they want to change. Values set in the config take precedence over the defaults, and tables set in both are
+/// merged the same way.
+pub type DefaultConfig = unsafe extern "C" fn() -> Box<toml::map::Map<String, toml::Value>>;
+
+/// A function that returns the ids of the managers a plugin's managers depend on, like a manager forwarding
+/// data to another. Plugins may export a function of this type named `florust_plugin_dependencies`, the server
+/// then only creates their managers once the managers they depend on are created, and shuts those down last.
+/// Plugins whose dependencies aren't loaded, or that depend on each other in a cycle, fail to load without
+/// being created.
+pub type PluginDependencies = unsafe extern "C" fn() -> Box<Vec<String>>;
+
 /// A type representing a double boxed trait. This type is double boxed as a boxed trait object is a fat
 /// pointer which would be difficult to transport across FFI boundaries. Boxing the box resolves this issue
 /// by making it a normal sized pointer.
+///
+/// Only the outer box crosses the FFI boundary. Values produced by the manager, including non-[`Copy`]
+/// types like [`String`] and [`Vec<f64>`], are returned through the trait object's methods and need no special handling.
 pub type FFIResult<T> = Box<Result<Box<T>>>;
 
+/// A function that returns a [`FFIResult`] which contains a data source manager of type `T`. The more
+/// specific aliases below should be preferred when writing a plugin.
+pub type CreateDataSourceManager<T> = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<T>;
+
 /// A function that returns a [`FFIBoxTrait`] which contains an [`IIntegerDataSourceManager`].
 pub type CreateIIntegerDataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<IIntegerDataSourceManager>;
 
@@ -119,4 +976,73 @@ pub type CreateIIntegerDataSourceManager = unsafe extern "C" fn(Box<Option<toml:
 pub type CreateUIntegerDataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<UIntegerDataSourceManager>;
 
 /// A function that returns a [`FFIBoxTrait`] which contains an [`FloatDataSourceManager`].
-pub type CreateFloatDataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<FloatDataSourceManager>;
\ No newline at end of file
+pub type CreateFloatDataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<FloatDataSourceManager>;
+
+/// A function that returns a [`FFIBoxTrait`] which contains an [`Float32DataSourceManager`].
+pub type CreateFloat32DataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<Float32DataSourceManager>;
+
+/// A function that returns a [`FFIBoxTrait`] which contains an [`BooleanDataSourceManager`].
+pub type CreateBooleanDataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<BooleanDataSourceManager>;
+
+/// A function that returns a [`FFIBoxTrait`] which contains an [`StringDataSourceManager`].
+pub type CreateStringDataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<StringDataSourceManager>;
+
+/// A function that returns a [`FFIBoxTrait`] which contains an [`FloatVecDataSourceManager`].
+pub type CreateFloatVecDataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<FloatVecDataSourceManager>;
+
+/// A function that returns a [`FFIBoxTrait`] which contains an [`I128DataSourceManager`].
+pub type CreateI128DataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<I128DataSourceManager>;
+
+/// A function that returns a [`FFIBoxTrait`] which contains an [`U128DataSourceManager`].
+pub type CreateU128DataSourceManager = unsafe extern "C" fn(Box<Option<toml::map::Map<String, toml::Value>>>) -> FFIResult<U128DataSourceManager>;
+
+/// A function that returns every data source manager of type `T` a plugin offers, as pairs of manager id and
+/// the function that creates the manager. Plugins offering more than one manager of the same type should
+/// export a function of this type named `florust_data_source_managers`, the server then creates and
+/// registers every manager in the list instead of calling a single create function. The more specific
+/// aliases below should be preferred when writing a plugin.
+pub type DataSourceManagerEntries<T> = unsafe extern "C" fn() -> Box<Vec<(String, CreateDataSourceManager<T>)>>;
+
+/// A function that returns every [`IIntegerDataSourceManager`] a plugin offers.
+pub type IIntegerDataSourceManagerEntries = DataSourceManagerEntries<IIntegerDataSourceManager>;
+
+/// A function that returns every [`UIntegerDataSourceManager`] a plugin offers.
+pub type UIntegerDataSourceManagerEntries = DataSourceManagerEntries<UIntegerDataSourceManager>;
+
+/// A function that returns every [`FloatDataSourceManager`] a plugin offers.
+pub type FloatDataSourceManagerEntries = DataSourceManagerEntries<FloatDataSourceManager>;
+
+/// A function that returns every [`Float32DataSourceManager`] a plugin offers.
+pub type Float32DataSourceManagerEntries = DataSourceManagerEntries<Float32DataSourceManager>;
+
+/// A function that returns every [`BooleanDataSourceManager`] a plugin offers.
+pub type BooleanDataSourceManagerEntries = DataSourceManagerEntries<BooleanDataSourceManager>;
+
+/// A function that returns every [`StringDataSourceManager`] a plugin offers.
+pub type StringDataSourceManagerEntries = DataSourceManagerEntries<StringDataSourceManager>;
+
+/// A function that returns every [`FloatVecDataSourceManager`] a plugin offers.
+pub type FloatVecDataSourceManagerEntries = DataSourceManagerEntries<FloatVecDataSourceManager>;
+
+/// A function that returns every [`I128DataSourceManager`] a plugin offers.
+pub type I128DataSourceManagerEntries = DataSourceManagerEntries<I128DataSourceManager>;
+
+/// A function that returns every [`U128DataSourceManager`] a plugin offers.
+pub type U128DataSourceManagerEntries = DataSourceManagerEntries<U128DataSourceManager>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docs_list_every_gated_capability() {
+        let docs = include_str!("../../docs/server/plugins.md");
+        let start = docs.find("The server never calls the methods of").expect("docs should list the gated capabilities");
+        let sentence = &docs[start..start + docs[start..].find(". ").unwrap()];
+
+        for (capability, name) in capabilities::NAMES {
+            let listed = sentence.contains(&format!("`{name}`"));
+            assert_eq!(listed, capabilities::GATED & capability != 0, "capability {name} is listed wrongly");
+        }
+    }
+}
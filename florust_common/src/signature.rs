@@ -0,0 +1,42 @@
+//! Signing of updates, so the server can tell updates sent by a data source apart from spoofed ones. Data
+//! sources that registered with a signing secret sign the data of every update with it, and send the signature
+//! in the [`SIGNATURE_HEADER`] header, which the server checks before the update reaches the manager.
+//!
+//! ```
+//! use florust_common::signature::{sign, verify};
+//!
+//! let signature = sign("secret", &21.5f64.to_be_bytes());
+//! assert!(verify("secret", &21.5f64.to_be_bytes(), &signature));
+//! assert!(!verify("secret", &22.5f64.to_be_bytes(), &signature));
+//! assert!(!verify("other secret", &21.5f64.to_be_bytes(), &signature));
+//! assert!(!verify("secret", &21.5f64.to_be_bytes(), "not hex"));
+//! ```
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Name of the header the signature of an update is sent in.
+pub const SIGNATURE_HEADER: &str = "X-Florust-Signature";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(secret: &str, data: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac
+}
+
+/// Signs `data` with `secret`, returning the hex encoded HMAC-SHA256 of `data`.
+pub fn sign(secret: &str, data: &[u8]) -> String {
+    hex::encode(mac(secret, data).finalize().into_bytes())
+}
+
+/// Checks that `signature` is the hex encoded HMAC-SHA256 of `data` signed with `secret`. Signatures are
+/// compared in constant time, so checking them doesn't reveal how much of a forged signature was right.
+pub fn verify(secret: &str, data: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature.trim()) else {
+        return false;
+    };
+
+    mac(secret, data).verify_slice(&signature).is_ok()
+}
@@ -0,0 +1,309 @@
+//! Helpers for unit testing [`DataSourceManager`] implementations without loading them into the server.
+//!
+//! [`ManagerDriver`] calls a manager in process the same way the server would, skipping the FFI boundary and
+//! the HTTP layer, so plugins can be tested with plain `#[test]` functions. Enabled by the `testing` feature,
+//! which plugins should only enable for their dev dependencies:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! florust_common = { path = "../florust_common", features = ["testing"] }
+//! ```
+//!
+//! Testing a manager that parses big endian floats, like the server's default float manager:
+//!
+//! ```
+//! use florust_common::{server::{self, DataSourceManagerError}, testing::ManagerDriver};
+//!
+//! let manager = server::from_parser("float", |data: &[u8]| {
+//!     let bytes = data.try_into().map_err(|_| DataSourceManagerError::InvalidData("expected 8 bytes".to_string()))?;
+//!     Ok(f64::from_be_bytes(bytes))
+//! });
+//!
+//! let mut driver = ManagerDriver::new(manager);
+//! driver.register("kitchen", None).unwrap();
+//!
+//! driver.assert_update("kitchen", &21.5f64.to_be_bytes(), 21.5);
+//! let err = driver.assert_update_fails("kitchen", &[0x01, 0x02]);
+//! assert!(matches!(err, DataSourceManagerError::InvalidData(_)));
+//! ```
+
+use std::{collections::HashMap, fmt::Debug};
+
+use rocket::tokio::runtime::{Builder, Runtime};
+use serde::Serialize;
+
+use crate::server::{capabilities, DataSourceManager, DataSourceManagerError, Result};
+
+/// Drives a [`DataSourceManager`] the way the server does, keeping track of which data sources are registered
+/// and the data they registered with. Validation methods are only called if the manager lists them in its
+/// [`capabilities`](DataSourceManager::capabilities), and ids are normalized before they reach the manager.
+///
+/// Calls the server would never make, like updating a data source that isn't registered, panic, as they point
+/// to a mistake in the test rather than in the manager.
+pub struct ManagerDriver<T> {
+    manager: Box<dyn DataSourceManager<T>>,
+    /// The data every registered data source registered with, keyed by its normalized id.
+    registered: HashMap<String, Option<Vec<u8>>>,
+    runtime: Runtime
+}
+
+impl<T: Send> ManagerDriver<T> {
+    pub fn new(manager: impl DataSourceManager<T> + 'static) -> ManagerDriver<T> {
+        ManagerDriver::from_boxed(Box::new(manager))
+    }
+
+    /// Creates a driver for a manager that's already boxed, like one returned by a plugin's create function.
+    pub fn from_boxed(manager: Box<dyn DataSourceManager<T>>) -> ManagerDriver<T> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime managers are driven on");
+
+        ManagerDriver {
+            manager,
+            registered: HashMap::new(),
+            runtime
+        }
+    }
+
+    /// The manager being driven, for calling methods the driver doesn't cover.
+    pub fn manager(&self) -> &dyn DataSourceManager<T> {
+        self.manager.as_ref()
+    }
+
+    fn supports(&self, capability: u32) -> bool {
+        self.manager.capabilities() & capability != 0
+    }
+
+    /// Tells the manager about the error `result` failed with, if it failed and the manager observes its errors,
+    /// passing `result` on either way.
+    fn observe<R>(&self, id: &str, result: Result<R>) -> Result<R> {
+        if let Err(err) = &result {
+            if self.supports(capabilities::ON_ERROR) {
+                self.runtime.block_on(self.manager.on_error(id, err));
+            }
+        }
+        result
+    }
+
+    /// Returns the normalized form of `id`, panicking if it isn't registered.
+    fn registered_id(&self, id: &str) -> String {
+        let id = self.manager.normalize_id(id);
+        assert!(self.registered.contains_key(&id), "data source ({}) isn't registered", id);
+        id
+    }
+
+    /// Registers the data source `id`, with `data` if given. The data source is only considered registered if
+    /// the manager accepts it.
+    pub fn register(&mut self, id: &str, data: Option<&[u8]>) -> Result<()> {
+        let id = self.manager.normalize_id(id);
+        assert!(!self.registered.contains_key(&id), "data source ({}) is already registered", id);
+
+        if self.supports(capabilities::VALIDATE_REGISTRATION) {
+            self.runtime.block_on(self.manager.validate_registration(&id, data))?;
+        }
+        self.observe(&id, self.runtime.block_on(self.manager.register_in_namespace("", id.clone(), data)))?;
+
+        self.registered.insert(id, data.map(<[u8]>::to_vec));
+        Ok(())
+    }
+
+    /// Asks the manager for the value the data source `id` starts out with, along with the data it registered
+    /// with, the way the server does right after registering it. Managers that don't produce initial values
+    /// aren't asked, and return `None`.
+    pub fn initial_value(&self, id: &str) -> Result<Option<T>> {
+        let id = self.registered_id(id);
+        if !self.supports(capabilities::INITIAL_VALUE) {
+            return Ok(None);
+        }
+
+        let registration_data = self.registered[&id].as_deref();
+        self.observe(&id, self.runtime.block_on(self.manager.initial_value(&id, registration_data)))
+    }
+
+    /// Serializes `value` the way the server does before sending it to clients. Managers that don't serialize
+    /// their values themselves aren't asked, and return `None`.
+    pub fn serialize_value(&self, value: &T) -> Result<Option<serde_json::Value>>
+    where
+        T: Serialize
+    {
+        if !self.supports(capabilities::SERIALIZE_VALUE) {
+            return Ok(None);
+        }
+
+        self.manager.serialize_value(value).map(Some)
+    }
+
+    /// Deregisters the data source `id`, with `data` if given. The data source stays registered if the manager
+    /// fails to deregister it.
+    pub fn deregister(&mut self, id: &str, data: Option<&[u8]>) -> Result<()> {
+        let id = self.registered_id(id);
+        self.observe(&id, self.runtime.block_on(self.manager.deregister_in_namespace("", &id, data)))?;
+
+        self.registered.remove(&id);
+        Ok(())
+    }
+
+    /// Passes `data` to the manager as an update from the data source `id`, along with the data it registered
+    /// with, returning the value the manager parsed from it.
+    pub fn update(&self, id: &str, data: &[u8]) -> Result<T> {
+        self.update_traced(id, data, None)
+    }
+
+    /// Passes `data` to the manager as an update from the data source `id`, as if it was sent with the
+    /// `traceparent` header `trace_id`. Only managers that support tracing are given the header.
+    pub fn update_traced(&self, id: &str, data: &[u8], trace_id: Option<&str>) -> Result<T> {
+        let id = self.registered_id(id);
+        if self.supports(capabilities::VALIDATE_DATA) {
+            self.runtime.block_on(self.manager.validate_data(&id, data))?;
+        }
+
+        let registration_data = self.registered[&id].as_deref();
+        let update = if self.supports(capabilities::TRACING) {
+            self.runtime.block_on(self.manager.update_data_traced("", &id, data, registration_data, trace_id))
+        }
+        else {
+            self.runtime.block_on(self.manager.update_data_in_namespace("", &id, data, registration_data))
+        };
+        self.observe(&id, update).map(|data| data.value)
+    }
+
+    /// Streams `data` to the manager as an update from the data source `id`, the way the server does for
+    /// managers that support streaming, returning the value the manager parsed from it. Managers that don't
+    /// support streaming, or that validate data, are given the whole update instead.
+    pub fn update_stream(&self, id: &str, mut data: &[u8]) -> Result<T> {
+        if !self.supports(capabilities::STREAM) || self.supports(capabilities::VALIDATE_DATA) {
+            return self.update(id, data);
+        }
+
+        let id = self.registered_id(id);
+        let registration_data = self.registered[&id].as_deref();
+        let update = self.runtime.block_on(self.manager.update_data_stream("", &id, &mut data, registration_data));
+        self.observe(&id, update).map(|data| data.value)
+    }
+
+    /// Passes `data` to the manager as a batch of updates from the data source `id`, returning the values the
+    /// manager parsed from it.
+    pub fn batch_update(&self, id: &str, data: &[u8]) -> Result<Vec<T>> {
+        let id = self.registered_id(id);
+        if self.supports(capabilities::VALIDATE_DATA) {
+            self.runtime.block_on(self.manager.validate_data(&id, data))?;
+        }
+
+        self.observe(&id, self.runtime.block_on(self.manager.batch_update_data(&id, data)))
+    }
+
+    /// Asks the manager to reset the data source `id`, if the manager supports resetting.
+    pub fn reset(&self, id: &str) -> Result<()> {
+        let id = self.registered_id(id);
+        if !self.supports(capabilities::RESET) {
+            return Ok(());
+        }
+
+        self.runtime.block_on(self.manager.reset(&id))
+    }
+
+    /// Asks the manager to combine the latest values of several data sources, paired with their ids, into a
+    /// single value, failing with [`DataSourceManagerError::Unsupported`] if the manager doesn't support it.
+    pub fn aggregate_sources(&self, values: &[(String, T)]) -> Result<T>
+    where
+        T: Sync
+    {
+        if !self.supports(capabilities::AGGREGATE_SOURCES) {
+            return Err(DataSourceManagerError::Unsupported("aggregating data sources".to_string()));
+        }
+
+        self.runtime.block_on(self.manager.aggregate_sources(values))
+    }
+
+    /// Asserts that the update `data` from the data source `id` is parsed to `expected`.
+    pub fn assert_update(&self, id: &str, data: &[u8], expected: T)
+    where
+        T: PartialEq + Debug
+    {
+        match self.update(id, data) {
+            Ok(value) => assert_eq!(value, expected, "update ({:?}) was parsed to the wrong value", data),
+            Err(err) => panic!("update ({:?}) was rejected: {}", data, err)
+        }
+    }
+
+    /// Asserts that the update `data` from the data source `id` is rejected, returning the error it was
+    /// rejected with.
+    pub fn assert_update_fails(&self, id: &str, data: &[u8]) -> DataSourceManagerError
+    where
+        T: Debug
+    {
+        match self.update(id, data) {
+            Ok(value) => panic!("update ({:?}) was accepted as {:?}", data, value),
+            Err(err) => err
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::async_trait;
+
+    use super::*;
+    use crate::server::TimestampedData;
+
+    /// A manager continuing traces, which parses updates into the length of the data its data source registered
+    /// with, or fails them if they weren't sent with a trace.
+    struct TracingManager;
+
+    #[async_trait]
+    impl DataSourceManager<u64> for TracingManager {
+        fn manager_id(&self) -> &'static str {
+            "tracing"
+        }
+
+        fn capabilities(&self) -> u32 {
+            capabilities::TRACING
+        }
+
+        async fn register(&self, _id: String) -> Result<()> {
+            Ok(())
+        }
+
+        async fn register_with_data(&self, _id: String, _data: &[u8]) -> Result<()> {
+            Ok(())
+        }
+
+        async fn deregister(&self, _id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn deregister_with_data(&self, _id: &str, _data: &[u8]) -> Result<()> {
+            Ok(())
+        }
+
+        async fn update_data(&self, _id: &str, _data: &[u8]) -> Result<u64> {
+            Err(DataSourceManagerError::InvalidData("expected a traced update".to_string()))
+        }
+
+        async fn update_data_traced(
+            &self,
+            _namespace: &str,
+            _id: &str,
+            _data: &[u8],
+            registration_data: Option<&[u8]>,
+            trace_id: Option<&str>
+        ) -> Result<TimestampedData<u64>> {
+            trace_id.ok_or(DataSourceManagerError::InvalidData("expected a trace".to_string()))?;
+            Ok(TimestampedData {
+                value: registration_data.map_or(0, <[u8]>::len) as u64,
+                measured_at: None
+            })
+        }
+    }
+
+    #[test]
+    fn traced_updates_are_given_the_registration_data() {
+        let mut driver = ManagerDriver::new(TracingManager);
+        driver.register("kitchen", Some(b"calibration")).unwrap();
+
+        let trace_id = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        assert_eq!(driver.update_traced("kitchen", &[], Some(trace_id)).unwrap(), 11);
+        assert!(driver.update_traced("kitchen", &[], None).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use std::path::Path;
+
+use rocket::{post, State};
+
+use crate::{
+    FlorustState,
+    auth::WriteAccess,
+    data_source::{DataSourceError, OkResponder, state_op_to_responder},
+    manager_and_data::ReloadReport
+};
+
+#[post("/plugins/reload?<path>")]
+pub async fn reload_plugin(
+    _access: WriteAccess,
+    state: &State<FlorustState>,
+    path: String
+) -> Result<OkResponder<Vec<ReloadReport>>, DataSourceError> {
+    state_op_to_responder(state.reload_plugin(Path::new(&path)).await)
+}
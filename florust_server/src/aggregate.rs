@@ -0,0 +1,243 @@
+use std::num::NonZeroUsize;
+
+use chrono::Duration;
+use rocket::{FromForm, FromFormField};
+
+use crate::manager_and_data::{self, DataPoint, DataType, ManagerAndDataError};
+
+/// A function that summarizes the values retained for a data source into a single value.
+#[derive(FromFormField, Clone, Copy)]
+pub enum Aggregation {
+    Min,
+    Max,
+    Mean,
+    Sum,
+    Count,
+    Last
+}
+
+/// How [`downsample`] picks the data points that represent the values retained for a data source.
+#[derive(FromFormField, Clone, Copy, Default)]
+pub enum Downsampling {
+    /// Largest-triangle-three-buckets, which keeps the data points that best preserve the shape of the values,
+    /// like their peaks and dips.
+    #[default]
+    Lttb,
+    /// Replaces every run of consecutive data points with their mean, measured at the mean of their times.
+    Mean
+}
+
+/// A request to represent the values retained for a data source by at most `max_points` data points.
+#[derive(Clone, Copy)]
+pub struct Downsample {
+    pub max_points: NonZeroUsize,
+    pub method: Downsampling
+}
+
+/// The query of an aggregation request, like `?fn=mean`.
+#[derive(FromForm)]
+pub struct AggregationQuery {
+    #[field(name = "fn")]
+    pub function: Aggregation
+}
+
+impl Aggregation {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Mean => "mean",
+            Self::Sum => "sum",
+            Self::Count => "count",
+            Self::Last => "last"
+        }
+    }
+}
+
+/// Summarizes `data_points` using `aggregation`, or returns `None` if there's nothing to summarize. `min` and
+/// `max` return a value of the same type as the data points, while `mean` and `sum` always return a float. Only
+/// `count` and `last` can be used on values that aren't numeric.
+pub fn aggregate(aggregation: Aggregation, data_points: &[DataPoint<DataType>]) -> manager_and_data::Result<Option<DataType>> {
+    match aggregation {
+        Aggregation::Count => return Ok(Some(DataType::UInteger(data_points.len() as u64))),
+        Aggregation::Last => return Ok(data_points.last().map(|data_point| data_point.value.clone())),
+        _ => ()
+    }
+
+    let mut values = Vec::with_capacity(data_points.len());
+    for data_point in data_points {
+        let Some(number) = data_point.value.as_f64() else {
+            return Err(ManagerAndDataError::UnsupportedAggregation(aggregation.name().to_string()));
+        };
+        values.push((number, &data_point.value));
+    }
+
+    let aggregated = match aggregation {
+        Aggregation::Min => values.iter()
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, value)| (*value).clone()),
+        Aggregation::Max => values.iter()
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, value)| (*value).clone()),
+        Aggregation::Sum => Some(DataType::Float(values.iter().map(|(number, _)| number).sum())),
+        Aggregation::Mean => (!values.is_empty()).then(|| {
+            DataType::Float(values.iter().map(|(number, _)| number).sum::<f64>() / values.len() as f64)
+        }),
+        Aggregation::Count | Aggregation::Last => unreachable!("handled above")
+    };
+
+    Ok(aggregated)
+}
+
+/// Computes how fast the values of a counter-like data source change per second, from the most recent of
+/// `data_points` and the most recent one before it that was measured at a different time, as the values of a
+/// batch are all measured at once. The rate is returned along with when the most recent value was measured.
+/// Fails unless both values are numeric, and there are at least two values measured at different times.
+pub fn rate(data_points: &[DataPoint<DataType>]) -> manager_and_data::Result<DataPoint<f64>> {
+    let Some((latest, earlier)) = data_points.split_last().filter(|(_, earlier)| !earlier.is_empty()) else {
+        return Err(ManagerAndDataError::RateUndefined("fewer than two values are logged".to_string()));
+    };
+    let Some(previous) = earlier.iter().rev().find(|data_point| data_point.timestamp != latest.timestamp) else {
+        return Err(ManagerAndDataError::RateUndefined("every value logged was measured at the same time".to_string()));
+    };
+    let (Some(previous_value), Some(latest_value)) = (previous.value.as_f64(), latest.value.as_f64()) else {
+        return Err(ManagerAndDataError::UnsupportedAggregation("rate".to_string()));
+    };
+
+    Ok(DataPoint {
+        value: (latest_value - previous_value) / seconds(latest.timestamp - previous.timestamp),
+        timestamp: latest.timestamp
+    })
+}
+
+/// Reduces `data_points` to at most `downsample.max_points` data points representing them, ordered by when they
+/// were measured, so long histories can be charted without sending every data point. Histories that are already
+/// short enough are returned as they are. Fails unless every value is numeric.
+pub fn downsample(mut data_points: Vec<DataPoint<DataType>>, downsample: Downsample) -> manager_and_data::Result<Vec<DataPoint<DataType>>> {
+    data_points.sort_by_key(|data_point| data_point.timestamp);
+    let Some(values) = data_points.iter().map(|data_point| data_point.value.as_f64()).collect::<Option<Vec<_>>>() else {
+        return Err(ManagerAndDataError::UnsupportedAggregation("downsample".to_string()));
+    };
+
+    let max_points = downsample.max_points.get();
+    if data_points.len() <= max_points {
+        return Ok(data_points);
+    }
+
+    Ok(match downsample.method {
+        Downsampling::Lttb => largest_triangle_three_buckets(data_points, &values, max_points),
+        Downsampling::Mean => bucket_means(&data_points, &values, max_points)
+    })
+}
+
+/// Splits `data_points`, which must be more than `buckets`, into `buckets` runs of consecutive data points of
+/// about the same length, and replaces each run with the mean of its `values`, measured at the mean of its times.
+fn bucket_means(data_points: &[DataPoint<DataType>], values: &[f64], buckets: usize) -> Vec<DataPoint<DataType>> {
+    let first = data_points[0].timestamp;
+    let len = data_points.len();
+
+    (0..buckets)
+        .map(|bucket| {
+            let range = bucket * len / buckets..(bucket + 1) * len / buckets;
+            let count = range.len() as f64;
+            let mean = values[range.clone()].iter().sum::<f64>() / count;
+            let offset = data_points[range].iter().map(|data_point| seconds(data_point.timestamp - first)).sum::<f64>() / count;
+
+            DataPoint {
+                value: DataType::Float(mean),
+                timestamp: first + Duration::nanoseconds((offset * 1e9) as i64)
+            }
+        })
+        .collect()
+}
+
+/// Picks `threshold` of `data_points`, which must be more than `threshold`, using largest-triangle-three-buckets.
+/// The first and last data points are always kept, and the data points in between are split into buckets, each
+/// keeping the data point that forms the largest triangle with the one kept before it and the mean of the
+/// bucket after it.
+fn largest_triangle_three_buckets(data_points: Vec<DataPoint<DataType>>, values: &[f64], threshold: usize) -> Vec<DataPoint<DataType>> {
+    let len = data_points.len();
+    let mut kept = Vec::with_capacity(threshold);
+    match threshold {
+        1 => kept.push(len - 1),
+        2 => kept.extend([0, len - 1]),
+        _ => {
+            let first = data_points[0].timestamp;
+            let times: Vec<f64> = data_points.iter().map(|data_point| seconds(data_point.timestamp - first)).collect();
+            let buckets = threshold - 2;
+            let bucket_size = (len - 2) as f64 / buckets as f64;
+            // The first and last data points aren't part of any bucket, the last one stands in for the bucket
+            // after the last bucket.
+            let bucket_start = |bucket: usize| if bucket == buckets { len - 1 } else { (bucket as f64 * bucket_size) as usize + 1 };
+
+            kept.push(0);
+            let mut previous = 0;
+            for bucket in 0..buckets {
+                let next_end = if bucket + 1 == buckets { len } else { bucket_start(bucket + 2) };
+                let next = bucket_start(bucket + 1)..next_end;
+                let next_len = next.len() as f64;
+                let next_time = times[next.clone()].iter().sum::<f64>() / next_len;
+                let next_value = values[next].iter().sum::<f64>() / next_len;
+
+                let area = |index: usize| {
+                    ((times[previous] - next_time) * (values[index] - values[previous])
+                        - (times[previous] - times[index]) * (next_value - values[previous])).abs()
+                };
+                let largest = (bucket_start(bucket)..bucket_start(bucket + 1))
+                    .max_by(|a, b| area(*a).total_cmp(&area(*b)))
+                    .unwrap_or(bucket_start(bucket));
+
+                kept.push(largest);
+                previous = largest;
+            }
+            kept.push(len - 1);
+        }
+    }
+
+    let mut kept = kept.into_iter().peekable();
+    data_points
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, data_point)| kept.next_if_eq(&index).map(|_| data_point))
+        .collect()
+}
+
+/// The length of `duration` in seconds, with nanosecond precision for durations short enough to have it.
+fn seconds(duration: Duration) -> f64 {
+    duration
+        .num_nanoseconds()
+        .map_or_else(|| duration.num_milliseconds() as f64 / 1e3, |nanos| nanos as f64 / 1e9)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+
+    fn data_point(value: f64, timestamp: DateTime<Utc>) -> DataPoint<DataType> {
+        DataPoint {
+            value: DataType::Float(value),
+            timestamp
+        }
+    }
+
+    #[test]
+    fn rates_skip_values_of_the_same_batch() {
+        let start = Utc::now();
+        let batch = start + Duration::seconds(2);
+        let data_points = [
+            data_point(1.0, start),
+            data_point(2.0, batch),
+            data_point(3.0, batch),
+            data_point(5.0, batch)
+        ];
+
+        let computed = rate(&data_points).unwrap();
+        assert_eq!(computed.value, 2.0);
+        assert_eq!(computed.timestamp, batch);
+
+        assert!(matches!(rate(&data_points[1..]), Err(ManagerAndDataError::RateUndefined(_))));
+        assert!(matches!(rate(&data_points[..1]), Err(ManagerAndDataError::RateUndefined(_))));
+    }
+}
@@ -0,0 +1,172 @@
+use std::{collections::{HashMap, HashSet}, sync::Arc, time::Duration};
+
+use tracing::warn;
+use rocket::{async_trait, serde::{Serialize, Deserialize}, tokio::{self, sync::Mutex}};
+use thiserror::Error;
+
+use crate::{config::AlertConfig, manager_and_data::{DataType, NamespacedId}};
+
+/// How long the webhook alerter waits for a response before giving up on an alert.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A range the values of a single data source are expected to stay within. Either bound may be omitted.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct AlertRule {
+    #[serde(default)]
+    pub namespace: String,
+    pub source: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>
+}
+
+impl AlertRule {
+    fn applies_to(&self, source: &NamespacedId) -> bool {
+        self.namespace == source.namespace && self.source == source.id
+    }
+
+    /// Whether `value` lies outside of the range of this rule.
+    fn is_violated_by(&self, value: f64) -> bool {
+        self.min.is_some_and(|min| value < min) || self.max.is_some_and(|max| value > max)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AlertError {
+    #[error("Failed to build alerter: {0}")]
+    Build(String)
+}
+
+/// A trait defining something that is notified whenever a data source's value crosses the threshold of one
+/// of its [`AlertRule`]s.
+#[async_trait]
+pub trait Alerter: Send + Sync {
+    /// Called when `value`, logged by the data source with the id `source_id` of the manager with the id
+    /// `manager_id`, moved outside of the range of `rule`. Data sources outside of the empty namespace are
+    /// identified as `<namespace>/<id>`.
+    async fn on_alert(&self, manager_id: &str, source_id: &str, value: f64, rule: &AlertRule);
+}
+
+/// The body sent by the [`WebhookAlerter`] for every alert.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct WebhookAlert<'a> {
+    manager_id: &'a str,
+    source_id: &'a str,
+    value: f64,
+    rule: &'a AlertRule
+}
+
+/// An [`Alerter`] that POSTs every alert as JSON to a configured URL.
+pub struct WebhookAlerter {
+    client: reqwest::Client,
+    url: String
+}
+
+impl WebhookAlerter {
+    pub fn new(url: String) -> Result<WebhookAlerter, AlertError> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .map_err(|err| AlertError::Build(err.to_string()))?;
+
+        Ok(WebhookAlerter { client, url })
+    }
+}
+
+#[async_trait]
+impl Alerter for WebhookAlerter {
+    async fn on_alert(&self, manager_id: &str, source_id: &str, value: f64, rule: &AlertRule) {
+        let alert = WebhookAlert { manager_id, source_id, value, rule };
+
+        let result = self.client
+            .post(&self.url)
+            .json(&alert)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        if let Err(err) = result {
+            warn!("Failed to send alert to webhook ({}): {}", self.url, err);
+        }
+    }
+}
+
+/// Evaluates the alert rules of every manager against newly logged values, notifying the registered
+/// alerters when a value crosses a threshold.
+pub struct Alerts {
+    /// Alert rules, keyed by the id of the manager they apply to.
+    rules: HashMap<String, Vec<AlertRule>>,
+    alerters: Vec<Arc<dyn Alerter>>,
+    /// Data sources whose latest value violates one of their rules, as pairs of manager and data source id.
+    /// Alerts are only fired when a data source enters this set, not for every value while it's in it.
+    violating: Mutex<HashSet<(String, NamespacedId)>>
+}
+
+impl Alerts {
+    /// Creates the alert rules from `config`, registering a [`WebhookAlerter`] if a webhook is configured.
+    pub fn from_config(config: &AlertConfig) -> Alerts {
+        let mut alerts = Alerts {
+            rules: config.rules().clone(),
+            alerters: Vec::new(),
+            violating: Mutex::new(HashSet::new())
+        };
+
+        if let Some(url) = config.webhook_url() {
+            match WebhookAlerter::new(url.to_string()) {
+                Ok(alerter) => alerts.add_alerter(Arc::new(alerter)),
+                Err(err) => warn!("Webhook alerter ({}) couldn't be created, alerts won't be sent to it: {}", url, err)
+            }
+        }
+
+        alerts
+    }
+
+    pub fn add_alerter(&mut self, alerter: Arc<dyn Alerter>) {
+        self.alerters.push(alerter);
+    }
+
+    /// Evaluates every rule that applies to the data source `source` against `value`. Values that aren't
+    /// numeric are never alerted on.
+    pub async fn evaluate(&self, manager_id: &str, source: &NamespacedId, value: &DataType) {
+        if self.alerters.is_empty() {
+            return;
+        }
+
+        let Some(rules) = self.rules.get(manager_id) else {
+            return;
+        };
+
+        let Some(value) = value.as_f64() else {
+            return;
+        };
+
+        let violated = rules
+            .iter()
+            .filter(|rule| rule.applies_to(source))
+            .find(|rule| rule.is_violated_by(value));
+
+        let key = (manager_id.to_string(), source.clone());
+        let mut violating = self.violating.lock().await;
+        let Some(rule) = violated else {
+            violating.remove(&key);
+            return;
+        };
+
+        if !violating.insert(key) {
+            return;
+        }
+        drop(violating);
+
+        // Alerters may be slow, so they're notified in the background rather than holding up the update.
+        for alerter in &self.alerters {
+            let alerter = alerter.clone();
+            let manager_id = manager_id.to_string();
+            let source_id = source.to_string();
+            let rule = rule.clone();
+            tokio::spawn(async move {
+                alerter.on_alert(&manager_id, &source_id, value, &rule).await;
+            });
+        }
+    }
+}
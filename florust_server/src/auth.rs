@@ -0,0 +1,120 @@
+use std::{collections::HashSet, sync::{Arc, RwLock}};
+
+use rocket::{Request, async_trait, catch, http::Status, request::{FromRequest, Outcome}};
+
+use crate::{FlorustState, config::AuthConfig};
+
+/// The API keys requests are authorized with. Reloaded whenever the server's config changes.
+pub struct ApiKeys {
+    write: HashSet<String>,
+    read: HashSet<String>
+}
+
+/// API keys shared between the server and the config watcher, which replaces them when the config changes.
+pub type SharedApiKeys = Arc<RwLock<ApiKeys>>;
+
+impl ApiKeys {
+    pub fn from_config(config: &AuthConfig) -> ApiKeys {
+        ApiKeys {
+            write: config.write_keys().iter().cloned().collect(),
+            read: config.read_keys().iter().cloned().collect()
+        }
+    }
+
+    /// Whether `key` may be used for requests that change anything. Every request may if no write keys are
+    /// configured.
+    fn allows_write(&self, key: Option<&str>) -> bool {
+        self.write.is_empty() || key.is_some_and(|key| self.write.contains(key))
+    }
+
+    /// Whether `key` may be used for requests that only read data. Every request may if no read keys are
+    /// configured, and write keys can always be used to read.
+    fn allows_read(&self, key: Option<&str>) -> bool {
+        self.read.is_empty()
+            || key.is_some_and(|key| self.read.contains(key) || self.write.contains(key))
+    }
+}
+
+/// Returns the bearer token sent in the `Authorization` header of `request`, if there is one.
+fn bearer_token<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    request.headers()
+        .get_one("Authorization")?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn authorize<T>(request: &Request<'_>, guard: T, allows: fn(&ApiKeys, Option<&str>) -> bool) -> Outcome<T, ()> {
+    let Some(state) = request.rocket().state::<FlorustState>() else {
+        return Outcome::Error((Status::InternalServerError, ()));
+    };
+
+    let api_keys = state.api_keys.read().unwrap_or_else(|err| err.into_inner());
+    if allows(&api_keys, bearer_token(request)) {
+        Outcome::Success(guard)
+    }
+    else {
+        Outcome::Error((Status::Unauthorized, ()))
+    }
+}
+
+/// A request guard that only succeeds for requests authorized with a write key, for endpoints that change
+/// anything.
+pub struct WriteAccess;
+
+#[async_trait]
+impl<'r> FromRequest<'r> for WriteAccess {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        authorize(request, WriteAccess, ApiKeys::allows_write)
+    }
+}
+
+/// A request guard that only succeeds for requests authorized with a read or write key, for endpoints that
+/// only read data.
+pub struct ReadAccess;
+
+#[async_trait]
+impl<'r> FromRequest<'r> for ReadAccess {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        authorize(request, ReadAccess, ApiKeys::allows_read)
+    }
+}
+
+/// Responds to unauthorized requests without a body, so nothing is revealed to unauthorized clients.
+#[catch(401)]
+pub fn unauthorized() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_keys(config: &str) -> ApiKeys {
+        ApiKeys::from_config(&toml::from_str(config).unwrap())
+    }
+
+    #[test]
+    fn read_keys_only_allow_reading() {
+        let api_keys = api_keys(r#"
+            write_keys = ["write"]
+            read_keys = ["read"]
+        "#);
+
+        assert!(api_keys.allows_read(Some("read")));
+        assert!(api_keys.allows_read(Some("write")));
+        assert!(!api_keys.allows_read(Some("other")));
+        assert!(!api_keys.allows_read(None));
+
+        assert!(api_keys.allows_write(Some("write")));
+        assert!(!api_keys.allows_write(Some("read")));
+    }
+
+    #[test]
+    fn every_request_is_allowed_without_keys() {
+        let api_keys = api_keys("");
+        assert!(api_keys.allows_read(None));
+        assert!(api_keys.allows_write(None));
+    }
+}
@@ -1,32 +1,42 @@
 pub struct CircularVec<T> {
     vec: Vec<T>,
     start: usize,
-    end: usize,
     max_size: usize,
 }
 
 impl<T> CircularVec<T> {
-    pub fn new(max_size: usize, default: T) -> CircularVec<T>
-    where
-        T: Clone,
-    {
+    pub fn new(max_size: usize) -> CircularVec<T> {
         CircularVec {
-            vec: vec![default; max_size],
+            vec: Vec::with_capacity(max_size),
             start: 0,
-            end: 0,
             max_size
         }
     }
 
+    /// Appends `val`, overwriting the oldest value if the vec is full.
     pub fn append(&mut self, val: T) {
-        self.vec[self.end] = val;
-        self.increment_range();
+        if self.max_size == 0 {
+            return;
+        }
+
+        if self.vec.len() < self.max_size {
+            self.vec.push(val);
+        }
+        else {
+            self.vec[self.start] = val;
+            self.increment_start();
+        }
     }
 
+    /// Returns the value at `index`, where index 0 is the oldest value.
     pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.vec.len() {
+            return None;
+        }
+
         let index = self.start + index;
-        let index = if index >= self.max_size {
-            index - self.max_size
+        let index = if index >= self.vec.len() {
+            index - self.vec.len()
         }
         else {
             index
@@ -35,19 +45,25 @@ impl<T> CircularVec<T> {
         self.vec.get(index)
     }
 
-    fn increment_range(&mut self) {
-        self.end += 1;
+    /// Returns the most recently appended value.
+    pub fn latest(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
 
-        if self.end == self.max_size {
-            self.end = 0;
-        }
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// The most values the vec holds at once, values appended to a vec that holds none are dropped.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
 
-        if self.end == self.start {
-            self.start += 1;
+    fn increment_start(&mut self) {
+        self.start += 1;
 
-            if self.start == self.max_size {
-                self.start = 0;
-            }
+        if self.start == self.max_size {
+            self.start = 0;
         }
     }
 }
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use rocket::{Request, async_trait, http::Status, request::{FromRequest, Outcome}};
+
+use crate::{FlorustState, config::{ClientAuthConfig, ClientBinding}, manager_and_data::{self, ManagerAndDataError, NamespacedId}};
+
+/// The identity of the client that made a request, taken from the common name of the TLS client certificate
+/// it presented. Requests without a valid certificate are rejected with `401 Unauthorized` if client
+/// certificates are required, and have no identity otherwise.
+pub struct ClientIdentity(pub Option<String>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for ClientIdentity {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let required = request.rocket()
+            .state::<FlorustState>()
+            .is_some_and(|state| state.client_auth.required);
+
+        match common_name(request).await {
+            Some(common_name) => Outcome::Success(ClientIdentity(Some(common_name))),
+            None if required => Outcome::Error((Status::Unauthorized, ())),
+            None => Outcome::Success(ClientIdentity(None))
+        }
+    }
+}
+
+#[cfg(feature = "client_cert_auth")]
+async fn common_name(request: &Request<'_>) -> Option<String> {
+    let certificate = request.guard::<rocket::mtls::Certificate<'_>>().await.succeeded()?;
+    certificate.subject().common_name().map(str::to_string)
+}
+
+#[cfg(not(feature = "client_cert_auth"))]
+async fn common_name(_request: &Request<'_>) -> Option<String> {
+    None
+}
+
+/// Decides which clients may act on behalf of which data sources.
+pub struct ClientAuth {
+    required: bool,
+    /// The common name of the only certificate each bound data source may be used with, keyed by data source.
+    bindings: HashMap<String, String>,
+    /// Bindings that only apply to the data sources of a single manager, keyed by manager and then by data
+    /// source. These take precedence over `bindings`, as data sources of different managers may share an id.
+    manager_bindings: HashMap<String, HashMap<String, String>>
+}
+
+impl ClientAuth {
+    pub fn from_config(config: &ClientAuthConfig) -> ClientAuth {
+        #[cfg(not(feature = "client_cert_auth"))]
+        if config.required() {
+            tracing::warn!("Client certificates are required, but the server was built without the client_cert_auth feature, every data source will be rejected");
+        }
+
+        let mut bindings = HashMap::new();
+        let mut manager_bindings = HashMap::new();
+        for (key, binding) in config.bindings() {
+            match binding {
+                ClientBinding::CommonName(common_name) => {
+                    bindings.insert(key.clone(), common_name.clone());
+                },
+                ClientBinding::Manager(sources) => {
+                    manager_bindings.insert(key.clone(), sources.clone());
+                }
+            }
+        }
+
+        ClientAuth {
+            required: config.required(),
+            bindings,
+            manager_bindings
+        }
+    }
+
+    /// Checks that the client identified by `identity` may act on behalf of the data source `source` of the
+    /// manager with the id `manager_id`. Data sources that aren't bound to a certificate may be used by every
+    /// client.
+    pub fn check(&self, manager_id: &str, identity: &ClientIdentity, source: &NamespacedId) -> manager_and_data::Result<()> {
+        let source_id = source.to_string();
+        let binding = self.manager_bindings
+            .get(manager_id)
+            .and_then(|sources| sources.get(&source_id))
+            .or_else(|| self.bindings.get(&source_id));
+
+        match binding {
+            Some(common_name) if identity.0.as_ref() != Some(common_name) => Err(
+                ManagerAndDataError::Forbidden(source.to_string())
+            ),
+            _ => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_auth() -> ClientAuth {
+        let config: ClientAuthConfig = toml::from_str(r#"
+            [bindings]
+            kitchen = "kitchen-sensor"
+
+            [bindings.FlorustDefaultIIntegerDataManager]
+            kitchen = "kitchen-counter"
+        "#).unwrap();
+        ClientAuth::from_config(&config)
+    }
+
+    fn identity(common_name: &str) -> ClientIdentity {
+        ClientIdentity(Some(common_name.to_string()))
+    }
+
+    #[test]
+    fn sources_sharing_an_id_are_bound_per_manager() {
+        let client_auth = client_auth();
+        let kitchen = NamespacedId::new("", "kitchen");
+
+        assert!(client_auth.check("FlorustDefaultFloatDataManager", &identity("kitchen-sensor"), &kitchen).is_ok());
+        assert!(client_auth.check("FlorustDefaultIIntegerDataManager", &identity("kitchen-counter"), &kitchen).is_ok());
+
+        assert!(matches!(
+            client_auth.check("FlorustDefaultIIntegerDataManager", &identity("kitchen-sensor"), &kitchen),
+            Err(ManagerAndDataError::Forbidden(_))
+        ));
+        assert!(matches!(
+            client_auth.check("FlorustDefaultFloatDataManager", &identity("kitchen-counter"), &kitchen),
+            Err(ManagerAndDataError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn unbound_sources_may_be_used_by_every_client() {
+        let client_auth = client_auth();
+        let garage = NamespacedId::new("", "garage");
+
+        assert!(client_auth.check("FlorustDefaultIIntegerDataManager", &identity("kitchen-counter"), &garage).is_ok());
+        assert!(client_auth.check("FlorustDefaultIIntegerDataManager", &ClientIdentity(None), &garage).is_ok());
+        assert!(client_auth.check("FlorustDefaultIIntegerDataManager", &ClientIdentity(None), &NamespacedId::new("", "kitchen")).is_err());
+    }
+}
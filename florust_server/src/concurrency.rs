@@ -0,0 +1,116 @@
+use std::{collections::HashMap, sync::atomic::{AtomicU64, AtomicUsize, Ordering}, time::{Duration, Instant}};
+
+use rocket::tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{manager_and_data::{self, ManagerAndDataError}, rate_limit::retry_after_secs};
+
+/// How many updates a single manager may parse at once, and how many more may wait for their turn.
+pub struct ConcurrencyLimit {
+    semaphore: Semaphore,
+    max_concurrent: usize,
+    max_queued: usize,
+    queued: AtomicUsize,
+    /// A moving average of how long updates take to parse, in nanoseconds, used to estimate how long rejected
+    /// updates should wait before they're retried.
+    average_hold: AtomicU64
+}
+
+impl ConcurrencyLimit {
+    /// Creates the limit of a manager that may parse `max_concurrent` updates at once, with up to `max_queued`
+    /// more waiting. Returns `None` if the manager isn't limited, which is the case if `max_concurrent` isn't
+    /// set or is zero.
+    pub fn new(max_concurrent: Option<usize>, max_queued: usize) -> Option<ConcurrencyLimit> {
+        let max_concurrent = max_concurrent.filter(|max| *max != 0)?;
+        Some(ConcurrencyLimit {
+            semaphore: Semaphore::new(max_concurrent),
+            max_concurrent,
+            max_queued,
+            queued: AtomicUsize::new(0),
+            average_hold: AtomicU64::new(0)
+        })
+    }
+
+    /// Adds how long an update took to parse to the moving average.
+    fn record_hold(&self, held: Duration) {
+        let held = u64::try_from(held.as_nanos()).unwrap_or(u64::MAX);
+        let _ = self.average_hold.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+            Some(if average == 0 { held } else { average - average / 8 + held / 8 })
+        });
+    }
+
+    /// Estimates how long it takes for every update that's parsing or waiting to be done, going by how long
+    /// updates usually take to parse.
+    fn estimated_wait(&self) -> Duration {
+        let waiting = self.queued.load(Ordering::Acquire) + self.max_concurrent;
+        Duration::from_nanos(self.average_hold.load(Ordering::Relaxed)).mul_f64(waiting as f64 / self.max_concurrent as f64)
+    }
+}
+
+/// Stops counting an update as queued once it's done waiting, including when its request is dropped while
+/// it's still waiting.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Permission to parse an update, which must be held for as long as the update is being parsed. Records how
+/// long it was held once it's dropped.
+pub struct Permit<'a> {
+    _permit: SemaphorePermit<'a>,
+    limit: &'a ConcurrencyLimit,
+    acquired: Instant
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.limit.record_hold(self.acquired.elapsed());
+    }
+}
+
+/// Limits how many updates each manager parses at once, so a slow manager can't tie up the server's runtime.
+/// Updates past the limit wait for an earlier update to finish, and are rejected once too many are waiting.
+pub struct ConcurrencyLimiter {
+    /// The limit of every manager that has one.
+    limits: HashMap<&'static str, ConcurrencyLimit>
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(limits: HashMap<&'static str, ConcurrencyLimit>) -> ConcurrencyLimiter {
+        ConcurrencyLimiter { limits }
+    }
+
+    /// Waits for the manager with the id `manager_id` to have room for another update, returning a permit that
+    /// must be held for as long as the update is being parsed. Returns `None` for managers without a limit.
+    pub async fn acquire(&self, manager_id: &str) -> manager_and_data::Result<Option<Permit<'_>>> {
+        let Some(limit) = self.limits.get(manager_id) else {
+            return Ok(None);
+        };
+
+        let permit = |permit| Some(Permit {
+            _permit: permit,
+            limit,
+            acquired: Instant::now()
+        });
+        let overloaded = || ManagerAndDataError::Overloaded {
+            manager_id: manager_id.to_string(),
+            retry_after: retry_after_secs(limit.estimated_wait())
+        };
+
+        if let Ok(acquired) = limit.semaphore.try_acquire() {
+            return Ok(permit(acquired));
+        }
+
+        let already_queued = limit.queued.fetch_add(1, Ordering::AcqRel);
+        let _queued = Queued(&limit.queued);
+        if already_queued >= limit.max_queued {
+            return Err(overloaded());
+        }
+
+        // The semaphore is never closed, so waiting on it can't fail.
+        let acquired = limit.semaphore.acquire().await.map_err(|_| overloaded())?;
+        Ok(permit(acquired))
+    }
+}
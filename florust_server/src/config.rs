@@ -0,0 +1,695 @@
+use std::{collections::HashMap, env, fs::read_to_string};
+
+use tracing::{info, warn};
+use rocket::serde::{Serialize, Deserialize};
+use toml::{Table, Value};
+
+use crate::{
+    alert::AlertRule,
+    idempotency::{DEFAULT_IDEMPOTENCY_MAX_KEYS, DEFAULT_IDEMPOTENCY_TTL},
+    large_integers::IntegerEncoding,
+    manager_and_data::DEFAULT_MANAGER_TIMEOUT,
+    non_finite::NonFiniteFloats,
+    write_buffer::DEFAULT_MAX_BUFFERED_ROWS,
+    upload::{DEFAULT_MAX_DECOMPRESSED_SIZE, DEFAULT_MAX_UPLOAD_SIZE}
+};
+
+/// Path of the server's config file, relative to the working directory of the server.
+pub const CONFIG_PATH: &str = "florust.toml";
+
+/// Prefix of the environment variables that override the configs of plugins.
+const ENV_PREFIX: &str = "FLORUST_";
+
+fn default_history_size() -> usize { 10 }
+
+fn default_mqtt_port() -> u16 { 1883 }
+
+fn default_mqtt_client_id() -> String { "florust".to_string() }
+
+fn default_mqtt_topic() -> String { "florust/{manager_id}/{source_id}".to_string() }
+
+fn default_plugins_dir() -> String { "plugins/".to_string() }
+
+fn default_manager_timeout() -> f64 { DEFAULT_MANAGER_TIMEOUT.as_secs_f64() }
+
+fn default_max_upload_size() -> usize { DEFAULT_MAX_UPLOAD_SIZE }
+
+fn default_max_decompressed_size() -> usize { DEFAULT_MAX_DECOMPRESSED_SIZE }
+
+fn default_idempotency_ttl() -> f64 { DEFAULT_IDEMPOTENCY_TTL.as_secs_f64() }
+
+fn default_idempotency_max_keys() -> usize { DEFAULT_IDEMPOTENCY_MAX_KEYS }
+
+fn default_max_buffered_rows() -> usize { DEFAULT_MAX_BUFFERED_ROWS }
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StorageConfig {
+    sqlite_path: Option<String>,
+    #[serde(default)]
+    flush_interval: f64,
+    #[serde(default = "default_max_buffered_rows")]
+    max_buffered_rows: usize
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            sqlite_path: None,
+            flush_interval: 0.0,
+            max_buffered_rows: default_max_buffered_rows()
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Path of the SQLite database values are persisted to, if persistence is enabled.
+    pub fn sqlite_path(&self) -> Option<&str> {
+        self.sqlite_path.as_deref()
+    }
+
+    /// How long, in seconds, values may be buffered before they're written to storage. Values are written as
+    /// soon as they're logged if this is zero.
+    #[cfg_attr(not(feature = "sqlite_storage"), allow(dead_code))]
+    pub fn flush_interval(&self) -> f64 {
+        self.flush_interval
+    }
+
+    /// How many values may be buffered before they're written to storage, regardless of how long they've
+    /// been buffered for.
+    #[cfg_attr(not(feature = "sqlite_storage"), allow(dead_code))]
+    pub fn max_buffered_rows(&self) -> usize {
+        self.max_buffered_rows
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct UnixSocketConfig {
+    path: Option<String>
+}
+
+impl UnixSocketConfig {
+    /// Path of the Unix domain socket data sources can send data over, if the socket is enabled.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MqttConfig {
+    host: Option<String>,
+    #[serde(default = "default_mqtt_port")]
+    port: u16,
+    #[serde(default = "default_mqtt_client_id")]
+    client_id: String,
+    username: Option<String>,
+    password: Option<String>,
+    #[serde(default = "default_mqtt_topic")]
+    topic: String,
+    #[serde(default)]
+    qos: u8,
+    #[serde(default)]
+    retain: bool
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        MqttConfig {
+            host: None,
+            port: default_mqtt_port(),
+            client_id: default_mqtt_client_id(),
+            username: None,
+            password: None,
+            topic: default_mqtt_topic(),
+            qos: 0,
+            retain: false
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "mqtt_export"), allow(dead_code))]
+impl MqttConfig {
+    /// Host of the MQTT broker values are republished to, if republishing is enabled.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Port of the MQTT broker.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The client id the server connects to the broker with.
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// The username and password the server connects to the broker with, if the broker requires them.
+    pub fn credentials(&self) -> Option<(&str, &str)> {
+        Some((self.username.as_deref()?, self.password.as_deref().unwrap_or_default()))
+    }
+
+    /// Template of the topic every value is published to, where `{manager_id}`, `{namespace}`, and
+    /// `{source_id}` are replaced by the ids of the manager and data source that produced the value.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// The MQTT quality of service values are published with, from 0 to 2.
+    pub fn qos(&self) -> u8 {
+        self.qos
+    }
+
+    /// Whether the broker should retain the last value published to every topic.
+    pub fn retain(&self) -> bool {
+        self.retain
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthConfig {
+    #[serde(default)]
+    write_keys: Vec<String>,
+    #[serde(default)]
+    read_keys: Vec<String>
+}
+
+impl AuthConfig {
+    /// API keys that authorize requests that change anything, like registrations and updates.
+    pub fn write_keys(&self) -> &[String] {
+        &self.write_keys
+    }
+
+    /// API keys that authorize requests that only read data.
+    pub fn read_keys(&self) -> &[String] {
+        &self.read_keys
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct CorsPolicyConfig {
+    #[serde(default)]
+    allowed_origins: Vec<String>,
+    allowed_methods: Option<Vec<String>>,
+    #[serde(default)]
+    allowed_headers: Vec<String>,
+    max_age: Option<u64>
+}
+
+impl CorsPolicyConfig {
+    /// Origins browsers may make cross-origin requests from, or `*` for every origin.
+    pub fn allowed_origins(&self) -> &[String] {
+        &self.allowed_origins
+    }
+
+    /// Methods cross-origin requests may use, if they're restricted further than by the kind of endpoint.
+    pub fn allowed_methods(&self) -> Option<&[String]> {
+        self.allowed_methods.as_deref()
+    }
+
+    /// Headers cross-origin requests may be sent with, or `*` for every header.
+    pub fn allowed_headers(&self) -> &[String] {
+        &self.allowed_headers
+    }
+
+    /// Seconds browsers may cache the answer to a preflight request for.
+    pub fn max_age(&self) -> Option<u64> {
+        self.max_age
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct CorsConfig {
+    #[serde(flatten)]
+    read: CorsPolicyConfig,
+    #[serde(default)]
+    write: CorsPolicyConfig
+}
+
+impl CorsConfig {
+    /// The policy of endpoints that only read data.
+    pub fn read(&self) -> &CorsPolicyConfig {
+        &self.read
+    }
+
+    /// The policy of endpoints that change anything.
+    pub fn write(&self) -> &CorsPolicyConfig {
+        &self.write
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct ClientAuthConfig {
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    bindings: HashMap<String, ClientBinding>
+}
+
+/// What a key of the client auth `bindings` table binds: either a data source of every manager, or the data
+/// sources of a single manager.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde", untagged)]
+pub enum ClientBinding {
+    /// The common name of the certificate the data source must present, whichever manager it belongs to.
+    CommonName(String),
+    /// The common name of the certificate each data source of the manager must present, keyed by data source.
+    Manager(HashMap<String, String>)
+}
+
+impl ClientAuthConfig {
+    /// Whether data sources must present a TLS client certificate.
+    pub fn required(&self) -> bool {
+        self.required
+    }
+
+    /// The common name of the certificate each bound data source must present, keyed by data source, or by
+    /// manager for bindings that only apply to the data sources of a single manager.
+    pub fn bindings(&self) -> &HashMap<String, ClientBinding> {
+        &self.bindings
+    }
+}
+
+/// Which data sources may register with a manager, by glob patterns matching their ids.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct SourceFilterConfig {
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>
+}
+
+impl SourceFilterConfig {
+    /// Patterns matching the only data sources that may register, every data source may if there are none.
+    pub fn allow(&self) -> &[String] {
+        &self.allow
+    }
+
+    /// Patterns matching data sources that may never register, even if they're allowed to.
+    pub fn deny(&self) -> &[String] {
+        &self.deny
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct AlertConfig {
+    webhook_url: Option<String>,
+    #[serde(default)]
+    rules: HashMap<String, Vec<AlertRule>>
+}
+
+impl AlertConfig {
+    /// URL every alert is POSTed to, if alerts should be sent to a webhook.
+    pub fn webhook_url(&self) -> Option<&str> {
+        self.webhook_url.as_deref()
+    }
+
+    /// Alert rules, keyed by the id of the manager they apply to.
+    pub fn rules(&self) -> &HashMap<String, Vec<AlertRule>> {
+        &self.rules
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct FlorustServerConfig {
+    #[serde(default = "default_history_size")]
+    history_size: usize,
+    #[serde(default)]
+    raw_history_size: usize,
+    #[serde(default = "default_plugins_dir")]
+    plugins_dir: String,
+    max_updates_per_second: Option<f64>,
+    #[serde(default = "default_manager_timeout")]
+    manager_timeout: f64,
+    idle_timeout: Option<f64>,
+    #[serde(default = "default_max_upload_size")]
+    max_upload_size: usize,
+    max_concurrent_updates: Option<usize>,
+    #[serde(default)]
+    max_queued_updates: usize,
+    #[serde(default)]
+    auto_register: bool,
+    #[serde(default)]
+    strict_managers: bool,
+    #[serde(default = "default_max_decompressed_size")]
+    max_decompressed_size: usize,
+    #[serde(default = "default_idempotency_ttl")]
+    idempotency_ttl: f64,
+    #[serde(default = "default_idempotency_max_keys")]
+    idempotency_max_keys: usize,
+    #[serde(default)]
+    non_finite_floats: NonFiniteFloats,
+    #[serde(default)]
+    integer_encoding: IntegerEncoding,
+    #[serde(default)]
+    storage: StorageConfig,
+    #[serde(default)]
+    alerts: AlertConfig,
+    #[serde(default)]
+    auth: AuthConfig,
+    #[serde(default)]
+    client_auth: ClientAuthConfig,
+    #[serde(default)]
+    source_filters: HashMap<String, SourceFilterConfig>,
+    #[serde(default)]
+    cors: CorsConfig,
+    #[serde(default)]
+    unix_socket: UnixSocketConfig,
+    #[serde(default)]
+    mqtt: MqttConfig
+}
+
+impl Default for FlorustServerConfig {
+    fn default() -> Self {
+        FlorustServerConfig {
+            history_size: default_history_size(),
+            raw_history_size: 0,
+            plugins_dir: default_plugins_dir(),
+            max_updates_per_second: None,
+            manager_timeout: default_manager_timeout(),
+            idle_timeout: None,
+            max_upload_size: default_max_upload_size(),
+            max_concurrent_updates: None,
+            max_queued_updates: 0,
+            auto_register: false,
+            strict_managers: false,
+            max_decompressed_size: default_max_decompressed_size(),
+            idempotency_ttl: default_idempotency_ttl(),
+            idempotency_max_keys: default_idempotency_max_keys(),
+            non_finite_floats: NonFiniteFloats::default(),
+            integer_encoding: IntegerEncoding::default(),
+            storage: StorageConfig::default(),
+            alerts: AlertConfig::default(),
+            auth: AuthConfig::default(),
+            client_auth: ClientAuthConfig::default(),
+            source_filters: HashMap::new(),
+            cors: CorsConfig::default(),
+            unix_socket: UnixSocketConfig::default(),
+            mqtt: MqttConfig::default()
+        }
+    }
+}
+
+impl FlorustServerConfig {
+    /// Loads the server config from [`CONFIG_PATH`], falling back to the default config if the file doesn't
+    /// exist or can't be parsed.
+    pub fn load() -> FlorustServerConfig {
+        let config_file = match read_to_string(CONFIG_PATH) {
+            Ok(str) => str,
+            Err(_) => {
+                info!("Server config ({}) not found, using default config", CONFIG_PATH);
+                return FlorustServerConfig::default();
+            }
+        };
+
+        match toml::from_str(&config_file) {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("Server config ({}) couldn't be parsed, using default config: {}", CONFIG_PATH, err);
+                FlorustServerConfig::default()
+            }
+        }
+    }
+
+    /// The maximum number of values retained for each data source, unless overridden by a plugin's config.
+    pub fn history_size(&self) -> usize {
+        self.history_size
+    }
+
+    /// The maximum number of updates retained for each data source as they were sent, so they can be replayed,
+    /// unless overridden by a plugin's config. No updates are retained if this is zero.
+    pub fn raw_history_size(&self) -> usize {
+        self.raw_history_size
+    }
+
+    /// Path of the dir plugins are loaded from, relative to the working directory of the server.
+    pub fn plugins_dir(&self) -> &str {
+        &self.plugins_dir
+    }
+
+    /// The maximum number of updates each data source may send per second, unless overridden by a plugin's
+    /// config. Data sources aren't limited if this isn't set.
+    pub fn max_updates_per_second(&self) -> Option<f64> {
+        self.max_updates_per_second
+    }
+
+    /// How long, in seconds, calls into a manager may take before they're abandoned, unless overridden by a
+    /// plugin's config.
+    pub fn manager_timeout(&self) -> f64 {
+        self.manager_timeout
+    }
+
+    /// How long, in seconds, a data source may go without registering or logging an update before it's
+    /// deregistered, unless overridden by a plugin's config. Data sources are never deregistered for going idle
+    /// if this isn't set.
+    pub fn idle_timeout(&self) -> Option<f64> {
+        self.idle_timeout
+    }
+
+    /// The maximum number of bytes the body of an upload or registration may be, unless overridden by a
+    /// plugin's config.
+    pub fn max_upload_size(&self) -> usize {
+        self.max_upload_size
+    }
+
+    /// The maximum number of updates each manager may parse at once, unless overridden by a plugin's config.
+    /// Managers aren't limited if this isn't set.
+    pub fn max_concurrent_updates(&self) -> Option<usize> {
+        self.max_concurrent_updates
+    }
+
+    /// The maximum number of updates that may wait for a manager that's parsing `max_concurrent_updates`
+    /// updates already, unless overridden by a plugin's config.
+    pub fn max_queued_updates(&self) -> usize {
+        self.max_queued_updates
+    }
+
+    /// Whether updates from data sources that aren't registered register them, instead of being rejected, unless
+    /// overridden by a plugin's config.
+    pub fn auto_register(&self) -> bool {
+        self.auto_register
+    }
+
+    /// Whether requests for managers that aren't loaded are rejected as soon as they're routed, and logged.
+    pub fn strict_managers(&self) -> bool {
+        self.strict_managers
+    }
+
+    /// The maximum number of bytes a compressed upload may decompress to.
+    pub fn max_decompressed_size(&self) -> usize {
+        self.max_decompressed_size
+    }
+
+    /// How long, in seconds, the idempotency keys of updates are remembered for.
+    pub fn idempotency_ttl(&self) -> f64 {
+        self.idempotency_ttl
+    }
+
+    /// The maximum number of idempotency keys remembered at once, across every data source.
+    pub fn idempotency_max_keys(&self) -> usize {
+        self.idempotency_max_keys
+    }
+
+    /// How floats that aren't finite are encoded in JSON responses.
+    pub fn non_finite_floats(&self) -> NonFiniteFloats {
+        self.non_finite_floats
+    }
+
+    /// How 64-bit integers are encoded in JSON responses.
+    pub fn integer_encoding(&self) -> IntegerEncoding {
+        self.integer_encoding
+    }
+
+    pub fn storage(&self) -> &StorageConfig {
+        &self.storage
+    }
+
+    pub fn alerts(&self) -> &AlertConfig {
+        &self.alerts
+    }
+
+    pub fn auth(&self) -> &AuthConfig {
+        &self.auth
+    }
+
+    pub fn client_auth(&self) -> &ClientAuthConfig {
+        &self.client_auth
+    }
+
+    /// Which data sources may register with each manager, keyed by manager.
+    pub fn source_filters(&self) -> &HashMap<String, SourceFilterConfig> {
+        &self.source_filters
+    }
+
+    pub fn cors(&self) -> &CorsConfig {
+        &self.cors
+    }
+
+    pub fn unix_socket(&self) -> &UnixSocketConfig {
+        &self.unix_socket
+    }
+
+    pub fn mqtt(&self) -> &MqttConfig {
+        &self.mqtt
+    }
+}
+
+/// Returns the prefix of the environment variables that override the config of the manager with the id
+/// `manager_id`. This is `FLORUST_`, followed by the id in uppercase with everything but letters and
+/// digits replaced by underscores, followed by another underscore.
+fn env_prefix(manager_id: &str) -> String {
+    let id: String = manager_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+
+    format!("{}{}_", ENV_PREFIX, id)
+}
+
+/// Returns every environment variable whose name and value are valid unicode, skipping the others, as they
+/// can't be config overrides.
+fn env_vars() -> impl Iterator<Item = (String, String)> {
+    env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+}
+
+/// Finds the ids in `env_ids` whose environment variables can't be told apart from those of another id, like
+/// `FLORUST_WEATHER_API_TOKEN`, which overrides `api_token` for the manager `weather` as well as `token` for
+/// the manager `weather_api`. Ids are only ambiguous while such a variable is set. Returns every ambiguous id
+/// along with why it is.
+pub fn ambiguous_env_ids<'a>(env_ids: impl IntoIterator<Item = &'a str>) -> HashMap<String, String> {
+    let prefixes: Vec<(&str, String)> = env_ids.into_iter().map(|id| (id, env_prefix(id))).collect();
+    let names: Vec<String> = env_vars().map(|(name, _)| name).collect();
+
+    let mut ambiguous = HashMap::new();
+    for (index, (id, prefix)) in prefixes.iter().enumerate() {
+        for (other_index, (other_id, other_prefix)) in prefixes.iter().enumerate() {
+            if index == other_index || !other_prefix.starts_with(prefix.as_str()) {
+                continue;
+            }
+
+            if let Some(name) = names.iter().find(|name| name.starts_with(other_prefix.as_str())) {
+                let reason = format!("environment variable {} overrides the configs of both {} and {}", name, id, other_id);
+                ambiguous.insert(id.to_string(), reason.clone());
+                ambiguous.insert(other_id.to_string(), reason);
+            }
+        }
+    }
+    ambiguous
+}
+
+/// Merges `config`, the config passed to a manager, over `defaults`, the default config exported by the
+/// manager's plugin. Values in `config` take precedence over the defaults, and tables present in both are
+/// merged the same way, so users only need to set the values they want to change.
+pub fn with_defaults(defaults: Option<&Table>, config: Option<Table>) -> Option<Table> {
+    let Some(defaults) = defaults else {
+        return config;
+    };
+
+    let mut merged = defaults.clone();
+    if let Some(config) = config {
+        merge_tables(&mut merged, config);
+    }
+    Some(merged)
+}
+
+/// Merges `over` into `table`, replacing the values of `table` with those of `over` unless both are tables,
+/// which are merged recursively instead.
+fn merge_tables(table: &mut Table, over: Table) {
+    for (key, value) in over {
+        match (table.get_mut(&key), value) {
+            (Some(Value::Table(table)), Value::Table(over)) => merge_tables(table, over),
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Merges the environment variables named `FLORUST_<MANAGER_ID>_<KEY>` over `config`, the config passed to
+/// the manager with the id `manager_id`. Keys are lowercased, and `__` separates the keys of nested tables,
+/// so `FLORUST_WEATHER_API__TOKEN` sets `token` in the `api` table of the manager `weather`. Values are parsed
+/// as TOML values, like `42` or `true`, falling back to strings if they can't be. Environment variables take
+/// precedence over the values in the config file.
+pub fn with_env_overrides(manager_id: &str, config: Option<Table>) -> Option<Table> {
+    let prefix = env_prefix(manager_id);
+    let overrides: Vec<(String, String)> = env_vars()
+        .filter_map(|(name, value)| Some((name.strip_prefix(&prefix)?.to_ascii_lowercase(), value)))
+        .filter(|(key, _)| !key.is_empty())
+        .collect();
+
+    if overrides.is_empty() {
+        return config;
+    }
+
+    let mut config = config.unwrap_or_default();
+    for (key, value) in overrides {
+        let value = Value::deserialize(toml::de::ValueDeserializer::new(&value))
+            .unwrap_or(Value::String(value));
+
+        let mut path: Vec<&str> = key.split("__").collect();
+        let Some(last) = path.pop() else {
+            continue;
+        };
+
+        let mut table = &mut config;
+        for section in path {
+            let entry = table.entry(section).or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            let Value::Table(section) = entry else {
+                unreachable!("entry was just made a table");
+            };
+            table = section;
+        }
+        table.insert(last.to_string(), value);
+    }
+
+    Some(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_overrides_are_merged_into_nested_tables() {
+        env::set_var("FLORUST_ENV_TEST_API__TOKEN", "secret");
+        env::set_var("FLORUST_ENV_TEST_RETRIES", "3");
+
+        let config = with_env_overrides("env-test", None).unwrap();
+        assert_eq!(config["api"]["token"].as_str(), Some("secret"));
+        assert_eq!(config["retries"].as_integer(), Some(3));
+    }
+
+    #[test]
+    fn ids_sharing_a_set_variable_are_ambiguous() {
+        env::set_var("FLORUST_AMBIGUOUS_API_TOKEN", "secret");
+
+        let ambiguous = ambiguous_env_ids(["ambiguous", "ambiguous_api", "unambiguous"]);
+        assert!(ambiguous.contains_key("ambiguous"));
+        assert!(ambiguous.contains_key("ambiguous_api"));
+        assert!(!ambiguous.contains_key("unambiguous"));
+
+        assert!(ambiguous_env_ids(["unset", "unset_api"]).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn variables_that_arent_unicode_are_skipped() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        env::set_var("FLORUST_NON_UNICODE_TEST_KEY", OsStr::from_bytes(b"\xff"));
+        env::set_var(OsStr::from_bytes(b"FLORUST_NON_UNICODE_TEST_\xff"), "value");
+        assert!(with_env_overrides("non_unicode_test", None).is_none());
+    }
+}
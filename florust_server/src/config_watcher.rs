@@ -0,0 +1,125 @@
+use std::{collections::HashMap, fs::{metadata, read_to_string}, path::{Path, PathBuf}, sync::Arc, time::{Duration, SystemTime}};
+
+use tracing::{info, warn};
+use rocket::tokio::time::interval;
+use toml::Table;
+
+use crate::{BoxedManagerAndData, auth::{ApiKeys, SharedApiKeys}, config::{CONFIG_PATH, FlorustServerConfig, with_env_overrides}};
+
+/// How often plugin config files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A plugin config file that is watched for changes, along with the id of the manager it configures.
+pub struct WatchedConfig {
+    manager_id: &'static str,
+    path: PathBuf,
+    /// The id the environment variables overriding the config are named after.
+    env_id: String,
+    modified: Option<SystemTime>
+}
+
+impl WatchedConfig {
+    pub fn new(manager_id: &'static str, path: PathBuf, env_id: String) -> WatchedConfig {
+        let modified = modified_time(&path);
+        WatchedConfig {
+            manager_id,
+            path,
+            env_id,
+            modified
+        }
+    }
+}
+
+/// Returns the config sections that get passed along to a plugin's data source manager, or `None` if there
+/// are none. Expects the `plugin` section to already have been removed.
+pub fn extra_config_sections(toml: Table) -> Option<Table> {
+    if !toml.is_empty() {
+        Some(toml)
+    }
+    else {
+        None
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    metadata(path).and_then(|md| md.modified()).ok()
+}
+
+/// Periodically checks the given config files for changes, and passes the new config to the manager
+/// they configure whenever they change. Changes to the `plugin` section are not applied until the server
+/// restarts.
+pub async fn watch_configs(managers_and_data: Arc<HashMap<&'static str, BoxedManagerAndData>>, mut configs: Vec<WatchedConfig>) {
+    if configs.is_empty() {
+        return;
+    }
+
+    let mut interval = interval(WATCH_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        for config in configs.iter_mut() {
+            let modified = modified_time(&config.path);
+            if modified == config.modified {
+                continue;
+            }
+            config.modified = modified;
+
+            let Some(manager) = managers_and_data.get(config.manager_id) else {
+                continue;
+            };
+
+            let mut toml = match read_to_string(&config.path).map(|file| file.parse::<Table>()) {
+                Ok(Ok(toml)) => toml,
+                Ok(Err(err)) => {
+                    warn!("Failed to parse changed config (file: {}): {}", config.path.to_string_lossy(), err);
+                    continue;
+                }
+                Err(err) => {
+                    warn!("Failed to open changed config (file: {}): {}", config.path.to_string_lossy(), err);
+                    continue;
+                }
+            };
+            toml.remove("plugin");
+
+            match manager.reconfigure(with_env_overrides(&config.env_id, extra_config_sections(toml))).await {
+                Ok(()) => info!("Reconfigured manager (id: {}) from changed config", config.manager_id),
+                Err(err) => warn!("Manager (id: {}) failed to reconfigure with error: {}", config.manager_id, err)
+            }
+        }
+    }
+}
+
+/// Periodically checks the server's config file for changes, and replaces `api_keys` with the keys in the new
+/// config whenever it changes. Other changes to the server's config are not applied until the server restarts.
+pub async fn watch_server_config(api_keys: SharedApiKeys) {
+    let path = Path::new(CONFIG_PATH);
+    let mut last_modified = modified_time(path);
+
+    let mut interval = interval(WATCH_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let modified = modified_time(path);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        // A config that can't be read keeps the current keys, rather than removing every key and leaving the
+        // server open to anyone while it's being replaced.
+        let config = match read_to_string(path).map(|file| toml::from_str::<FlorustServerConfig>(&file)) {
+            Ok(Ok(config)) => config,
+            Ok(Err(err)) => {
+                warn!("Failed to parse changed server config (file: {}), keeping the current API keys: {}", CONFIG_PATH, err);
+                continue;
+            }
+            Err(err) => {
+                warn!("Failed to read changed server config (file: {}), keeping the current API keys: {}", CONFIG_PATH, err);
+                continue;
+            }
+        };
+
+        *api_keys.write().unwrap_or_else(|err| err.into_inner()) = ApiKeys::from_config(config.auth());
+        info!("Reloaded API keys from changed server config");
+    }
+}
@@ -0,0 +1,253 @@
+use std::io::Cursor;
+
+use rocket::{Request, Response, Route, async_trait, fairing::{Fairing, Info, Kind}, http::{Header, Method, Status}};
+
+use crate::config::{CorsConfig, CorsPolicyConfig};
+
+/// Methods cross-origin reads may use, unless their policy lists its own. `POST` is only used by reads that
+/// send what they read as a body.
+const DEFAULT_READ_METHODS: [&str; 3] = ["GET", "HEAD", "POST"];
+
+/// Methods cross-origin writes may use, unless their policy lists its own.
+const DEFAULT_WRITE_METHODS: [&str; 3] = ["POST", "PUT", "DELETE"];
+
+/// The headers of responses that pages are allowed to read, besides the ones browsers always expose.
+const EXPOSED_HEADERS: [&str; 3] = ["ETag", "Florust-Stale", "Retry-After"];
+
+/// The routes that only read data despite not using `GET` or `HEAD`, by name.
+const READ_ROUTES: [&str; 1] = ["latest_bulk_of"];
+
+/// Whether `route` only reads data.
+fn is_read(route: &Route) -> bool {
+    route.method == Method::Get
+        || route.method == Method::Head
+        || route.name.as_deref().is_some_and(|name| READ_ROUTES.contains(&name))
+}
+
+/// Whether `path` matches the path of a route, `pattern`, in which every dynamic segment matches any segment.
+fn matches_path(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.trim_matches('/').split('/');
+    let mut path = path.trim_matches('/').split('/');
+    loop {
+        match (pattern.next(), path.next()) {
+            (Some(segment), _) if segment.starts_with('<') && segment.ends_with("..>") => return true,
+            (Some(segment), Some(other)) if segment == other || segment.starts_with('<') => continue,
+            (None, None) => return true,
+            _ => return false
+        }
+    }
+}
+
+/// Which cross-origin requests browsers may make to a kind of endpoint. A policy without any allowed origins
+/// leaves its endpoints same-origin only.
+struct CorsPolicy {
+    allowed_origins: Vec<String>,
+    allowed_methods: Vec<String>,
+    allowed_headers: Vec<String>,
+    max_age: Option<u64>
+}
+
+impl CorsPolicy {
+    fn from_config(config: &CorsPolicyConfig, default_methods: &[&str]) -> CorsPolicy {
+        let allowed_methods = match config.allowed_methods() {
+            Some(methods) => methods.iter().map(|method| method.to_ascii_uppercase()).collect(),
+            None => default_methods.iter().map(|method| method.to_string()).collect()
+        };
+
+        CorsPolicy {
+            allowed_origins: config.allowed_origins().to_vec(),
+            allowed_methods,
+            allowed_headers: config.allowed_headers().to_vec(),
+            max_age: config.max_age()
+        }
+    }
+
+    /// Returns the `Access-Control-Allow-Origin` requests from `origin` are answered with, or `None` if
+    /// `origin` isn't allowed.
+    fn allow_origin<'a>(&self, origin: &'a str) -> Option<&'a str> {
+        if self.allowed_origins.iter().any(|allowed| allowed == "*") {
+            Some("*")
+        }
+        else {
+            self.allowed_origins.iter().any(|allowed| allowed == origin).then_some(origin)
+        }
+    }
+
+    fn allows_method(&self, method: &str) -> bool {
+        self.allowed_methods.iter().any(|allowed| allowed.eq_ignore_ascii_case(method))
+    }
+
+    /// Whether every header in `headers`, a comma separated list as sent in `Access-Control-Request-Headers`,
+    /// is allowed.
+    fn allows_headers(&self, headers: &str) -> bool {
+        headers
+            .split(',')
+            .map(str::trim)
+            .filter(|header| !header.is_empty())
+            .all(|header| self.allowed_headers.iter().any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(header)))
+    }
+}
+
+/// A fairing that lets browsers call the server from other origins, like a dashboard served from elsewhere.
+/// Reads and writes have separate policies, so writes can be kept stricter than reads. Answers preflight
+/// requests itself, as no route handles `OPTIONS`.
+pub struct Cors {
+    read: CorsPolicy,
+    write: CorsPolicy
+}
+
+impl Cors {
+    pub fn from_config(config: &CorsConfig) -> Cors {
+        Cors {
+            read: CorsPolicy::from_config(config.read(), &DEFAULT_READ_METHODS),
+            write: CorsPolicy::from_config(config.write(), &DEFAULT_WRITE_METHODS)
+        }
+    }
+
+    /// The policy of reads if `read` is set, and of writes otherwise.
+    fn policy(&self, read: bool) -> &CorsPolicy {
+        if read {
+            &self.read
+        }
+        else {
+            &self.write
+        }
+    }
+
+    /// Whether a request made with `method` to `path` would only read data, going by the routes it could be
+    /// handled by. Requests no route handles are reads if they use `GET` or `HEAD`.
+    fn is_read_request(request: &Request<'_>, method: &str, path: &str) -> bool {
+        let mut routes = request.rocket()
+            .routes()
+            .filter(|route| route.method.as_str().eq_ignore_ascii_case(method) && matches_path(route.uri.path(), path))
+            .peekable();
+        if routes.peek().is_none() {
+            return method.eq_ignore_ascii_case("GET") || method.eq_ignore_ascii_case("HEAD");
+        }
+        routes.all(is_read)
+    }
+
+    /// Answers a preflight request from `origin` for a request made with `method` and sent with `headers`, if
+    /// that request is allowed. Otherwise the preflight is left unanswered, which browsers treat as a refusal.
+    fn preflight(&self, origin: &str, method: &str, headers: Option<&str>, read: bool, response: &mut Response<'_>) {
+        let policy = self.policy(read);
+        let Some(allowed_origin) = policy.allow_origin(origin) else {
+            return;
+        };
+        if !policy.allows_method(method) || !headers.is_none_or(|headers| policy.allows_headers(headers)) {
+            return;
+        }
+
+        response.set_status(Status::NoContent);
+        response.remove_header("Content-Type");
+        response.set_sized_body(0, Cursor::new(""));
+        response.set_header(Header::new("Access-Control-Allow-Origin", allowed_origin.to_string()));
+        response.set_header(Header::new("Access-Control-Allow-Methods", policy.allowed_methods.join(", ")));
+        if !policy.allowed_headers.is_empty() {
+            response.set_header(Header::new("Access-Control-Allow-Headers", policy.allowed_headers.join(", ")));
+        }
+        if let Some(max_age) = policy.max_age {
+            response.set_header(Header::new("Access-Control-Max-Age", max_age.to_string()));
+        }
+        response.adjoin_header(Header::new("Vary", "Origin"));
+    }
+}
+
+#[async_trait]
+impl Fairing for Cors {
+    fn info(&self) -> Info {
+        Info {
+            name: "CORS",
+            kind: Kind::Response
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(origin) = request.headers().get_one("Origin") else {
+            return;
+        };
+
+        if request.method() == Method::Options {
+            if let Some(method) = request.headers().get_one("Access-Control-Request-Method") {
+                let read = Self::is_read_request(request, method, request.uri().path().as_str());
+                self.preflight(origin, method, request.headers().get_one("Access-Control-Request-Headers"), read, response);
+                return;
+            }
+        }
+
+        let read = match request.route() {
+            Some(route) => is_read(route),
+            None => Self::is_read_request(request, request.method().as_str(), request.uri().path().as_str())
+        };
+        let policy = self.policy(read);
+        let Some(allowed_origin) = policy.allow_origin(origin) else {
+            return;
+        };
+        if !policy.allows_method(request.method().as_str()) {
+            return;
+        }
+
+        response.set_header(Header::new("Access-Control-Allow-Origin", allowed_origin.to_string()));
+        response.set_header(Header::new("Access-Control-Expose-Headers", EXPOSED_HEADERS.join(", ")));
+        response.adjoin_header(Header::new("Vary", "Origin"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{Build, Rocket, get, local::asynchronous::Client, post, routes};
+
+    use super::*;
+
+    #[get("/<_>/latest_bulk")]
+    fn latest_bulk() {}
+
+    #[post("/<_>/latest_bulk")]
+    fn latest_bulk_of() {}
+
+    #[post("/<_>/deregister_all")]
+    fn deregister_all() {}
+
+    fn rocket() -> Rocket<Build> {
+        let config: CorsConfig = toml::from_str(r#"allowed_origins = ["https://dashboard.example.com"]"#).unwrap();
+        rocket::build()
+            .mount("/manager", routes![latest_bulk, latest_bulk_of, deregister_all])
+            .attach(Cors::from_config(&config))
+    }
+
+    /// Returns the `Access-Control-Allow-Origin` a preflight for a `method` request to `path` is answered with.
+    async fn preflight(client: &Client, method: &str, path: &str) -> Option<String> {
+        let response = client.options(path.to_string())
+            .header(Header::new("Origin", "https://dashboard.example.com"))
+            .header(Header::new("Access-Control-Request-Method", method.to_string()))
+            .dispatch().await;
+        response.headers().get_one("Access-Control-Allow-Origin").map(str::to_string)
+    }
+
+    #[test]
+    fn dynamic_segments_match_any_segment() {
+        assert!(matches_path("/manager/<manager_id>/latest_bulk", "/manager/Tp/latest_bulk"));
+        assert!(matches_path("/data_source/<manager_id>/<data_source_id..>", "/data_source/Tp/tenant/kitchen"));
+        assert!(!matches_path("/manager/<manager_id>/latest_bulk", "/manager/Tp/deregister_all"));
+        assert!(!matches_path("/manager/<manager_id>/latest_bulk", "/manager/Tp/latest_bulk/more"));
+    }
+
+    #[rocket::async_test]
+    async fn posts_that_only_read_use_the_read_policy() {
+        let client = Client::untracked(rocket()).await.unwrap();
+
+        assert!(preflight(&client, "POST", "/manager/Tp/latest_bulk").await.is_some());
+        assert!(preflight(&client, "POST", "/manager/Tp/deregister_all").await.is_none());
+
+        let response = client.post("/manager/Tp/latest_bulk")
+            .header(Header::new("Origin", "https://dashboard.example.com"))
+            .dispatch().await;
+        assert!(response.headers().get_one("Access-Control-Allow-Origin").is_some());
+        assert_eq!(response.headers().get_one("Access-Control-Expose-Headers"), Some("ETag, Florust-Stale, Retry-After"));
+
+        let response = client.post("/manager/Tp/deregister_all")
+            .header(Header::new("Origin", "https://dashboard.example.com"))
+            .dispatch().await;
+        assert!(response.headers().get_one("Access-Control-Allow-Origin").is_none());
+    }
+}
@@ -1,18 +1,85 @@
-use florust_common::{UploadedData, server::FlorustServerPluginError};
-use rocket::{form::Form, post, put, get, Responder, State, serde::json::Json};
+use std::collections::BTreeSet;
 
-use crate::{FlorustState, manager_and_data::{ManagerAndDataError, DataType, self}};
+use florust_common::server::{FlorustServerPluginError, DataSourceManagerError};
+use rocket::{
+    post, put, get, Request, State,
+    http::{ContentType, Header, MediaType, Status},
+    response::{self, Responder},
+    serde::{Serialize, json::Json},
+    tokio::io::AsyncReadExt
+};
+
+use crate::{
+    FlorustState,
+    auth::{ReadAccess, WriteAccess},
+    client_auth::ClientIdentity,
+    idempotency::IdempotencyKey,
+    known_manager::KnownManager,
+    signing::Signature,
+    trace::TraceParent,
+    manager_and_data::{ManagerAndDataError, DataPoint, NamespacedId, ServedValue, UntaggedDataType, self},
+    upload::{StreamBody, UploadBody, UploadError, optional_upload}
+};
+
+/// The media type of CBOR encoded bodies.
+const CBOR: MediaType = MediaType::const_new("application", "cbor", &[]);
+
+/// A responder that serializes its value as CBOR if the request prefers it, using its `Accept` header, or as
+/// JSON otherwise. CBOR is more compact, and doesn't lose precision on large integers like some JSON clients.
+pub struct Negotiated<T>(pub T);
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Negotiated<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let prefers_cbor = request
+            .accept()
+            .is_some_and(|accept| *accept.preferred().media_type() == CBOR);
+
+        if !prefers_cbor {
+            return Json(self.0).respond_to(request);
+        }
+
+        let mut body = Vec::new();
+        ciborium::into_writer(&self.0, &mut body).map_err(|_| Status::InternalServerError)?;
+        (ContentType(CBOR), body).respond_to(request)
+    }
+}
+
+/// The `Retry-After` header of `429 Too Many Requests` responses, telling clients how many seconds to wait
+/// before retrying.
+pub struct RetryAfter(u64);
+
+impl From<RetryAfter> for Header<'static> {
+    fn from(value: RetryAfter) -> Self {
+        Header::new("Retry-After", value.0.to_string())
+    }
+}
 
 #[derive(Responder)]
 pub enum DataSourceError {
-    #[response(status = 400, content_type = "json")]
-    BadRequest(Json<ManagerAndDataError>),
-    #[response(status = 404, content_type = "json")]
-    NotFound(Json<ManagerAndDataError>),
-    #[response(status = 409, content_type = "json")]
-    Conflict(Json<ManagerAndDataError>),
-    #[response(status = 500, content_type = "json")]
-    InternalError(Json<ManagerAndDataError>)
+    #[response(status = 400)]
+    BadRequest(Negotiated<ManagerAndDataError>),
+    #[response(status = 401)]
+    Unauthorized(Negotiated<ManagerAndDataError>),
+    #[response(status = 403)]
+    Forbidden(Negotiated<ManagerAndDataError>),
+    #[response(status = 404)]
+    NotFound(Negotiated<ManagerAndDataError>),
+    #[response(status = 409)]
+    Conflict(Negotiated<ManagerAndDataError>),
+    #[response(status = 413)]
+    PayloadTooLarge(Negotiated<ManagerAndDataError>),
+    #[response(status = 415)]
+    UnsupportedMediaType(Negotiated<ManagerAndDataError>),
+    #[response(status = 422)]
+    UnprocessableEntity(Negotiated<ManagerAndDataError>),
+    #[response(status = 429)]
+    TooManyRequests(Negotiated<ManagerAndDataError>, RetryAfter),
+    #[response(status = 500)]
+    InternalError(Negotiated<ManagerAndDataError>),
+    #[response(status = 503)]
+    ServiceUnavailable(Negotiated<ManagerAndDataError>),
+    #[response(status = 504)]
+    GatewayTimeout(Negotiated<ManagerAndDataError>)
 }
 
 impl From<ManagerAndDataError> for DataSourceError {
@@ -20,20 +87,87 @@ impl From<ManagerAndDataError> for DataSourceError {
         match &value {
             ManagerAndDataError::DataSourceManager(error) => match error {
                 FlorustServerPluginError::DataSourceAlreadyExists(_) | FlorustServerPluginError::DataSourceAlreadyDeregistered(_) => Self::Conflict(
-                    Json(value)
+                    Negotiated(value)
                 ),
                 FlorustServerPluginError::DataSourceDoesntExist(_) | FlorustServerPluginError::DataSourceManagerDoesntExist(_)=> Self::NotFound(
-                    Json(value)
+                    Negotiated(value)
+                ),
+                FlorustServerPluginError::DataSourceManager(DataSourceManagerError::OutOfRange { .. }) => Self::UnprocessableEntity(
+                    Negotiated(value)
+                ),
+                FlorustServerPluginError::DataSourceManager(DataSourceManagerError::RegistrationRejected(_)) => Self::BadRequest(
+                    Negotiated(value)
+                ),
+                FlorustServerPluginError::DataSourceManager(DataSourceManagerError::Timeout) => Self::GatewayTimeout(
+                    Negotiated(value)
+                ),
+                FlorustServerPluginError::DataSourceManager(DataSourceManagerError::AtCapacity(_)) => Self::ServiceUnavailable(
+                    Negotiated(value)
                 ),
                 FlorustServerPluginError::DataSourceManager(_) => Self::BadRequest(
-                    Json(value)
+                    Negotiated(value)
+                ),
+                FlorustServerPluginError::ManagerTypeMismatch { .. } => Self::BadRequest(
+                    Negotiated(value)
+                ),
+                FlorustServerPluginError::PluginLoadFailed { .. }
+                | FlorustServerPluginError::PluginPanicked { .. }
+                | FlorustServerPluginError::ConfigInvalid { .. }
+                | FlorustServerPluginError::DependencyMissing { .. }
+                | FlorustServerPluginError::DependencyCycle { .. }
+                | FlorustServerPluginError::AbiVersionMissing(_)
+                | FlorustServerPluginError::AbiVersionMismatch { .. } => Self::InternalError(
+                    Negotiated(value)
                 ),
             },
             ManagerAndDataError::NoData => Self::InternalError(
-                Json(value)
+                Negotiated(value)
             ),
             ManagerAndDataError::IndexOutOfBounds => Self::InternalError(
-                Json(value)
+                Negotiated(value)
+            ),
+            ManagerAndDataError::ConfirmationRequired => Self::BadRequest(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::RateLimited { retry_after, .. } | ManagerAndDataError::Overloaded { retry_after, .. } => {
+                let retry_after = RetryAfter(*retry_after);
+                Self::TooManyRequests(Negotiated(value), retry_after)
+            },
+            ManagerAndDataError::InvalidUpload(_) => Self::BadRequest(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::InvalidRegistration(_) => Self::BadRequest(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::PayloadTooLarge(_) => Self::PayloadTooLarge(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::Forbidden(_) | ManagerAndDataError::RegistrationDenied { .. } => Self::Forbidden(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::InvalidSignature(_) => Self::Unauthorized(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::UnsupportedContentType { .. } => Self::UnsupportedMediaType(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::UnsupportedAggregation(_) => Self::BadRequest(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::RateUndefined(_) => Self::UnprocessableEntity(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::PluginNotLoaded(_) => Self::NotFound(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::ReloadFailed(_) => Self::UnprocessableEntity(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::MissingCapabilities { .. } => Self::UnprocessableEntity(
+                Negotiated(value)
+            ),
+            ManagerAndDataError::NonMonotonic { .. } => Self::Conflict(
+                Negotiated(value)
             ),
         }
     }
@@ -41,71 +175,219 @@ impl From<ManagerAndDataError> for DataSourceError {
 
 #[derive(Responder)]
 #[response(status = 200)]
-pub struct OkResponder<T>(Json<T>) where T: Send + Sync;
+pub struct OkResponder<T>(Negotiated<T>) where T: Send + Sync;
 
-fn state_op_to_responder<T: Send + Sync>(op_result: manager_and_data::Result<T>) -> Result<OkResponder<T>, DataSourceError> {
-    op_result.map(|value| OkResponder(Json(value)))
+/// A responder for operations that may succeed without producing a value, responding with 204 in that case.
+#[derive(Responder)]
+pub enum OptionalResponder<T> where T: Send + Sync {
+    #[response(status = 200)]
+    Some(Negotiated<T>),
+    #[response(status = 204)]
+    None(())
+}
+
+pub fn optional_state_op_to_responder<T: Send + Sync>(op_result: manager_and_data::Result<Option<T>>) -> Result<OptionalResponder<T>, DataSourceError> {
+    op_result.map(|value| match value {
+        Some(value) => OptionalResponder::Some(Negotiated(value)),
+        None => OptionalResponder::None(())
+    })
         .map_err(DataSourceError::from)
 }
 
-#[post("/register/<manager_id>/<data_source_id>", data = "<data>")]
+pub fn state_op_to_responder<T: Send + Sync>(op_result: manager_and_data::Result<T>) -> Result<OkResponder<T>, DataSourceError> {
+    op_result.map(|value| OkResponder(Negotiated(value)))
+        .map_err(DataSourceError::from)
+}
+
+/// Registers a data source, tagging it with every `tag` parameter given.
+#[post("/register/<manager_id>/<data_source_id..>?<data_type>&<initial_value>&<tag>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn register(
+    _access: WriteAccess,
+    _manager: KnownManager,
     state: &State<FlorustState>,
+    identity: ClientIdentity,
     manager_id: String,
-    data_source_id: String,
-    data: Option<Form<UploadedData>>
+    data_source_id: NamespacedId,
+    data_type: Option<String>,
+    initial_value: Option<bool>,
+    tag: Vec<String>,
+    data: Result<UploadBody, UploadError>
 ) -> Result<OkResponder<()>, DataSourceError> {
-    let data = match &data {
-        Some(data) => Some(data.data.as_slice()),
-        None => None
-    };
+    state.check_data_type(&manager_id, data_type.as_deref())?;
+    state.check_client(&manager_id, &identity, &data_source_id)?;
+    let data = optional_upload(data)?;
+    if let Some(data) = &data {
+        state.check_capabilities(&manager_id, &data.requires)?;
+    }
+    let signing_secret = data.as_ref().and_then(|data| data.signing_secret.as_deref());
+    let data = data.as_ref().map(|data| data.data.as_slice());
 
-    state_op_to_responder(state.register_data_source(&manager_id, data_source_id, data).await)
+    let tags = tag.into_iter().collect::<BTreeSet<_>>();
+    if tags.contains("") {
+        return Err(ManagerAndDataError::InvalidUpload("tags must not be empty".to_string()).into());
+    }
+
+    if initial_value.unwrap_or(false) {
+        let data = data.filter(|data| !data.is_empty()).ok_or_else(|| ManagerAndDataError::InvalidUpload("an initial value requires data to be sent".to_string()))?;
+        state.register_with_initial_value(&manager_id, data_source_id, data, signing_secret, tags).await?;
+    } else {
+        state.register_data_source(&manager_id, data_source_id, data, signing_secret, tags).await?;
+    }
+
+    state_op_to_responder(Ok(()))
 }
 
-#[post("/unregister/<manager_id>/<data_source_id>", data = "<data>")]
+/// Deregisters a data source. By default, if its manager fails to deregister it, the data source stays
+/// registered and the manager's error is returned, so the deregistration can be retried. With
+/// `deregister_force=true`, the manager's error is only logged and the data source is deregistered regardless,
+/// so a manager that keeps failing to clean up can't leave the data source's id stuck.
+#[post("/unregister/<manager_id>/<data_source_id..>?<deregister_force>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn unregister(
+    _access: WriteAccess,
+    _manager: KnownManager,
     state: &State<FlorustState>,
+    identity: ClientIdentity,
     manager_id: String,
-    data_source_id: String,
-    data: Option<Form<UploadedData>>
+    data_source_id: NamespacedId,
+    deregister_force: Option<bool>,
+    data: Result<UploadBody, UploadError>
 ) -> Result<OkResponder<()>, DataSourceError> {
-    let data = match &data {
-        Some(data) => Some(data.data.as_slice()),
-        None => None
-    };
+    state.check_client(&manager_id, &identity, &data_source_id)?;
+    let data = optional_upload(data)?;
+    let data = data.as_ref().map(|data| data.data.as_slice());
 
-    state_op_to_responder(state.deregister_data_source(&manager_id, &data_source_id, data).await)
+    state_op_to_responder(state.deregister_data_source(&manager_id, &data_source_id, data, deregister_force.unwrap_or(false)).await)
 }
 
-#[put("/upload_data/<manager_id>/<data_source_id>", data = "<data>")]
-pub async fn json_upload_data(
+#[put("/upload_data/<manager_id>/<data_source_id..>?<data_type>&<echo>&<allow_reset>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_data(
+    _access: WriteAccess,
+    _manager: KnownManager,
     state: &State<FlorustState>,
+    identity: ClientIdentity,
     manager_id: String,
-    data_source_id: String,
-    data: Json<UploadedData>,
-) -> Result<OkResponder<()>, DataSourceError> {
+    data_source_id: NamespacedId,
+    data_type: Option<String>,
+    echo: Option<bool>,
+    allow_reset: Option<bool>,
+    idempotency_key: IdempotencyKey,
+    trace_parent: TraceParent,
+    signature: Signature,
+    data: Result<UploadBody, UploadError>,
+) -> Result<OkResponder<Option<UntaggedDataType>>, DataSourceError> {
+    state.check_data_type(&manager_id, data_type.as_deref())?;
+    state.check_client(&manager_id, &identity, &data_source_id)?;
+    let data = data.map_err(ManagerAndDataError::from)?;
+    state.check_signature(&manager_id, &data_source_id, &data.0.data, signature.0.as_deref())?;
+    let echoed = state.update_data_idempotent(&manager_id, &data_source_id, idempotency_key.0.as_deref(), data.0.data.as_slice(), trace_parent.0.as_deref(), allow_reset.unwrap_or(false)).await
+        .map(|value| echo.unwrap_or(false).then_some(UntaggedDataType(value)));
 
-    state_op_to_responder(state.update_data(&manager_id, &data_source_id, data.data.as_slice()).await)
+    state_op_to_responder(echoed)
 }
 
-#[put("/upload_data/<manager_id>/<data_source_id>", data = "<data>")]
-pub async fn form_upload_data(
+#[put("/stream_data/<manager_id>/<data_source_id..>?<data_type>&<echo>&<allow_reset>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn stream_data(
+    _access: WriteAccess,
+    _manager: KnownManager,
     state: &State<FlorustState>,
+    identity: ClientIdentity,
     manager_id: String,
-    data_source_id: String,
-    data: Form<UploadedData>,
-) -> Result<OkResponder<()>, DataSourceError> {
+    data_source_id: NamespacedId,
+    data_type: Option<String>,
+    echo: Option<bool>,
+    allow_reset: Option<bool>,
+    signature: Signature,
+    content_type: Option<&ContentType>,
+    mut data: StreamBody<'_>,
+) -> Result<OkResponder<Option<UntaggedDataType>>, DataSourceError> {
+    state.check_data_type(&manager_id, data_type.as_deref())?;
+    state.check_client(&manager_id, &identity, &data_source_id)?;
+    state.check_content_type(&manager_id, content_type)?;
 
-    state_op_to_responder(state.update_data(&manager_id, &data_source_id, data.data.as_slice()).await)
+    let echoed = async {
+        if !state.requires_signature(&manager_id, &data_source_id)? {
+            return state.update_data_stream(&manager_id, &data_source_id, &mut data, allow_reset.unwrap_or(false)).await;
+        }
+
+        // The signature can only be checked once the whole update has been read, so updates that must be
+        // signed are read in full before they're passed on.
+        let mut body = Vec::new();
+        data.read_to_end(&mut body).await
+            .map_err(|err| ManagerAndDataError::InvalidUpload(err.to_string()))?;
+        state.check_signature(&manager_id, &data_source_id, &body, signature.0.as_deref())?;
+        state.update_data_stream(&manager_id, &data_source_id, &mut body.as_slice(), allow_reset.unwrap_or(false)).await
+    }.await
+    .map_err(|err| match data.exceeded_limit() {
+        Some(limit) => ManagerAndDataError::PayloadTooLarge(limit),
+        None => err
+    })
+    .map(|value| echo.unwrap_or(false).then_some(UntaggedDataType(value)));
+
+    state_op_to_responder(echoed)
+}
+
+#[put("/batch_upload_data/<manager_id>/<data_source_id..>?<data_type>&<echo>&<allow_reset>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn batch_upload_data(
+    _access: WriteAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    identity: ClientIdentity,
+    manager_id: String,
+    data_source_id: NamespacedId,
+    data_type: Option<String>,
+    echo: Option<bool>,
+    allow_reset: Option<bool>,
+    signature: Signature,
+    data: Result<UploadBody, UploadError>,
+) -> Result<OkResponder<Option<Vec<UntaggedDataType>>>, DataSourceError> {
+    state.check_data_type(&manager_id, data_type.as_deref())?;
+    state.check_client(&manager_id, &identity, &data_source_id)?;
+    let data = data.map_err(ManagerAndDataError::from)?;
+    state.check_signature(&manager_id, &data_source_id, &data.0.data, signature.0.as_deref())?;
+    let echoed = state.batch_update_data(&manager_id, &data_source_id, data.0.data.as_slice(), allow_reset.unwrap_or(false)).await
+        .map(|values| echo.unwrap_or(false).then(|| values.into_iter().map(UntaggedDataType).collect()));
+
+    state_op_to_responder(echoed)
 }
 
-#[get("/<manager_id>/<data_source_id>/<index>")]
+#[get("/<manager_id>/<data_source_id>/<index>?<namespace>")]
 pub async fn get_data(
+    _access: ReadAccess,
+    _manager: KnownManager,
     state: &State<FlorustState>,
     manager_id: String,
     data_source_id: String,
-    index: usize
-) -> Result<OkResponder<DataType>, DataSourceError> {
+    index: usize,
+    namespace: Option<String>
+) -> Result<OkResponder<DataPoint<ServedValue>>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
     state_op_to_responder(state.get_data(&manager_id, &data_source_id, index).await)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the body of the `404 Not Found` response `error` is answered with.
+    fn not_found_body(error: FlorustServerPluginError) -> String {
+        match DataSourceError::from(ManagerAndDataError::DataSourceManager(error)) {
+            DataSourceError::NotFound(Negotiated(error)) => serde_json::to_string(&error).unwrap(),
+            _ => panic!("error isn't answered with 404 Not Found")
+        }
+    }
+
+    #[test]
+    fn missing_managers_and_sources_have_distinct_responses() {
+        let manager = not_found_body(FlorustServerPluginError::DataSourceManagerDoesntExist("weather".to_string()));
+        let source = not_found_body(FlorustServerPluginError::DataSourceDoesntExist("kitchen".to_string()));
+
+        assert!(manager.contains("DataSourceManagerDoesntExist"));
+        assert!(source.contains("DataSourceDoesntExist"));
+        assert_ne!(manager, source);
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+
+use florust_common::server::FlorustServerPluginError;
+
+/// A plugin whose managers may depend on the managers of other plugins, sorted before its managers are created.
+pub trait Dependent {
+    /// The ids of the plugin's managers, as far as they're known before the managers are created.
+    fn manager_ids(&self) -> &[String];
+
+    /// What the plugin is called when it fails to load.
+    fn name(&self) -> &str;
+
+    /// The ids of the managers the plugin's managers depend on.
+    fn dependencies(&self) -> &[String];
+}
+
+enum Visit {
+    InProgress,
+    /// The plugin can be brought up in this stage, after every plugin it depends on.
+    Staged(usize),
+    Failed
+}
+
+/// Walks the dependencies of plugins depth first, assigning each the stage after the last of its dependencies.
+struct Sorter<'a, P> {
+    plugins: &'a [P],
+    /// The ids of the managers that are already up, which plugins can depend on from the first stage.
+    available: &'a HashSet<&'a str>,
+    /// The index of the plugin offering each manager, by manager id.
+    providers: HashMap<&'a str, usize>,
+    visits: Vec<Option<Visit>>,
+    /// The plugins whose dependencies are being walked, each depending on the one after it.
+    path: Vec<usize>,
+    failures: Vec<FlorustServerPluginError>
+}
+
+impl<'a, P: Dependent> Sorter<'a, P> {
+    fn visit(&mut self, index: usize) -> Option<usize> {
+        match self.visits[index] {
+            Some(Visit::Staged(stage)) => return Some(stage),
+            Some(Visit::Failed) => return None,
+            Some(Visit::InProgress) => {
+                self.fail_cycle(index);
+                return None;
+            },
+            None => ()
+        }
+
+        self.visits[index] = Some(Visit::InProgress);
+        self.path.push(index);
+
+        let plugins = self.plugins;
+        let mut stage = Some(0);
+        for dependency in plugins[index].dependencies() {
+            if self.available.contains(dependency.as_str()) {
+                continue;
+            }
+
+            let dependency_stage = match self.providers.get(dependency.as_str()) {
+                // Managers of the same plugin are created together.
+                Some(&provider) if provider == index => continue,
+                Some(&provider) => self.visit(provider),
+                None => None
+            };
+            match dependency_stage {
+                Some(dependency_stage) => stage = stage.map(|stage: usize| stage.max(dependency_stage + 1)),
+                None => {
+                    // Plugins that are part of a cycle were already failed along with the rest of it.
+                    if !matches!(self.visits[index], Some(Visit::Failed)) {
+                        self.failures.push(FlorustServerPluginError::DependencyMissing {
+                            manager_id: plugins[index].name().to_string(),
+                            dependency: dependency.clone()
+                        });
+                    }
+                    stage = None;
+                    break;
+                }
+            }
+        }
+
+        self.path.pop();
+        self.visits[index] = Some(match stage {
+            Some(stage) => Visit::Staged(stage),
+            None => Visit::Failed
+        });
+        stage
+    }
+
+    /// Fails every plugin in the cycle of dependencies that leads back to the plugin at `index`.
+    fn fail_cycle(&mut self, index: usize) {
+        let start = self.path
+            .iter()
+            .position(|in_progress| *in_progress == index)
+            .expect("plugins in progress should be on the path");
+        let cycle = self.path[start..].to_vec();
+        let described = cycle
+            .iter()
+            .chain([&index])
+            .map(|index| self.plugins[*index].name())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+
+        for index in cycle {
+            self.visits[index] = Some(Visit::Failed);
+            self.failures.push(FlorustServerPluginError::DependencyCycle {
+                manager_id: self.plugins[index].name().to_string(),
+                cycle: described.clone()
+            });
+        }
+    }
+}
+
+/// Splits `plugins` into the stages their managers are created in, every plugin coming after the plugins
+/// offering the managers it depends on, and plugins only depending on plugins of earlier stages or on the
+/// managers in `available`, which are already up. Plugins whose dependencies aren't loaded, including ones that
+/// fail to load for their own dependencies, or that depend on each other in a cycle, are left out, adding why
+/// to `failures`.
+pub fn load_stages<P: Dependent>(available: &HashSet<&str>, plugins: Vec<P>, failures: &mut Vec<FlorustServerPluginError>) -> Vec<Vec<P>> {
+    let visits = {
+        let mut sorter = Sorter {
+            plugins: &plugins,
+            available,
+            providers: plugins
+                .iter()
+                .enumerate()
+                .flat_map(|(index, plugin)| plugin.manager_ids().iter().map(move |manager_id| (manager_id.as_str(), index)))
+                .collect(),
+            visits: plugins.iter().map(|_| None).collect(),
+            path: Vec::new(),
+            failures: Vec::new()
+        };
+
+        // Sorted so the same cycles are reported the same way every time.
+        let mut order = (0..plugins.len()).collect::<Vec<_>>();
+        order.sort_by_key(|index| plugins[*index].name());
+        for index in order {
+            sorter.visit(index);
+        }
+        failures.append(&mut sorter.failures);
+        sorter.visits
+    };
+
+    let mut staged: Vec<Vec<P>> = Vec::new();
+    for (plugin, visit) in plugins.into_iter().zip(visits) {
+        let Some(Visit::Staged(stage)) = visit else {
+            continue;
+        };
+        if staged.len() <= stage {
+            staged.resize_with(stage + 1, Vec::new);
+        }
+        staged[stage].push(plugin);
+    }
+
+    staged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestPlugin {
+        manager_ids: Vec<String>,
+        dependencies: Vec<String>
+    }
+
+    impl Dependent for TestPlugin {
+        fn manager_ids(&self) -> &[String] {
+            &self.manager_ids
+        }
+
+        fn name(&self) -> &str {
+            &self.manager_ids[0]
+        }
+
+        fn dependencies(&self) -> &[String] {
+            &self.dependencies
+        }
+    }
+
+    fn plugin(manager_id: &str, dependencies: &[&str]) -> TestPlugin {
+        TestPlugin {
+            manager_ids: vec![manager_id.to_string()],
+            dependencies: dependencies.iter().map(|dependency| dependency.to_string()).collect()
+        }
+    }
+
+    /// Sorts `plugins` with `available` already up, returning the names of the plugins in every stage, sorted,
+    /// and the failures.
+    fn sort(available: &[&str], plugins: Vec<TestPlugin>) -> (Vec<Vec<String>>, Vec<FlorustServerPluginError>) {
+        let mut failures = Vec::new();
+        let stages = load_stages(&available.iter().copied().collect(), plugins, &mut failures)
+            .into_iter()
+            .map(|stage| {
+                let mut names = stage.iter().map(|plugin| plugin.name().to_string()).collect::<Vec<_>>();
+                names.sort();
+                names
+            })
+            .collect();
+        (stages, failures)
+    }
+
+    #[test]
+    fn dependencies_come_first() {
+        let (stages, failures) = sort(&["Float"], vec![
+            plugin("Alerts", &["Forwarder", "Float"]),
+            plugin("Forwarder", &["Float"]),
+            plugin("Logger", &["Float"])
+        ]);
+
+        assert!(failures.is_empty());
+        assert_eq!(stages, [vec!["Forwarder", "Logger"], vec!["Alerts"]]);
+    }
+
+    #[test]
+    fn missing_dependencies_fail_their_dependents() {
+        let (stages, failures) = sort(&[], vec![
+            plugin("Forwarder", &["Weather"]),
+            plugin("Alerts", &["Forwarder"]),
+            plugin("Logger", &[])
+        ]);
+
+        assert_eq!(stages, [vec!["Logger"]]);
+        assert_eq!(failures.len(), 2);
+        assert!(failures.iter().any(|failure| matches!(
+            failure,
+            FlorustServerPluginError::DependencyMissing { manager_id, dependency } if manager_id == "Forwarder" && dependency == "Weather"
+        )));
+        assert!(failures.iter().any(|failure| matches!(
+            failure,
+            FlorustServerPluginError::DependencyMissing { manager_id, dependency } if manager_id == "Alerts" && dependency == "Forwarder"
+        )));
+    }
+
+    #[test]
+    fn cycles_fail_every_plugin_in_them_and_their_dependents() {
+        let (stages, failures) = sort(&[], vec![
+            plugin("A", &["B"]),
+            plugin("B", &["C"]),
+            plugin("C", &["A"]),
+            plugin("Dependent", &["B"]),
+            plugin("Logger", &[])
+        ]);
+
+        assert_eq!(stages, [vec!["Logger"]]);
+        let cycles = failures
+            .iter()
+            .filter_map(|failure| match failure {
+                FlorustServerPluginError::DependencyCycle { manager_id, cycle } => Some((manager_id.as_str(), cycle.as_str())),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(cycles, [("A", "A -> B -> C -> A"), ("B", "A -> B -> C -> A"), ("C", "A -> B -> C -> A")]);
+        assert!(failures.iter().any(|failure| matches!(
+            failure,
+            FlorustServerPluginError::DependencyMissing { manager_id, dependency } if manager_id == "Dependent" && dependency == "B"
+        )));
+        assert_eq!(failures.len(), 4);
+    }
+
+    #[test]
+    fn managers_of_the_same_plugin_may_depend_on_each_other() {
+        let (stages, failures) = sort(&[], vec![TestPlugin {
+            manager_ids: vec!["Raw".to_string(), "Smoothed".to_string()],
+            dependencies: vec!["Raw".to_string()]
+        }]);
+
+        assert!(failures.is_empty());
+        assert_eq!(stages, [vec!["Raw"]]);
+    }
+}
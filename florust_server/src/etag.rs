@@ -0,0 +1,86 @@
+use std::{convert::Infallible, hash::{DefaultHasher, Hash, Hasher}};
+
+use rocket::{Request, Responder, async_trait, http::Header, request::{FromRequest, Outcome}};
+
+use crate::{LatestData, data_source::Negotiated, manager_and_data::{DataPoint, ServedValue}};
+
+/// The tags a request was sent with in its `If-None-Match` header, if any.
+pub struct IfNoneMatch(Option<String>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for IfNoneMatch {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let tags = request.headers().get_one("If-None-Match").map(str::to_string);
+        Outcome::Success(IfNoneMatch(tags))
+    }
+}
+
+impl IfNoneMatch {
+    /// Whether `etag` is one of the tags the request was sent with. Weak tags match their strong counterparts,
+    /// and `*` matches every tag.
+    pub fn matches(&self, etag: &str) -> bool {
+        let Some(tags) = &self.0 else {
+            return false;
+        };
+
+        tags.split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+    }
+}
+
+/// Returns the entity tag of `data_point`, which changes whenever its value or timestamp does.
+pub fn etag(data_point: &DataPoint<ServedValue>) -> String {
+    let mut hasher = DefaultHasher::new();
+    data_point.timestamp.hash(&mut hasher);
+    // Not every value can be hashed, like floats, but their serialized form can.
+    serde_json::to_string(&data_point.value).unwrap_or_default().hash(&mut hasher);
+
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// The `Florust-Stale` header of responses with a last known good value, served while the data source's
+/// manager reloads.
+pub struct Stale;
+
+impl From<Stale> for Header<'static> {
+    fn from(_: Stale) -> Self {
+        Header::new("Florust-Stale", "reloading")
+    }
+}
+
+/// A responder for the latest value of a data source, tagged with an `ETag` so clients polling it can ask to
+/// only be sent values they don't have yet.
+#[derive(Responder)]
+pub enum LatestResponder {
+    #[response(status = 200)]
+    Some(Negotiated<DataPoint<ServedValue>>, Header<'static>),
+    #[response(status = 200)]
+    Stale(Negotiated<DataPoint<ServedValue>>, Header<'static>, Stale),
+    #[response(status = 204)]
+    None(()),
+    #[response(status = 304)]
+    NotModified((), Header<'static>)
+}
+
+impl LatestResponder {
+    /// Responds with the latest value, or without it if the request was sent with its tag in `If-None-Match`.
+    pub fn new(latest: LatestData, if_none_match: &IfNoneMatch) -> Self {
+        let Some(data_point) = latest.data_point else {
+            return LatestResponder::None(());
+        };
+
+        let etag = etag(&data_point);
+        if if_none_match.matches(&etag) {
+            LatestResponder::NotModified((), Header::new("ETag", etag))
+        }
+        else if latest.stale {
+            LatestResponder::Stale(Negotiated(data_point), Header::new("ETag", etag), Stale)
+        }
+        else {
+            LatestResponder::Some(Negotiated(data_point), Header::new("ETag", etag))
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use rocket::response::stream::TextStream;
+
+use serde_json::Value;
+
+use crate::manager_and_data::{DataPoint, DataType, ManagerAndData, ServedValue};
+
+/// Header row of exported CSV files.
+const CSV_HEADER: &str = "timestamp,value\n";
+
+/// Streams `history` as CSV, with a `timestamp` and a `value` column. Values are written the way the manager
+/// serves them, or formatted using its [`value_to_string`](ManagerAndData::value_to_string) if it doesn't
+/// serialize values itself. Rows are formatted as they're sent, so the whole file is never held in memory at
+/// once.
+pub fn csv_export(manager: &dyn ManagerAndData, history: Vec<DataPoint<DataType>>) -> TextStream![String + '_] {
+    TextStream! {
+        yield CSV_HEADER.to_string();
+        for data_point in history {
+            let value = match manager.serve_value(data_point.value) {
+                ServedValue::Serialized(_, Value::String(value)) => value,
+                ServedValue::Serialized(_, value) => value.to_string(),
+                ServedValue::Logged(value) => manager.value_to_string(&value)
+            };
+            yield format!("{},{}\n", data_point.timestamp.to_rfc3339(), csv_field(&value));
+        }
+    }
+}
+
+/// Quotes `field` if it contains characters that would otherwise break the row it's in, like the commas
+/// separating the elements of vectors.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
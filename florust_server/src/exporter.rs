@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use rocket::async_trait;
+use thiserror::Error;
+
+use crate::manager_and_data::{DataType, NamespacedId};
+
+#[derive(Error, Debug)]
+#[cfg_attr(not(feature = "mqtt_export"), allow(dead_code))]
+pub enum ExportError {
+    #[error("Exporter failed to connect: {0}")]
+    Connect(String),
+    #[error("Exporter failed to export value: {0}")]
+    Export(String)
+}
+
+pub type Result<T> = std::result::Result<T, ExportError>;
+
+/// A trait defining a place every value produced by a data source manager is republished to as soon as it's
+/// logged, like a message broker other systems subscribe to. Unlike a [`StorageBackend`](crate::storage::StorageBackend),
+/// values are only passed on, never read back.
+#[async_trait]
+pub trait Exporter: Send + Sync {
+    /// Starts the work the exporter does in the background, like keeping up its connection. Called once the
+    /// server has started, as background tasks can't be spawned before then. Does nothing by default.
+    fn start(&self) {}
+
+    /// Exports `value`, produced by the manager with the id `manager_id` for the data source `source`, which
+    /// was measured at `ts`. Exporting shouldn't wait on the place values are exported to, as updates aren't
+    /// answered until every exporter is done.
+    async fn export(&self, manager_id: &str, source: &NamespacedId, value: &DataType, ts: DateTime<Utc>) -> Result<()>;
+}
@@ -0,0 +1,46 @@
+use florust_common::server::FlorustServerPluginError;
+use rocket::{get, State, Responder, serde::Serialize};
+
+use crate::{FlorustState, auth::ReadAccess, data_source::Negotiated, manager_and_data::NamespacedId};
+
+/// The health of a single loaded manager.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ManagerHealth {
+    pub manager_id: &'static str,
+    pub library_loaded: bool,
+    pub registered_sources: usize,
+    /// Registered data sources that went too long without an update, going by the manager's expected interval.
+    /// They don't make the manager unhealthy, as it's the data sources that stopped sending updates.
+    pub stale_sources: Vec<NamespacedId>,
+    pub healthy: bool
+}
+
+/// The health of the server, which is healthy only if every manager is. Plugins that failed to load at
+/// startup are listed, but don't make the server unhealthy, as it keeps serving the plugins that did load.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct HealthReport<'a> {
+    pub healthy: bool,
+    pub managers: Vec<ManagerHealth>,
+    pub plugin_failures: &'a [FlorustServerPluginError]
+}
+
+#[derive(Responder)]
+pub enum HealthResponder<'a> {
+    #[response(status = 200)]
+    Healthy(Negotiated<HealthReport<'a>>),
+    #[response(status = 503)]
+    Unhealthy(Negotiated<HealthReport<'a>>)
+}
+
+#[get("/health")]
+pub async fn health(_access: ReadAccess, state: &State<FlorustState>) -> HealthResponder<'_> {
+    let report = state.health().await;
+    if report.healthy {
+        HealthResponder::Healthy(Negotiated(report))
+    }
+    else {
+        HealthResponder::Unhealthy(Negotiated(report))
+    }
+}
@@ -0,0 +1,185 @@
+use std::{collections::{HashMap, VecDeque}, convert::Infallible, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use rocket::{
+    Request,
+    async_trait,
+    request::{FromRequest, Outcome},
+    tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard}
+};
+
+use crate::manager_and_data::{DataType, NamespacedId};
+
+/// Name of the header clients send to make an update idempotent.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// How long idempotency keys are remembered for, unless the server config sets its own `idempotency_ttl`.
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(300);
+
+/// How many idempotency keys are remembered at most, unless the server config sets its own
+/// `idempotency_max_keys`.
+pub const DEFAULT_IDEMPOTENCY_MAX_KEYS: usize = 10_000;
+
+/// The idempotency key a request was sent with, if any, taken from its `Idempotency-Key` header.
+pub struct IdempotencyKey(pub Option<String>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let key = request.headers().get_one(IDEMPOTENCY_KEY_HEADER).map(str::to_string);
+        Outcome::Success(IdempotencyKey(key))
+    }
+}
+
+/// Identifies an update by the data source that sent it and the idempotency key it was sent with.
+type CacheKey = (&'static str, NamespacedId, String);
+
+/// Remembers the values logged by recent updates that were sent with an idempotency key, so a data source
+/// that retries an update gets back the value logged the first time, instead of logging it twice. Keys are
+/// forgotten once they're older than the TTL, or once too many newer keys are remembered.
+pub struct IdempotencyCache {
+    ttl: Duration,
+    max_keys: usize,
+    entries: Mutex<CacheEntries>,
+    /// A lock for every key an update is being passed to its manager with, so retries of the update wait for
+    /// it to finish instead of reaching the manager at the same time.
+    in_flight: Mutex<HashMap<CacheKey, Arc<AsyncMutex<()>>>>
+}
+
+/// Holds the lock of an idempotency key until it's dropped, forgetting the lock once nothing else is waiting
+/// for it.
+pub struct KeyLock<'a> {
+    cache: &'a IdempotencyCache,
+    key: CacheKey,
+    guard: Option<OwnedMutexGuard<()>>
+}
+
+impl Drop for KeyLock<'_> {
+    fn drop(&mut self) {
+        self.guard.take();
+
+        let mut in_flight = self.cache.in_flight.lock().unwrap_or_else(|err| err.into_inner());
+        if in_flight.get(&self.key).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    values: HashMap<CacheKey, DataType>,
+    /// Every remembered key, oldest first, along with when it was remembered.
+    order: VecDeque<(Instant, CacheKey)>
+}
+
+impl CacheEntries {
+    /// Forgets keys until none are older than `ttl`, and at most `max_keys` are left.
+    fn evict(&mut self, now: Instant, ttl: Duration, max_keys: usize) {
+        while let Some((inserted, _)) = self.order.front() {
+            if now.duration_since(*inserted) < ttl && self.order.len() <= max_keys {
+                break;
+            }
+
+            if let Some((_, key)) = self.order.pop_front() {
+                self.values.remove(&key);
+            }
+        }
+    }
+}
+
+impl IdempotencyCache {
+    pub fn new(ttl: Duration, max_keys: usize) -> IdempotencyCache {
+        IdempotencyCache {
+            ttl,
+            max_keys,
+            entries: Mutex::new(CacheEntries::default()),
+            in_flight: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Waits until no other update the data source `source` sent with `key` is being passed to its manager,
+    /// and keeps others from being passed to it until the returned lock is dropped.
+    pub async fn lock(&self, manager_id: &'static str, source: &NamespacedId, key: &str) -> KeyLock<'_> {
+        let cache_key = (manager_id, source.clone(), key.to_string());
+        let lock = self.in_flight
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .entry(cache_key.clone())
+            .or_default()
+            .clone();
+
+        KeyLock {
+            cache: self,
+            key: cache_key,
+            guard: Some(lock.lock_owned().await)
+        }
+    }
+
+    /// Returns the value logged by the update the data source `source` sent with `key`, if it's remembered.
+    pub fn get(&self, manager_id: &'static str, source: &NamespacedId, key: &str) -> Option<DataType> {
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        entries.evict(Instant::now(), self.ttl, self.max_keys);
+        entries.values.get(&(manager_id, source.clone(), key.to_string())).cloned()
+    }
+
+    /// Remembers that the update the data source `source` sent with `key` logged `value`.
+    pub fn insert(&self, manager_id: &'static str, source: &NamespacedId, key: &str, value: DataType) {
+        if self.max_keys == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        let cache_key = (manager_id, source.clone(), key.to_string());
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        if entries.values.insert(cache_key.clone(), value).is_none() {
+            entries.order.push_back((now, cache_key));
+        }
+        entries.evict(now, self.ttl, self.max_keys);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rocket::tokio::{self, time};
+
+    use super::*;
+
+    #[rocket::async_test]
+    async fn concurrent_retries_reach_the_manager_once() {
+        let cache = Arc::new(IdempotencyCache::new(DEFAULT_IDEMPOTENCY_TTL, DEFAULT_IDEMPOTENCY_MAX_KEYS));
+        let updates = Arc::new(AtomicUsize::new(0));
+        let source = NamespacedId::new("", "kitchen");
+
+        let retries = (0..3).map(|_| {
+            let (cache, updates, source) = (cache.clone(), updates.clone(), source.clone());
+            tokio::spawn(async move {
+                let _lock = cache.lock("manager", &source, "key").await;
+                if let Some(value) = cache.get("manager", &source, "key") {
+                    return value;
+                }
+
+                time::sleep(Duration::from_millis(20)).await;
+                let value = DataType::UInteger(updates.fetch_add(1, Ordering::SeqCst) as u64);
+                cache.insert("manager", &source, "key", value.clone());
+                value
+            })
+        }).collect::<Vec<_>>();
+
+        for retry in retries {
+            assert!(matches!(retry.await.unwrap(), DataType::UInteger(0)));
+        }
+        assert_eq!(updates.load(Ordering::SeqCst), 1);
+        assert!(cache.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn keys_of_other_sources_dont_wait() {
+        let cache = IdempotencyCache::new(DEFAULT_IDEMPOTENCY_TTL, DEFAULT_IDEMPOTENCY_MAX_KEYS);
+        let _kitchen = cache.lock("manager", &NamespacedId::new("", "kitchen"), "key").await;
+        let garage = NamespacedId::new("", "garage");
+        assert!(time::timeout(Duration::from_secs(1), cache.lock("manager", &garage, "key")).await.is_ok());
+    }
+}
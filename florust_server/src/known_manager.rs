@@ -0,0 +1,62 @@
+use rocket::{Either, Request, async_trait, catch, http::Status, request::{FromRequest, Outcome}, response::status::NotFound};
+use tracing::warn;
+
+use florust_common::server::FlorustServerPluginError;
+
+use crate::{FlorustState, data_source::DataSourceError, manager_and_data::ManagerAndDataError, upload::manager_id};
+
+/// The id of a manager that isn't loaded, which a request was rejected for by [`KnownManager`].
+struct UnknownManager(Option<String>);
+
+/// A request guard that, in strict mode, rejects requests for managers that aren't loaded with a
+/// `404 Not Found` response, before anything else about the request is looked at, like its body or the data
+/// source it's for. Requests to routes without a `<manager_id>` segment are let through.
+pub struct KnownManager;
+
+#[async_trait]
+impl<'r> FromRequest<'r> for KnownManager {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(state) = request.rocket().state::<FlorustState>() else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+        if !state.strict_managers {
+            return Outcome::Success(KnownManager);
+        }
+
+        let Some(manager_id) = manager_id(request) else {
+            return Outcome::Success(KnownManager);
+        };
+        if state.manager_exists(manager_id) {
+            return Outcome::Success(KnownManager);
+        }
+
+        let mut loaded = state.managers_and_data.keys().copied().collect::<Vec<_>>();
+        loaded.sort_unstable();
+        warn!(
+            "Rejected {} {} for manager ({}), which isn't loaded, loaded managers: {}",
+            request.method(),
+            request.uri().path(),
+            manager_id,
+            loaded.join(", ")
+        );
+        request.local_cache(|| UnknownManager(Some(manager_id.to_string())));
+        Outcome::Error((Status::NotFound, ()))
+    }
+}
+
+/// Responds to requests [`KnownManager`] rejected with the same error that's returned when a manager that
+/// isn't loaded is looked up, so clients can tell it apart from a data source that doesn't exist. Other
+/// requests that aren't found get an empty response. Only registered in strict mode.
+#[catch(404)]
+pub fn unknown_manager(request: &Request<'_>) -> Either<DataSourceError, NotFound<()>> {
+    match request.local_cache(|| UnknownManager(None)) {
+        UnknownManager(Some(manager_id)) => Either::Left(DataSourceError::from(
+            ManagerAndDataError::DataSourceManager(
+                FlorustServerPluginError::DataSourceManagerDoesntExist(manager_id.clone())
+            )
+        )),
+        UnknownManager(None) => Either::Right(NotFound(()))
+    }
+}
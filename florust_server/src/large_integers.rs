@@ -0,0 +1,56 @@
+use std::{fmt::Display, result, str::FromStr, sync::OnceLock};
+
+use rocket::serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// How 64-bit integers are encoded in JSON. Clients that read numbers as doubles, like JavaScript, lose
+/// precision on integers above 2^53. Formats that keep integers exact, like CBOR, always encode them as numbers.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+pub enum IntegerEncoding {
+    /// As numbers, like `9007199254740993`.
+    #[default]
+    Number,
+    /// As strings holding the number, like `"9007199254740993"`.
+    String
+}
+
+/// The encoding configured for the server, set once while it starts.
+static ENCODING: OnceLock<IntegerEncoding> = OnceLock::new();
+
+/// Sets how 64-bit integers are encoded in JSON. Only the first encoding set is used.
+pub fn set_encoding(encoding: IntegerEncoding) {
+    let _ = ENCODING.set(encoding);
+}
+
+fn encoding() -> IntegerEncoding {
+    ENCODING.get().copied().unwrap_or_default()
+}
+
+/// Every way an integer may be encoded, as either encoding is accepted when reading one back.
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+enum Encoded<T> {
+    Number(T),
+    String(String)
+}
+
+/// For `#[serde(with)]` on `i64` and `u64` fields.
+pub fn serialize<T: Serialize + Display, S: Serializer>(value: &T, serializer: S) -> result::Result<S::Ok, S::Error> {
+    match encoding() {
+        IntegerEncoding::String if serializer.is_human_readable() => serializer.collect_str(value),
+        _ => value.serialize(serializer)
+    }
+}
+
+/// Reads back integers in either encoding.
+pub fn deserialize<'de, T, D>(deserializer: D) -> result::Result<T, D::Error>
+where
+    T: Deserialize<'de> + FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>
+{
+    match Encoded::deserialize(deserializer)? {
+        Encoded::Number(value) => Ok(value),
+        Encoded::String(value) => value.parse().map_err(D::Error::custom)
+    }
+}
@@ -1,16 +1,94 @@
+mod admin;
+mod aggregate;
+mod alert;
+mod auth;
 mod circular_vec;
+mod client_auth;
+mod concurrency;
+mod config;
+mod config_watcher;
+mod cors;
 mod data_source;
+mod dependency;
+mod etag;
+mod export;
+mod exporter;
+mod health;
+mod idempotency;
+mod known_manager;
+mod large_integers;
+mod manager;
 mod manager_and_data;
+mod metrics;
+mod non_finite;
+mod pagination;
+mod rate_limit;
+mod reaper;
+mod request_timer;
+mod schema;
+mod signing;
+mod source_filter;
+mod storage;
+mod stream;
+mod trace;
+mod transform;
+mod upload;
+mod write_buffer;
+#[cfg(feature = "sqlite_storage")]
+mod sqlite_storage;
+#[cfg(feature = "mqtt_export")]
+mod mqtt_exporter;
+#[cfg(all(unix, feature = "unix_socket"))]
+mod unix_socket;
 #[cfg(any(feature = "iinteger_default_plugin", feature = "uinteger_default_plugin", feature = "float_default_plugin"))]
 mod default_plugins;
 
-use log::{info, warn};
-use manager_and_data::{ManagerAndDataError, DataType, IIntegerManagerAndData, UIntegerManagerAndData, FloatManagerAndData};
-use rocket::{launch, routes, serde::{Serialize, Deserialize}};
+use tracing::{debug, info, instrument, warn, Span};
+use rocket::futures::future::join_all;
+use manager_and_data::{BulkRegistration, BulkRegistrationResult, ManagerAndDataError, DataType, DataPoint, DeregisterAllReport, LastError, LatestBulkResult, ManagerInfo, ManagerStats, ManagerSummary, NamespacedId, ReloadReport, ReplayedUpdate, ServedValue, UntaggedServedValue, IIntegerManagerAndData, UIntegerManagerAndData, FloatManagerAndData, Float32ManagerAndData, BooleanManagerAndData, StringManagerAndData, FloatVecManagerAndData, I128ManagerAndData, U128ManagerAndData};
+use rocket::{catchers, launch, routes, fairing::AdHoc, http::{ContentType, MediaType}, response::stream::TextStream, serde::{Serialize, Deserialize}};
 use toml::Table;
-use std::{collections::HashMap, sync::Arc, fs::{read_dir, read_to_string}};
+use aggregate::{Aggregation, Downsample};
+use alert::Alerts;
+use auth::{ApiKeys, SharedApiKeys};
+use client_auth::{ClientAuth, ClientIdentity};
+use concurrency::{ConcurrencyLimit, ConcurrencyLimiter};
+use config::FlorustServerConfig;
+use cors::Cors;
+use exporter::Exporter;
+use health::{HealthReport, ManagerHealth};
+use idempotency::{DEFAULT_IDEMPOTENCY_TTL, IdempotencyCache};
+use metrics::Metrics;
+use pagination::{HistoryPage, PageQuery};
+use rate_limit::{RateLimiter, retry_after_secs};
+use request_timer::RequestTimer;
+use signing::SigningSecrets;
+use source_filter::SourceFilters;
+use storage::StorageBackend;
+use stream::{ManagerStream, MAX_WAIT_TIMEOUT, StreamedDataPoint};
+use transform::{SharedTransforms, Transforms};
+use rocket::tokio::{io::AsyncRead, sync::broadcast};
+use std::{collections::{BTreeSet, HashMap, HashSet}, env::{self, consts::DLL_EXTENSION}, ops::Deref, process, sync::{Arc, RwLock, atomic::{AtomicUsize, Ordering}}, fs::{self, File, OpenOptions, read_dir, read_to_string}, io, path::{Path, PathBuf}, time::Duration};
 
-use florust_common::server::{FlorustServerPluginError, CreateIIntegerDataSourceManager, CreateUIntegerDataSourceManager, CreateFloatDataSourceManager};
+use florust_common::server::{
+    capabilities,
+    FlorustServerPluginError,
+    FLORUST_ABI_VERSION,
+    FlorustAbiVersion,
+    DefaultConfig,
+    PluginDependencies,
+    CreateDataSourceManager,
+    DataSourceManagerEntries,
+    IIntegerDataSourceManager,
+    UIntegerDataSourceManager,
+    FloatDataSourceManager,
+    Float32DataSourceManager,
+    BooleanDataSourceManager,
+    StringDataSourceManager,
+    FloatVecDataSourceManager,
+    I128DataSourceManager,
+    U128DataSourceManager
+};
 
 #[cfg(feature = "iinteger_default_plugin")]
 use default_plugins::DefaultIIntegerDataManager;
@@ -23,13 +101,133 @@ use default_plugins::DefaultFloatDataManager;
 
 type BoxedManagerAndData = Box<dyn manager_and_data::ManagerAndData>;
 
-pub struct FlorustState {
+/// A plugin that was successfully loaded, along with the path of its config file if it has one.
+struct LoadedPlugin {
+    manager_and_data: BoxedManagerAndData,
+    /// The dir or library the plugin was loaded from, if it came from the plugins dir.
+    plugin_path: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+    /// The id the environment variables overriding the manager's config are named after, see
+    /// [`config::with_env_overrides`].
+    env_id: Option<String>,
+    max_updates_per_second: Option<f64>,
+    idle_timeout: Option<Duration>,
+    max_upload_size: usize,
+    concurrency_limit: Option<ConcurrencyLimit>,
+    auto_register: bool
+}
+
+/// Creates the managers of a plugin, adding why to the failures passed to it if any fail to be created.
+type CreatePlugin = Box<dyn FnOnce(&mut Vec<FlorustServerPluginError>) -> Vec<LoadedPlugin>>;
+
+/// A plugin whose library was opened, but whose managers haven't been created yet, so the managers they depend
+/// on can be created first.
+struct PendingPlugin {
+    /// The ids of the plugin's managers, if the plugin lists them. The id of a single manager isn't known until
+    /// it's created.
+    manager_ids: Vec<String>,
+    /// The plugin's manager ids if they're known, or its name otherwise.
+    name: String,
+    /// The ids the environment variables overriding the configs of the plugin's managers are named after, see
+    /// [`config::with_env_overrides`].
+    env_ids: Vec<String>,
+    /// The ids of the managers the plugin's managers depend on, see [`dependency::load_stages`].
+    dependencies: Vec<String>,
+    create: CreatePlugin
+}
+
+impl PendingPlugin {
+    /// A plugin whose managers were created along with the server, like the default plugins.
+    fn created(plugin: LoadedPlugin) -> PendingPlugin {
+        let manager_id = plugin.manager_and_data.manager_id().to_string();
+        PendingPlugin {
+            manager_ids: vec![manager_id.clone()],
+            name: manager_id,
+            env_ids: Vec::new(),
+            dependencies: Vec::new(),
+            create: Box::new(move |_| vec![plugin])
+        }
+    }
+
+    fn create(self, failures: &mut Vec<FlorustServerPluginError>) -> Vec<LoadedPlugin> {
+        (self.create)(failures)
+    }
+}
+
+impl dependency::Dependent for PendingPlugin {
+    fn manager_ids(&self) -> &[String] {
+        &self.manager_ids
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dependencies(&self) -> &[String] {
+        &self.dependencies
+    }
+}
+
+/// The state shared by every request. It's cheap to clone, so tasks running outside of requests, like the Unix
+/// socket listener, can hold on to it.
+#[derive(Clone)]
+pub struct FlorustState(Arc<FlorustStateInner>);
+
+/// The latest value of a data source, along with whether it's the last known good value served while its
+/// manager reloads, which the manager may have replaced by the time the reload is done.
+pub struct LatestData {
+    pub data_point: Option<DataPoint<ServedValue>>,
+    pub stale: bool
+}
+
+impl Deref for FlorustState {
+    type Target = FlorustStateInner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+pub struct FlorustStateInner {
     managers_and_data: Arc<
         HashMap<
             &'static str,
             BoxedManagerAndData,
         >,
     >,
+    storage: Option<Box<dyn StorageBackend>>,
+    /// Every exporter values are republished to once they're logged.
+    exporters: Vec<Box<dyn Exporter>>,
+    streams: HashMap<&'static str, ManagerStream>,
+    alerts: Alerts,
+    metrics: Arc<Metrics>,
+    rate_limiter: Arc<RateLimiter>,
+    signing_secrets: Arc<SigningSecrets>,
+    concurrency_limiter: ConcurrencyLimiter,
+    idempotency: IdempotencyCache,
+    /// The maximum size of uploads to every manager, in bytes.
+    upload_limits: HashMap<&'static str, usize>,
+    max_upload_size: usize,
+    max_decompressed_size: usize,
+    /// The managers that register unknown data sources on their first update, instead of rejecting it.
+    auto_register: HashSet<&'static str>,
+    /// Why every plugin that didn't load at startup failed to load.
+    plugin_failures: Vec<FlorustServerPluginError>,
+    /// The ids of the managers in the stages they were brought up in, every manager coming after the managers it
+    /// depends on.
+    load_stages: Vec<Vec<&'static str>>,
+    /// The dir or library every manager loaded from the plugins dir was loaded from, to find them by when their
+    /// plugin is reloaded.
+    plugin_paths: HashMap<&'static str, PathBuf>,
+    /// The managers whose plugin is being replaced by a reload, whose latest values are served from their
+    /// stream until it's done.
+    reloading: RwLock<HashSet<&'static str>>,
+    client_auth: ClientAuth,
+    source_filters: SourceFilters,
+    api_keys: SharedApiKeys,
+    /// Whether requests for managers that aren't loaded are rejected before anything else is looked at, see
+    /// [`known_manager::KnownManager`].
+    strict_managers: bool,
 }
 
 impl FlorustState {
@@ -48,55 +246,855 @@ impl FlorustState {
             )
     }
 
-    pub async fn register_data_source(&self, manager_id: &str, data_source_id: String, data: Option<&[u8]>) -> manager_and_data::Result<()> {
-        if let Some(data) = data {
-            self.get_manager_or_err(manager_id)?
-                .register_with_data(data_source_id, data).await
+    /// Returns the maximum number of bytes an upload to the manager with the id `manager_id` may be.
+    pub fn max_upload_size(&self, manager_id: &str) -> usize {
+        self.upload_limits.get(manager_id).copied().unwrap_or(self.max_upload_size)
+    }
+
+    /// Returns the canonical form of the data source id `data_source_id`, as decided by its manager.
+    pub fn normalize_id(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<NamespacedId> {
+        Ok(self.get_manager_or_err(manager_id)?.normalize_id(data_source_id))
+    }
+
+    /// Checks that the client identified by `identity` may act on behalf of the data source `data_source_id`.
+    pub fn check_client(&self, manager_id: &str, identity: &ClientIdentity, data_source_id: &NamespacedId) -> manager_and_data::Result<()> {
+        self.client_auth.check(manager_id, identity, &self.normalize_id(manager_id, data_source_id)?)
+    }
+
+    /// Checks that `data`, an update from the data source `data_source_id`, was signed with the secret the data
+    /// source registered with, if it registered with one.
+    pub fn check_signature(&self, manager_id: &str, data_source_id: &NamespacedId, data: &[u8], signature: Option<&str>) -> manager_and_data::Result<()> {
+        self.signing_secrets.verify(manager_id, &self.normalize_id(manager_id, data_source_id)?, data, signature)
+    }
+
+    /// Whether updates from the data source `data_source_id` must be signed.
+    pub fn requires_signature(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<bool> {
+        Ok(self.signing_secrets.requires_signature(manager_id, &self.normalize_id(manager_id, data_source_id)?))
+    }
+
+    /// Checks that an update sent with `content_type` is of the content type the manager with the id `manager_id`
+    /// expects, if it expects one. Parameters like `charset` are ignored.
+    pub fn check_content_type(&self, manager_id: &str, content_type: Option<&ContentType>) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let Some(expected) = manager.expected_content_type() else {
+            return Ok(());
+        };
+
+        let matches = match (MediaType::parse_flexible(expected), content_type) {
+            (Some(expected), Some(actual)) => expected == *actual.media_type(),
+            _ => false
+        };
+
+        if matches {
+            Ok(())
         }
         else {
-            self.get_manager_or_err(manager_id)?
-                .register(data_source_id).await
+            Err(ManagerAndDataError::UnsupportedContentType {
+                expected: expected.to_string(),
+                actual: content_type.map(ContentType::to_string)
+            })
+        }
+    }
+
+    /// Checks that the manager with the id `manager_id` produces values of type `expected`, if a type is
+    /// expected at all.
+    pub fn check_data_type(&self, manager_id: &str, expected: Option<&str>) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        match expected {
+            Some(expected) if expected != manager.data_type() => Err(
+                ManagerAndDataError::DataSourceManager(
+                    FlorustServerPluginError::ManagerTypeMismatch {
+                        manager_id: manager_id.to_string(),
+                        expected: expected.to_string(),
+                        actual: manager.data_type().to_string()
+                    }
+                )
+            ),
+            _ => Ok(())
+        }
+    }
+
+    /// Checks that the manager with the id `manager_id` has every capability in `required`, named as in
+    /// [`capabilities::NAMES`].
+    pub fn check_capabilities(&self, manager_id: &str, required: &[String]) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let mut missing = Vec::new();
+        for name in required {
+            let (capability, _) = capabilities::NAMES.iter()
+                .find(|(_, known)| known == name)
+                .ok_or_else(|| ManagerAndDataError::InvalidUpload(format!("unknown capability ({name})")))?;
+            if manager.capabilities() & capability == 0 {
+                missing.push(name.clone());
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ManagerAndDataError::MissingCapabilities {
+                manager_id: manager_id.to_string(),
+                missing
+            })
+        }
+    }
+
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id), err(Display, level = "warn"))]
+    pub async fn register_data_source(
+        &self,
+        manager_id: &str,
+        data_source_id: NamespacedId,
+        data: Option<&[u8]>,
+        signing_secret: Option<&str>,
+        tags: BTreeSet<String>
+    ) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = manager.normalize_id(&data_source_id);
+        self.source_filters.check(manager_id, &data_source_id)?;
+        let initial_value = match data {
+            Some(data) => manager.register_with_data(data_source_id.clone(), data, tags).await?,
+            None => manager.register(data_source_id.clone(), tags).await?
+        };
+
+        info!("Data source registered");
+        self.signing_secrets.set(manager_id, &data_source_id, signing_secret);
+        self.metrics.sources_registered(manager_id, 1);
+        self.initial_value_logged(manager_id, &data_source_id, initial_value).await;
+        Ok(())
+    }
+
+    /// Registers the data source `data_source_id` with `data` and tagged with `tags`, and logs `data` as its first
+    /// update, returning the value it logged. The registration is rolled back if the update fails. The update is
+    /// rate and concurrency limited like any other update.
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id), err(Display, level = "warn"))]
+    pub async fn register_with_initial_value(
+        &self,
+        manager_id: &str,
+        data_source_id: NamespacedId,
+        data: &[u8],
+        signing_secret: Option<&str>,
+        tags: BTreeSet<String>
+    ) -> manager_and_data::Result<DataType> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = manager.normalize_id(&data_source_id);
+        self.source_filters.check(manager_id, &data_source_id)?;
+        self.check_rate_limit(manager_id, &data_source_id)?;
+        let permit = self.concurrency_limiter.acquire(manager_id).await?;
+
+        let data_point = manager
+            .register_with_initial_value(data_source_id.clone(), data, tags).await
+            .inspect_err(|_| self.metrics.update_failed(manager_id))?;
+        drop(permit);
+
+        info!("Data source registered");
+        self.signing_secrets.set(manager_id, &data_source_id, signing_secret);
+        self.metrics.sources_registered(manager_id, 1);
+        self.metrics.updates_logged(manager_id, &data_source_id, 1);
+        let value = data_point.value.clone();
+        self.data_logged(manager_id, &data_source_id, data_point).await;
+        Ok(value)
+    }
+
+    /// Registers every data source in `registrations`, on behalf of the client identified by `identity`. Data
+    /// sources are registered one by one, and stay registered even if others fail to register.
+    pub async fn register_bulk(
+        &self,
+        manager_id: &str,
+        identity: &ClientIdentity,
+        registrations: Vec<BulkRegistration>
+    ) -> manager_and_data::Result<Vec<BulkRegistrationResult>> {
+        self.get_manager_or_err(manager_id)?;
+
+        let mut results = Vec::with_capacity(registrations.len());
+        for registration in registrations {
+            let source = self.normalize_id(manager_id, &NamespacedId::new(registration.namespace, registration.id))?;
+            let checked = self.check_client(manager_id, identity, &source)
+                .and_then(|_| self.check_capabilities(manager_id, &registration.requires))
+                .and_then(|_| match registration.tags.contains("") {
+                    true => Err(ManagerAndDataError::InvalidUpload("tags must not be empty".to_string())),
+                    false => Ok(())
+                });
+            let registered = match checked {
+                Ok(()) => self.register_data_source(manager_id, source.clone(), registration.data.as_deref(), registration.signing_secret.as_deref(), registration.tags).await,
+                Err(err) => Err(err)
+            };
+
+            results.push(BulkRegistrationResult {
+                source,
+                registered: registered.is_ok(),
+                error: registered.err()
+            });
         }
+
+        Ok(results)
     }
 
-    pub async fn deregister_data_source(&self, manager_id: &str, data_source_id: &str, data: Option<&[u8]>) -> manager_and_data::Result<()> {
+    /// Checks whether the data source `data_source_id` could register with `data` on behalf of the client
+    /// identified by `identity`, without registering it.
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id), err(Display, level = "warn"))]
+    pub async fn can_register(
+        &self,
+        manager_id: &str,
+        identity: &ClientIdentity,
+        data_source_id: &NamespacedId,
+        data: Option<&[u8]>
+    ) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = &manager.normalize_id(data_source_id);
+        self.client_auth.check(manager_id, identity, data_source_id)?;
+        self.source_filters.check(manager_id, data_source_id)?;
+        manager.can_register(data_source_id, data).await
+    }
+
+    /// Deregisters the data source `data_source_id`. See [`ManagerAndData::deregister`] for what `force` does
+    /// when its manager fails to deregister it.
+    ///
+    /// [`ManagerAndData::deregister`]: manager_and_data::ManagerAndData::deregister
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id), err(Display, level = "warn"))]
+    pub async fn deregister_data_source(
+        &self,
+        manager_id: &str,
+        data_source_id: &NamespacedId,
+        data: Option<&[u8]>,
+        force: bool
+    ) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = &manager.normalize_id(data_source_id);
         if let Some(data) = data {
-            self.get_manager_or_err(manager_id)?
-                .deregister_with_data(data_source_id, data).await
+            manager.deregister_with_data(data_source_id, data, force).await?;
         }
         else {
-            self.get_manager_or_err(manager_id)?
-                .deregister(data_source_id).await
+            manager.deregister(data_source_id, force).await?;
         }
+
+        info!("Data source deregistered");
+        self.rate_limiter.forget(manager_id, data_source_id);
+        self.signing_secrets.forget(manager_id, data_source_id);
+        self.metrics.forget(manager_id, data_source_id);
+        self.metrics.sources_registered(manager_id, -1);
+        Ok(())
     }
 
-    pub async fn update_data(&self, manager_id: &str, data_source_id: &str, data: &[u8]) -> manager_and_data::Result<()> {
-        self.managers_and_data
+    /// Deregisters every data source of the manager with the id `manager_id` the client identified by `identity`
+    /// may act on behalf of. Data sources bound to another client stay registered, and are reported as failed.
+    /// Nothing is deregistered unless `confirm` is set, so it can't be done by accident.
+    #[instrument(skip_all, fields(manager_id = %manager_id), err(Display, level = "warn"))]
+    pub async fn deregister_all(&self, manager_id: &str, identity: &ClientIdentity, confirm: bool) -> manager_and_data::Result<DeregisterAllReport> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        if !confirm {
+            return Err(ManagerAndDataError::ConfirmationRequired);
+        }
+
+        let report = manager.deregister_all(&|source| self.client_auth.check(manager_id, identity, source)).await;
+        for data_source_id in &report.deregistered {
+            self.rate_limiter.forget(manager_id, data_source_id);
+            self.signing_secrets.forget(manager_id, data_source_id);
+            self.metrics.forget(manager_id, data_source_id);
+        }
+        self.metrics.sources_registered(manager_id, -(report.deregistered.len() as i64));
+        Ok(report)
+    }
+
+    /// Resets the running state the manager with the id `manager_id` keeps for the data source `data_source_id`.
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id), err(Display, level = "warn"))]
+    pub async fn reset(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        manager.reset(&manager.normalize_id(data_source_id)).await
+    }
+
+    /// Passes the updates retained for the data source `data_source_id` to the manager with the id `manager_id`
+    /// again, returning what it parses them into now, without logging anything.
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id), err(Display, level = "warn"))]
+    pub async fn replay(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<Vec<ReplayedUpdate>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        manager.replay(&manager.normalize_id(data_source_id)).await
+    }
+
+    /// Makes sure the data source `data_source_id` hasn't exceeded its rate limit.
+    fn check_rate_limit(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<()> {
+        self.rate_limiter
+            .try_acquire(manager_id, data_source_id)
+            .map_err(|wait| ManagerAndDataError::RateLimited {
+                source_id: data_source_id.to_string(),
+                retry_after: retry_after_secs(wait)
+            })
+    }
+
+    /// Registers the data source `data_source_id` before its update is passed on, if it isn't registered and its
+    /// manager registers data sources on their first update. Concurrent first updates only register it once, the
+    /// others find it already registered.
+    async fn auto_register(&self, manager_id: &str, manager: &BoxedManagerAndData, data_source_id: &NamespacedId) -> manager_and_data::Result<()> {
+        if !self.auto_register.contains(manager_id) || manager.is_registered(data_source_id).await {
+            return Ok(());
+        }
+        self.source_filters.check(manager_id, data_source_id)?;
+
+        match manager.register(data_source_id.clone(), BTreeSet::new()).await {
+            Ok(initial_value) => {
+                info!("Data source registered on its first update");
+                self.metrics.sources_registered(manager_id, 1);
+                self.initial_value_logged(manager_id, data_source_id, initial_value).await;
+                Ok(())
+            },
+            Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::DataSourceAlreadyExists(_))) => Ok(()),
+            Err(err) => Err(err)
+        }
+    }
+
+    /// Passes an update to the manager with the id `manager_id`, along with the `traceparent` header it was sent
+    /// with, if any, returning the value it logged.
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id, trace_id), err(Display, level = "warn"))]
+    pub async fn update_data(
+        &self,
+        manager_id: &str,
+        data_source_id: &NamespacedId,
+        data: &[u8],
+        trace_id: Option<&str>,
+        allow_reset: bool
+    ) -> manager_and_data::Result<DataType> {
+        if let Some(trace_id) = trace_id {
+            Span::current().record("trace_id", trace_id);
+        }
+
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = &manager.normalize_id(data_source_id);
+        self.check_rate_limit(manager_id, data_source_id)?;
+        self.auto_register(manager_id, manager, data_source_id).await?;
+        let permit = self.concurrency_limiter.acquire(manager_id).await?;
+
+        let data_point = manager
+            .update_data(data_source_id, data, trace_id, allow_reset).await
+            .inspect_err(|_| self.metrics.update_failed(manager_id))?;
+        drop(permit);
+
+        debug!(value = %manager.value_to_string(&data_point.value), "Data logged");
+        self.metrics.updates_logged(manager_id, data_source_id, 1);
+        let value = data_point.value.clone();
+        self.data_logged(manager_id, data_source_id, data_point).await;
+        Ok(value)
+    }
+
+    /// Passes an update to the manager with the id `manager_id` as it's read from `reader`, returning the value
+    /// it logged.
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id), err(Display, level = "warn"))]
+    pub async fn update_data_stream(
+        &self,
+        manager_id: &str,
+        data_source_id: &NamespacedId,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        allow_reset: bool
+    ) -> manager_and_data::Result<DataType> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = &manager.normalize_id(data_source_id);
+        self.check_rate_limit(manager_id, data_source_id)?;
+        self.auto_register(manager_id, manager, data_source_id).await?;
+        let permit = self.concurrency_limiter.acquire(manager_id).await?;
+
+        let data_point = manager
+            .update_data_stream(data_source_id, reader, allow_reset).await
+            .inspect_err(|_| self.metrics.update_failed(manager_id))?;
+        drop(permit);
+
+        debug!(value = %manager.value_to_string(&data_point.value), "Data logged");
+        self.metrics.updates_logged(manager_id, data_source_id, 1);
+        let value = data_point.value.clone();
+        self.data_logged(manager_id, data_source_id, data_point).await;
+        Ok(value)
+    }
+
+    /// Passes an update sent with the idempotency key `key` to the manager with the id `manager_id`. An update
+    /// retried with a key that's still remembered returns the value logged the first time, without reaching the
+    /// manager again, while one retried before the first has finished waits for it. Updates without a key are
+    /// always passed to the manager.
+    pub async fn update_data_idempotent(
+        &self,
+        manager_id: &str,
+        data_source_id: &NamespacedId,
+        key: Option<&str>,
+        data: &[u8],
+        trace_id: Option<&str>,
+        allow_reset: bool
+    ) -> manager_and_data::Result<DataType> {
+        let Some(key) = key else {
+            return self.update_data(manager_id, data_source_id, data, trace_id, allow_reset).await;
+        };
+
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = &manager.normalize_id(data_source_id);
+        let _lock = self.idempotency.lock(manager.manager_id(), data_source_id, key).await;
+        if let Some(value) = self.idempotency.get(manager.manager_id(), data_source_id, key) {
+            return Ok(value);
+        }
+
+        let value = self.update_data(manager_id, data_source_id, data, trace_id, allow_reset).await?;
+        self.idempotency.insert(manager.manager_id(), data_source_id, key, value.clone());
+        Ok(value)
+    }
+
+    /// Passes a batch of updates to the manager with the id `manager_id`, returning the values it logged.
+    #[instrument(skip_all, fields(manager_id = %manager_id, source_id = %data_source_id), err(Display, level = "warn"))]
+    pub async fn batch_update_data(&self, manager_id: &str, data_source_id: &NamespacedId, data: &[u8], allow_reset: bool) -> manager_and_data::Result<Vec<DataType>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = &manager.normalize_id(data_source_id);
+        self.check_rate_limit(manager_id, data_source_id)?;
+        self.auto_register(manager_id, manager, data_source_id).await?;
+        let permit = self.concurrency_limiter.acquire(manager_id).await?;
+
+        let data_points = manager
+            .batch_update_data(data_source_id, data, allow_reset).await
+            .inspect_err(|_| self.metrics.update_failed(manager_id))?;
+        drop(permit);
+
+        self.metrics.updates_logged(manager_id, data_source_id, data_points.len() as u64);
+        let mut values = Vec::with_capacity(data_points.len());
+        for data_point in data_points {
+            debug!(value = %manager.value_to_string(&data_point.value), "Data logged");
+            values.push(data_point.value.clone());
+            self.data_logged(manager_id, data_source_id, data_point).await;
+        }
+        Ok(values)
+    }
+
+    /// Subscribes to every data point logged by the data sources of the manager with the id `manager_id`.
+    pub fn subscribe(&self, manager_id: &str) -> manager_and_data::Result<broadcast::Receiver<StreamedDataPoint>> {
+        self.streams
             .get(manager_id)
+            .map(ManagerStream::subscribe)
             .ok_or(
                 ManagerAndDataError::DataSourceManager(
-                        FlorustServerPluginError::DataSourceManagerDoesntExist(manager_id.to_string()
-                    )
+                    FlorustServerPluginError::DataSourceManagerDoesntExist(manager_id.to_string())
                 )
-            )?
-            .update_data(data_source_id, data).await
+            )
     }
 
-    pub async fn get_data(&self, manager_id: &str, data_source_id: &str, index: usize) -> manager_and_data::Result<DataType> {
-        self.get_manager_or_err(manager_id)?
-            .get_data(data_source_id, index).await
+    /// Waits for the data source `data_source_id` to log a data point with a sequence number greater than
+    /// `since`, returning right away if it already has, or `None` if it doesn't within `timeout`.
+    pub async fn wait_for_data(
+        &self,
+        manager_id: &str,
+        data_source_id: &NamespacedId,
+        since: u64,
+        timeout: Duration
+    ) -> manager_and_data::Result<Option<StreamedDataPoint>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = &manager.normalize_id(data_source_id);
+        // Fails if the data source doesn't exist, rather than waiting for it to log something it never will.
+        manager.latest_data(data_source_id).await?;
+
+        let Some(stream) = self.streams.get(manager_id) else {
+            return Ok(None);
+        };
+        Ok(stream.wait(data_source_id, since, timeout.min(MAX_WAIT_TIMEOUT)).await)
+    }
+
+    /// Called after a data source has registered, with the value it started out with, if its manager produced
+    /// one.
+    async fn initial_value_logged(&self, manager_id: &str, data_source_id: &NamespacedId, initial_value: Option<DataPoint<DataType>>) {
+        if let Some(data_point) = initial_value {
+            self.metrics.updates_logged(manager_id, data_source_id, 1);
+            self.data_logged(manager_id, data_source_id, data_point).await;
+        }
+    }
+
+    /// Called after a data source has logged a new data point.
+    async fn data_logged(&self, manager_id: &str, data_source_id: &NamespacedId, data_point: DataPoint<DataType>) {
+        if let (Some(stream), Some(manager)) = (self.streams.get(manager_id), self.managers_and_data.get(manager_id)) {
+            stream.publish(data_source_id, data_point.clone().served_by(manager.as_ref()));
+        }
+
+        self.alerts.evaluate(manager_id, data_source_id, &data_point.value).await;
+
+        for exporter in &self.exporters {
+            if let Err(err) = exporter.export(manager_id, data_source_id, &data_point.value, data_point.timestamp).await {
+                warn!(
+                    "Failed to export data point for data source (manager id: {}, data source id: {}): {}",
+                    manager_id,
+                    data_source_id,
+                    err
+                );
+            }
+        }
+
+        if let Some(storage) = &self.storage {
+            if let Err(err) = storage.store(manager_id, data_source_id, data_point.value, data_point.timestamp).await {
+                warn!(
+                    "Failed to store data point for data source (manager id: {}, data source id: {}): {}",
+                    manager_id,
+                    data_source_id,
+                    err
+                );
+            }
+        }
+    }
+
+    pub async fn get_data(&self, manager_id: &str, data_source_id: &NamespacedId, index: usize) -> manager_and_data::Result<DataPoint<ServedValue>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_point = manager.get_data(&manager.normalize_id(data_source_id), index).await?;
+        Ok(data_point.served_by(manager.as_ref()))
+    }
+
+    /// Returns the latest value of the data source `data_source_id`. Reads of a manager wait while its plugin
+    /// is being reloaded, so during a reload the last value the data source logged is returned right away
+    /// instead, marked as stale, if it logged one since the server started.
+    pub async fn latest_data(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<LatestData> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let data_source_id = &manager.normalize_id(data_source_id);
+
+        if self.is_reloading(manager_id) {
+            if let Some(data_point) = self.streams.get(manager_id).and_then(|stream| stream.latest(data_source_id)) {
+                return Ok(LatestData {
+                    data_point: Some(data_point),
+                    stale: true
+                });
+            }
+        }
+
+        Ok(LatestData {
+            data_point: manager.latest_data(data_source_id).await?.map(|data_point| data_point.served_by(manager.as_ref())),
+            stale: false
+        })
+    }
+
+    /// Returns the latest values of the data sources with the ids `ids` under `namespace`, or of every data
+    /// source registered under it if no ids are given, keyed by id. Data sources that can't be read are keyed
+    /// to why, rather than failing the reads of every other data source.
+    pub async fn latest_bulk(&self, manager_id: &str, namespace: &str, ids: Option<Vec<String>>) -> manager_and_data::Result<HashMap<String, LatestBulkResult>> {
+        let ids = match ids {
+            Some(ids) => ids,
+            None => self.registered_sources(manager_id, namespace, &[]).await?
+        };
+
+        let mut results = HashMap::with_capacity(ids.len());
+        for id in ids {
+            let result = match self.latest_data(manager_id, &NamespacedId::new(namespace.to_string(), id.clone())).await {
+                Ok(latest) => LatestBulkResult {
+                    value: latest.data_point,
+                    stale: latest.stale,
+                    error: None
+                },
+                Err(err) => LatestBulkResult {
+                    value: None,
+                    stale: false,
+                    error: Some(err)
+                }
+            };
+            results.insert(id, result);
+        }
+
+        Ok(results)
+    }
+
+    /// Whether the plugin of the manager with the id `manager_id` is being replaced by a reload.
+    fn is_reloading(&self, manager_id: &str) -> bool {
+        self.reloading
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .contains(manager_id)
+    }
+
+    /// Returns the most recent error the manager with the id `manager_id` failed an update from the data source
+    /// `data_source_id` with, if it ever did.
+    pub async fn last_error(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<Option<LastError>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        manager.last_error(&manager.normalize_id(data_source_id)).await
+    }
+
+    async fn logged_history(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<Vec<DataPoint<DataType>>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        manager.history(&manager.normalize_id(data_source_id)).await
+    }
+
+    /// Returns every value retained for the data source `data_source_id`, prepared to be sent to clients.
+    pub async fn history(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<Vec<DataPoint<ServedValue>>> {
+        let history = self.logged_history(manager_id, data_source_id).await?;
+        Ok(self.served(manager_id, history))
+    }
+
+    /// Prepares `data_points`, logged by the manager with the id `manager_id`, to be sent to clients.
+    fn served(&self, manager_id: &str, data_points: Vec<DataPoint<DataType>>) -> Vec<DataPoint<ServedValue>> {
+        let manager = &self.managers_and_data[manager_id];
+        data_points
+            .into_iter()
+            .map(|data_point| data_point.served_by(manager.as_ref()))
+            .collect()
+    }
+
+    /// Returns the page `page` asks for of the values retained for the data source `data_source_id`, reduced to
+    /// the data points representing it if `downsample` is given.
+    pub async fn history_page(
+        &self,
+        manager_id: &str,
+        data_source_id: &NamespacedId,
+        page: &PageQuery,
+        downsample: Option<Downsample>
+    ) -> manager_and_data::Result<HistoryPage<ServedValue>> {
+        let history = self.logged_history(manager_id, data_source_id).await?;
+        let mut page = pagination::paginate(history, page);
+        if let Some(downsample) = downsample {
+            page.data = aggregate::downsample(page.data, downsample)?;
+        }
+        Ok(HistoryPage {
+            data: self.served(manager_id, page.data),
+            next: page.next
+        })
+    }
+
+    /// Returns at most `downsample.max_points` data points representing the values retained for the data
+    /// source `data_source_id`.
+    pub async fn downsampled_history(&self, manager_id: &str, data_source_id: &NamespacedId, downsample: Downsample) -> manager_and_data::Result<Vec<DataPoint<ServedValue>>> {
+        let history = self.logged_history(manager_id, data_source_id).await?;
+        Ok(self.served(manager_id, aggregate::downsample(history, downsample)?))
+    }
+
+    /// Exports the values retained for the data source `data_source_id` as CSV, only exporting the page `page`
+    /// asks for if it asks for one.
+    pub async fn export_csv(&self, manager_id: &str, data_source_id: &NamespacedId, page: &PageQuery) -> manager_and_data::Result<TextStream![String + '_]> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let mut history = manager.history(&manager.normalize_id(data_source_id)).await?;
+        if page.is_paged() {
+            history = pagination::paginate(history, page).data;
+        }
+        Ok(export::csv_export(manager.as_ref(), history))
+    }
+
+    /// Summarizes the values retained for the data source `data_source_id` using `aggregation`. Aggregations
+    /// that pick one of the values, like `max`, send it the way its manager serves values.
+    pub async fn aggregate(&self, manager_id: &str, data_source_id: &NamespacedId, aggregation: Aggregation) -> manager_and_data::Result<Option<UntaggedServedValue>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let history = self.logged_history(manager_id, data_source_id).await?;
+        Ok(aggregate::aggregate(aggregation, &history)?.map(|value| match aggregation {
+            Aggregation::Min | Aggregation::Max | Aggregation::Last => UntaggedServedValue(manager.serve_value(value)),
+            Aggregation::Mean | Aggregation::Sum | Aggregation::Count => UntaggedServedValue(ServedValue::Logged(value))
+        }))
+    }
+
+    /// Computes how fast the values of the data source `data_source_id` change per second, from its two most
+    /// recent values.
+    pub async fn rate(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<DataPoint<f64>> {
+        let history = self.logged_history(manager_id, data_source_id).await?;
+        aggregate::rate(&history)
+    }
+
+    /// Has the manager with the id `manager_id` combine the latest values of every data source registered
+    /// under `namespace` into a single value, as if it came from a virtual data source.
+    pub async fn aggregate_sources(&self, manager_id: &str, namespace: &str) -> manager_and_data::Result<Option<DataPoint<DataType>>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        manager.aggregate_sources(namespace).await
+    }
+
+    pub fn manager_info(&self, manager_id: &str) -> manager_and_data::Result<ManagerInfo> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        Ok(ManagerInfo {
+            manager_id: manager.manager_id(),
+            data_type: manager.data_type(),
+            unit: manager.unit(),
+            description: manager.description(),
+            capabilities: capabilities::names(manager.capabilities()),
+            expected_content_type: manager.expected_content_type(),
+            expected_interval: manager.expected_interval().map(|interval| interval.as_secs_f64()),
+            max_sources: manager.max_sources()
+        })
+    }
+
+    /// Returns an overview of what the manager with the id `manager_id` has ingested since the server started.
+    pub async fn manager_stats(&self, manager_id: &str) -> manager_and_data::Result<ManagerStats> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        let counts = self.metrics.update_counts(manager_id);
+        Ok(ManagerStats {
+            manager_id: manager.manager_id(),
+            total_updates: counts.as_ref().map_or(0, |counts| counts.updates),
+            update_errors: counts.as_ref().map_or(0, |counts| counts.update_errors),
+            registered_sources: manager.registered_count().await,
+            updates_last_minute: counts.as_ref().map_or(0, |counts| counts.recent_updates),
+            average_update_interval: manager.average_update_interval().await.map(|interval| interval.as_secs_f64())
+        })
+    }
+
+    /// Lets every manager know the server is shutting down, waiting until all of them are done, then writes
+    /// every value that's still buffered to storage. Managers are shut down before the managers they depend on.
+    pub async fn shutdown(&self) {
+        for stage in self.load_stages.iter().rev() {
+            join_all(stage.iter().filter_map(|manager_id| self.managers_and_data.get(manager_id)).map(|manager| async move {
+                if let Err(err) = manager.shutdown().await {
+                    warn!("Manager (id: {}) failed to shut down with error: {}", manager.manager_id(), err);
+                }
+            })).await;
+        }
+
+        if let Some(storage) = &self.storage {
+            if let Err(err) = storage.flush().await {
+                warn!("Failed to write buffered values to storage on shutdown: {}", err);
+            }
+        }
+    }
+
+    /// Succeeds if the data source `data_source_id` is currently registered, without involving its manager.
+    pub async fn source_exists(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        if manager.is_registered(&manager.normalize_id(data_source_id)).await {
+            Ok(())
+        }
+        else {
+            Err(
+                ManagerAndDataError::DataSourceManager(
+                    FlorustServerPluginError::DataSourceDoesntExist(data_source_id.to_string())
+                )
+            )
+        }
+    }
+
+    /// Lists every loaded manager, sorted by id.
+    pub async fn managers(&self) -> Vec<ManagerSummary> {
+        let mut managers = Vec::with_capacity(self.managers_and_data.len());
+        for manager in self.managers_and_data.values() {
+            managers.push(ManagerSummary {
+                manager_id: manager.manager_id(),
+                value_type: manager.data_type(),
+                registered_source_count: manager.registered_count().await
+            });
+        }
+        managers.sort_by_key(|manager| manager.manager_id);
+
+        managers
+    }
+
+    /// Reports the health of every manager, sorted by manager id.
+    pub async fn health(&self) -> HealthReport<'_> {
+        let mut managers = Vec::with_capacity(self.managers_and_data.len());
+        for manager in self.managers_and_data.values() {
+            managers.push(ManagerHealth {
+                manager_id: manager.manager_id(),
+                library_loaded: manager.library_loaded(),
+                registered_sources: manager.registered_count().await,
+                stale_sources: manager.stale_sources().await,
+                healthy: manager.is_healthy()
+            });
+        }
+        managers.sort_by_key(|manager| manager.manager_id);
+
+        HealthReport {
+            healthy: managers.iter().all(|manager| manager.healthy),
+            managers,
+            plugin_failures: &self.plugin_failures
+        }
+    }
+
+    /// Reloads the plugin at `path`, either a plugin's dir or a library placed directly in the plugins dir,
+    /// giving each manager loaded from it the manager of the same id from the plugin's current library. Its
+    /// data sources are registered with the new managers, keeping everything logged for them. If the plugin
+    /// fails to load, or no longer offers the same managers, the loaded one is kept.
+    #[instrument(skip_all, fields(path = %path.display()), err(Display, level = "warn"))]
+    pub async fn reload_plugin(&self, path: &Path) -> manager_and_data::Result<Vec<ReloadReport>> {
+        let not_loaded = || ManagerAndDataError::PluginNotLoaded(path.to_string_lossy().to_string());
+        let path = path.canonicalize().map_err(|_| not_loaded())?;
+        let mut manager_ids: Vec<&str> = self.plugin_paths
+            .iter()
+            .filter(|(_, plugin_path)| **plugin_path == path)
+            .map(|(manager_id, _)| *manager_id)
+            .collect();
+        if manager_ids.is_empty() {
+            return Err(not_loaded());
+        }
+
+        let server_config = FlorustServerConfig::load();
+        let mut failures = Vec::new();
+        let plugin = if path.is_dir() {
+            load_plugin_dir(&server_config, &path, true, &mut failures)
+        }
+        else {
+            let timeout = manager_timeout(server_config.manager_timeout()).unwrap_or(manager_and_data::DEFAULT_MANAGER_TIMEOUT);
+            load_plugin_lib(&server_config, &path, timeout, true, &mut failures)
+        };
+        let plugins = plugin.map(|plugin| plugin.create(&mut failures)).unwrap_or_default();
+        if let Some(failure) = failures.first() {
+            return Err(ManagerAndDataError::ReloadFailed(failure.to_string()));
+        }
+
+        let mut reloaded_ids: Vec<&str> = plugins.iter().map(|plugin| plugin.manager_and_data.manager_id()).collect();
+        manager_ids.sort_unstable();
+        reloaded_ids.sort_unstable();
+        if manager_ids != reloaded_ids {
+            return Err(ManagerAndDataError::ReloadFailed(format!(
+                "reloaded plugin offers managers ({}) other than the loaded ones ({})",
+                reloaded_ids.join(", "),
+                manager_ids.join(", ")
+            )));
+        }
+        for plugin in &plugins {
+            let manager = self.get_manager_or_err(plugin.manager_and_data.manager_id())?;
+            if manager.data_type() != plugin.manager_and_data.data_type() {
+                return Err(ManagerAndDataError::ReloadFailed(format!(
+                    "reloaded manager ({}) produces values of type {} instead of {}",
+                    manager.manager_id(),
+                    plugin.manager_and_data.data_type(),
+                    manager.data_type()
+                )));
+            }
+        }
+
+        let mut reports = Vec::new();
+        for plugin in plugins {
+            let manager = self.get_manager_or_err(plugin.manager_and_data.manager_id())?;
+            self.reloading.write().unwrap_or_else(|err| err.into_inner()).insert(manager.manager_id());
+            let report = manager.replace_plugin(plugin.manager_and_data.into_plugin()).await;
+            self.reloading.write().unwrap_or_else(|err| err.into_inner()).remove(manager.manager_id());
+            let report = report?;
+            for failure in &report.failed {
+                warn!("Reloaded manager (id: {}) failed to register data source (id: {}) again: {}", manager.manager_id(), failure.source, failure.error);
+                self.rate_limiter.forget(manager.manager_id(), &failure.source);
+                self.signing_secrets.forget(manager.manager_id(), &failure.source);
+                self.metrics.forget(manager.manager_id(), &failure.source);
+            }
+            self.metrics.sources_registered(manager.manager_id(), -(report.failed.len() as i64));
+            reports.push(report);
+        }
+
+        info!("Plugin reloaded");
+        Ok(reports)
+    }
+
+    /// Returns the ids of the data sources registered under `namespace` with the manager with the id
+    /// `manager_id`, keeping only the ones tagged with every tag in `tags`.
+    pub async fn registered_sources(&self, manager_id: &str, namespace: &str, tags: &[String]) -> manager_and_data::Result<Vec<String>> {
+        Ok(
+            self.get_manager_or_err(manager_id)?
+                .registered_sources(namespace, tags).await
+        )
     }
-}
 
-fn default_max_data() -> usize { 10 }
+    pub async fn tags(&self, manager_id: &str, data_source_id: &NamespacedId) -> manager_and_data::Result<BTreeSet<String>> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        manager.tags(&manager.normalize_id(data_source_id)).await
+    }
+
+    /// Replaces the tags of the data source `data_source_id` by `tags`, which must not be empty strings.
+    pub async fn set_tags(&self, manager_id: &str, data_source_id: &NamespacedId, tags: BTreeSet<String>) -> manager_and_data::Result<()> {
+        let manager = self.get_manager_or_err(manager_id)?;
+        if tags.contains("") {
+            return Err(ManagerAndDataError::InvalidUpload("tags must not be empty".to_string()));
+        }
+
+        manager.set_tags(&manager.normalize_id(data_source_id), tags).await
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct FlorustServerPluginConfig {
     name: String,
     lib: String,
-    #[serde(default = "default_max_data")]
-    max_data: usize,
+    max_data: Option<usize>,
+    max_raw_data: Option<usize>,
+    max_updates_per_second: Option<f64>,
+    manager_timeout: Option<f64>,
+    idle_timeout: Option<f64>,
+    max_upload_size: Option<usize>,
+    max_concurrent_updates: Option<usize>,
+    max_queued_updates: Option<usize>,
+    auto_register: Option<bool>,
+    #[serde(default)]
+    transforms: Vec<String>,
+    #[serde(default)]
+    monotonic: bool,
     data_type: String,
     create_func: Option<String>
 }
@@ -110,10 +1108,52 @@ impl FlorustServerPluginConfig {
         &self.lib
     }
 
-    pub fn max_data(&self) -> usize {
+    pub fn max_data(&self) -> Option<usize> {
         self.max_data
     }
 
+    pub fn max_raw_data(&self) -> Option<usize> {
+        self.max_raw_data
+    }
+
+    pub fn max_updates_per_second(&self) -> Option<f64> {
+        self.max_updates_per_second
+    }
+
+    pub fn manager_timeout(&self) -> Option<f64> {
+        self.manager_timeout
+    }
+
+    pub fn idle_timeout(&self) -> Option<f64> {
+        self.idle_timeout
+    }
+
+    pub fn max_upload_size(&self) -> Option<usize> {
+        self.max_upload_size
+    }
+
+    pub fn max_concurrent_updates(&self) -> Option<usize> {
+        self.max_concurrent_updates
+    }
+
+    pub fn max_queued_updates(&self) -> Option<usize> {
+        self.max_queued_updates
+    }
+
+    pub fn auto_register(&self) -> Option<bool> {
+        self.auto_register
+    }
+
+    /// The names of the transforms updates go through before they reach the plugin's managers, in order.
+    pub fn transforms(&self) -> &[String] {
+        &self.transforms
+    }
+
+    /// Whether updates lower than the last value logged for their data source are rejected, for counters.
+    pub fn monotonic(&self) -> bool {
+        self.monotonic
+    }
+
     pub fn data_type(&self) -> &str {
         &self.data_type
     }
@@ -125,34 +1165,346 @@ impl FlorustServerPluginConfig {
 
 #[launch]
 fn launch() -> _ {
+    // Rocket installs its own logger for its own messages, so only Florust's events go through this subscriber.
+    if let Err(err) = tracing::subscriber::set_global_default(tracing_subscriber::fmt().finish()) {
+        eprintln!("Failed to set up logging: {}", err);
+    }
+
+    let server_config = FlorustServerConfig::load();
+    non_finite::set_encoding(server_config.non_finite_floats());
+    large_integers::set_encoding(server_config.integer_encoding());
+
     let mut managers = HashMap::new();
-    for plugin in load_plugins() {
-        if let Some(_) = managers.get(plugin.manager_id()) {
-            warn!("Skipping plugin (id: {}) because a plugin with the same id already exists", plugin.manager_id());
-            continue;
+    let mut rate_limits = HashMap::new();
+    let mut upload_limits = HashMap::new();
+    let mut idle_timeouts = HashMap::new();
+    let mut concurrency_limits = HashMap::new();
+    let mut auto_register = HashSet::new();
+    let mut plugin_paths = HashMap::new();
+    let mut watched_configs = Vec::new();
+    let mut plugin_failures = Vec::new();
+    let plugins = load_plugins(&server_config, &mut plugin_failures);
+    // Managers that could be given environment variables meant for another manager are rejected, rather than
+    // guessing which of them a variable is for.
+    let ambiguous_env_ids = config::ambiguous_env_ids(plugins.iter().flat_map(|plugin| plugin.env_ids.iter().map(String::as_str)));
+    let (independent, dependent): (Vec<_>, Vec<_>) = plugins
+        .into_iter()
+        .filter(|plugin| match plugin.env_ids.iter().find_map(|env_id| ambiguous_env_ids.get(env_id)) {
+            Some(reason) => {
+                plugin_failures.push(FlorustServerPluginError::ConfigInvalid {
+                    manager_id: plugin.name.clone(),
+                    detail: reason.clone()
+                });
+                false
+            },
+            None => true
+        })
+        .partition(|plugin| plugin.dependencies.is_empty());
+
+    // Plugins without dependencies are created first, as the ids of their managers may not be known until then,
+    // and the others are only created once the managers they depend on are.
+    let mut manager_ids = HashSet::new();
+    let mut stages = vec![create_stage(independent, &mut manager_ids, &mut plugin_failures)];
+    for stage in dependency::load_stages(&manager_ids.clone(), dependent, &mut plugin_failures) {
+        stages.push(create_stage(stage, &mut manager_ids, &mut plugin_failures));
+    }
+
+    let mut load_stages = Vec::new();
+    for stage in stages {
+        load_stages.push(stage.iter().map(|plugin| plugin.manager_and_data.manager_id()).collect());
+        for plugin in stage {
+            let manager_id = plugin.manager_and_data.manager_id();
+
+            if let (Some(config_path), Some(env_id)) = (plugin.config_path, plugin.env_id) {
+                watched_configs.push(config_watcher::WatchedConfig::new(manager_id, config_path, env_id));
+            }
+            if let Some(max_updates_per_second) = max_updates_per_second(plugin.max_updates_per_second) {
+                rate_limits.insert(manager_id, max_updates_per_second);
+            }
+            if let Some(idle_timeout) = plugin.idle_timeout {
+                idle_timeouts.insert(manager_id, idle_timeout);
+            }
+            if let Some(concurrency_limit) = plugin.concurrency_limit {
+                concurrency_limits.insert(manager_id, concurrency_limit);
+            }
+            if plugin.auto_register {
+                auto_register.insert(manager_id);
+            }
+            if let Some(plugin_path) = plugin.plugin_path {
+                plugin_paths.insert(manager_id, plugin_path);
+            }
+            upload_limits.insert(manager_id, plugin.max_upload_size);
+            managers.insert(manager_id, plugin.manager_and_data);
         }
+    }
 
-        managers.insert(plugin.manager_id(), plugin);
+    // Plugins that fail to load are skipped, so a single bad plugin can't keep the others from being served.
+    for failure in &plugin_failures {
+        warn!("Skipping plugin that failed to load: {}", failure);
     }
 
-    let florust_state = FlorustState {
-        managers_and_data: Arc::new(managers),
-    };
+    let streams = managers
+        .keys()
+        .map(|manager_id| (*manager_id, ManagerStream::default()))
+        .collect();
+    let metrics = Arc::new(Metrics::new(managers.keys().copied()));
+    let rate_limiter = Arc::new(RateLimiter::new(rate_limits));
+    let signing_secrets = Arc::new(SigningSecrets::new());
+
+    let managers_and_data = Arc::new(managers);
+    let api_keys = Arc::new(RwLock::new(ApiKeys::from_config(server_config.auth())));
+    let florust_state = FlorustState(Arc::new(FlorustStateInner {
+        managers_and_data: managers_and_data.clone(),
+        storage: open_storage(&server_config),
+        exporters: open_exporters(&server_config),
+        streams,
+        alerts: Alerts::from_config(server_config.alerts()),
+        metrics: metrics.clone(),
+        rate_limiter: rate_limiter.clone(),
+        signing_secrets: signing_secrets.clone(),
+        concurrency_limiter: ConcurrencyLimiter::new(concurrency_limits),
+        idempotency: IdempotencyCache::new(
+            Duration::try_from_secs_f64(server_config.idempotency_ttl()).unwrap_or(DEFAULT_IDEMPOTENCY_TTL),
+            server_config.idempotency_max_keys()
+        ),
+        upload_limits,
+        max_upload_size: server_config.max_upload_size(),
+        max_decompressed_size: server_config.max_decompressed_size(),
+        auto_register,
+        plugin_failures,
+        load_stages,
+        plugin_paths,
+        reloading: RwLock::new(HashSet::new()),
+        client_auth: ClientAuth::from_config(server_config.client_auth()),
+        source_filters: SourceFilters::from_config(server_config.source_filters()),
+        api_keys: api_keys.clone(),
+        strict_managers: server_config.strict_managers(),
+    }));
 
-    rocket::build().manage(florust_state).mount(
+    let unix_socket_path = server_config.unix_socket().path().map(str::to_string);
+    let catchers = if server_config.strict_managers() {
+        catchers![auth::unauthorized, known_manager::unknown_manager]
+    } else {
+        catchers![auth::unauthorized]
+    };
+    rocket::build().manage(florust_state).attach(RequestTimer::new(metrics.clone())).attach(Cors::from_config(server_config.cors())).attach(
+        AdHoc::on_liftoff("Background tasks", |rocket| Box::pin(async move {
+            if let Some(state) = rocket.state::<FlorustState>() {
+                state.exporters.iter().for_each(|exporter| exporter.start());
+                if let Some(storage) = &state.storage {
+                    storage.start();
+                }
+            }
+            rocket::tokio::spawn(config_watcher::watch_configs(managers_and_data.clone(), watched_configs));
+            rocket::tokio::spawn(config_watcher::watch_server_config(api_keys));
+            rocket::tokio::spawn(reaper::reap_idle_sources(managers_and_data, idle_timeouts, metrics, rate_limiter));
+        }))
+    ).attach(
+        AdHoc::on_shutdown("Manager shutdown", |rocket| Box::pin(async move {
+            if let Some(state) = rocket.state::<FlorustState>() {
+                state.shutdown().await;
+            }
+        }))
+    ).attach(
+        AdHoc::on_liftoff("Unix socket listener", move |rocket| Box::pin(async move {
+            if let (Some(state), Some(path)) = (rocket.state::<FlorustState>(), unix_socket_path) {
+                listen_unix_socket(state.clone(), path);
+            }
+        }))
+    ).register(
+        "/",
+        catchers
+    ).mount(
+        "/",
+        routes![metrics::metrics, health::health, manager::managers]
+    ).mount(
         "/data_source",
         routes![
             data_source::register,
             data_source::unregister,
-            data_source::json_upload_data,
-            data_source::form_upload_data,
+            data_source::upload_data,
+            data_source::stream_data,
+            data_source::batch_upload_data,
             data_source::get_data
         ],
+    ).mount(
+        "/manager",
+        routes![
+            manager::info,
+            manager::stats,
+            manager::latest_data,
+            manager::latest_bulk,
+            manager::latest_bulk_of,
+            manager::wait,
+            manager::source_exists,
+            manager::history,
+            manager::last_error,
+            manager::aggregate,
+            manager::rate,
+            manager::aggregate_sources,
+            manager::export_csv,
+            manager::stream,
+            manager::registered_sources,
+            manager::tags,
+            manager::set_tags,
+            manager::deregister_all,
+            manager::can_register,
+            manager::register_bulk,
+            manager::reset,
+            manager::replay
+        ]
+    ).mount(
+        "/admin",
+        routes![admin::reload_plugin]
     )
 }
 
-fn load_plugins() -> Vec<BoxedManagerAndData> {
+fn open_storage(server_config: &FlorustServerConfig) -> Option<Box<dyn StorageBackend>> {
+    let sqlite_path = server_config.storage().sqlite_path()?;
+
+    #[cfg(feature = "sqlite_storage")] {
+        match sqlite_storage::SqliteStorage::open(sqlite_path) {
+            Ok(storage) => {
+                info!("Persisting data to SQLite database: {}", sqlite_path);
+                let storage_config = server_config.storage();
+                Some(write_buffer::WriteBuffer::wrap(Box::new(storage), storage_config.flush_interval(), storage_config.max_buffered_rows()))
+            },
+            Err(err) => {
+                warn!("Failed to open SQLite database ({}), data won't be persisted: {}", sqlite_path, err);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "sqlite_storage"))] {
+        warn!(
+            "SQLite database ({}) configured, but the server was built without the sqlite_storage feature, data won't be persisted",
+            sqlite_path
+        );
+        None
+    }
+}
+
+/// Connects to every place values are configured to be republished to.
+fn open_exporters(server_config: &FlorustServerConfig) -> Vec<Box<dyn Exporter>> {
+    #[cfg_attr(not(feature = "mqtt_export"), allow(unused_mut))]
+    let mut exporters: Vec<Box<dyn Exporter>> = Vec::new();
+
+    if let Some(host) = server_config.mqtt().host() {
+        #[cfg(feature = "mqtt_export")] {
+            match mqtt_exporter::MqttExporter::connect(server_config.mqtt()) {
+                Ok(exporter) => {
+                    info!("Republishing data to MQTT broker: {}", host);
+                    exporters.push(Box::new(exporter));
+                },
+                Err(err) => warn!("Failed to connect to MQTT broker ({}), data won't be republished: {}", host, err)
+            }
+        }
+
+        #[cfg(not(feature = "mqtt_export"))]
+        warn!(
+            "MQTT broker ({}) configured, but the server was built without the mqtt_export feature, data won't be republished",
+            host
+        );
+    }
+
+    exporters
+}
+
+/// Starts listening for data sources on the Unix domain socket at `path`.
+fn listen_unix_socket(state: FlorustState, path: String) {
+    #[cfg(all(unix, feature = "unix_socket"))] {
+        rocket::tokio::spawn(unix_socket::listen(state, path));
+    }
+
+    #[cfg(not(all(unix, feature = "unix_socket")))] {
+        let _ = state;
+        warn!(
+            "Unix socket ({}) configured, but the server was built without the unix_socket feature, or for a platform without Unix sockets, not listening on it",
+            path
+        );
+    }
+}
+
+/// Converts a timeout in seconds, as found in configs, to a [`Duration`]. Returns `None` if the timeout is
+/// negative or not finite.
+fn manager_timeout(secs: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+/// Converts an idle timeout in seconds, as found in configs, to a [`Duration`]. Returns `None` if data sources
+/// shouldn't be deregistered for going idle, which is the case if the timeout isn't set, is zero, or is
+/// invalid.
+fn idle_timeout(secs: Option<f64>) -> Option<Duration> {
+    let secs = secs?;
+    match Duration::try_from_secs_f64(secs) {
+        Ok(timeout) if !timeout.is_zero() => Some(timeout),
+        Ok(_) => None,
+        Err(_) => {
+            warn!("Invalid idle_timeout ({}), data sources won't be deregistered for going idle", secs);
+            None
+        }
+    }
+}
+
+/// Checks a rate limit in updates per second, as found in configs. Returns `None` if data sources shouldn't be
+/// limited, which is the case if the limit isn't set, or isn't a positive, finite number, as those would
+/// reject every update.
+fn max_updates_per_second(limit: Option<f64>) -> Option<f64> {
+    let limit = limit?;
+    if limit.is_finite() && limit > 0.0 {
+        Some(limit)
+    } else {
+        warn!("Invalid max_updates_per_second ({}), data sources won't be rate limited", limit);
+        None
+    }
+}
+
+/// Creates the managers of every plugin in `stage`, returning every plugin whose managers were created. Plugins
+/// depending on managers that aren't in `manager_ids`, the ids of the managers created in earlier stages, aren't
+/// created, as the managers they depend on failed to be, and managers whose id is already in use are dropped,
+/// adding why to `failures`. The ids of the created managers are added to `manager_ids`.
+fn create_stage(
+    stage: Vec<PendingPlugin>,
+    manager_ids: &mut HashSet<&'static str>,
+    failures: &mut Vec<FlorustServerPluginError>
+) -> Vec<LoadedPlugin> {
+    let mut created = Vec::new();
+    for plugin in stage {
+        let missing = plugin.dependencies
+            .iter()
+            .find(|dependency| !manager_ids.contains(dependency.as_str()) && !plugin.manager_ids.contains(dependency));
+        if let Some(dependency) = missing {
+            failures.push(FlorustServerPluginError::DependencyMissing {
+                manager_id: plugin.name.clone(),
+                dependency: dependency.clone()
+            });
+            continue;
+        }
+
+        created.extend(plugin.create(failures));
+    }
+
+    created.retain(|plugin| {
+        let manager_id = plugin.manager_and_data.manager_id();
+        if !manager_ids.insert(manager_id) {
+            failures.push(FlorustServerPluginError::DataSourceAlreadyExists(manager_id.to_string()));
+            return false;
+        }
+        true
+    });
+    created
+}
+
+/// Opens every plugin, including the default plugins, without creating the managers of any plugin from the
+/// plugins dir, so they can be created once the managers they depend on are. Plugins that fail to open are
+/// skipped, adding why to `failures`.
+fn load_plugins(server_config: &FlorustServerConfig, failures: &mut Vec<FlorustServerPluginError>) -> Vec<PendingPlugin> {
     let mut plugins = Vec::new();
+    let default_timeout = manager_timeout(server_config.manager_timeout()).unwrap_or_else(|| {
+        warn!("Server config has an invalid manager_timeout, using the default timeout");
+        manager_and_data::DEFAULT_MANAGER_TIMEOUT
+    });
 
     // Load default plugins if they are enabled.
     #[cfg(feature = "iinteger_default_plugin")] {
@@ -160,9 +1512,19 @@ fn load_plugins() -> Vec<BoxedManagerAndData> {
 
         let iinteger_manager = Box::new(IIntegerManagerAndData::new(
             Box::new(DefaultIIntegerDataManager{}) as _,
-            10
-        )) as BoxedManagerAndData;
-        plugins.push(iinteger_manager);
+            server_config.history_size()
+        ).with_timeout(default_timeout).with_raw_history_size(server_config.raw_history_size())) as BoxedManagerAndData;
+        plugins.push(PendingPlugin::created(LoadedPlugin {
+            manager_and_data: iinteger_manager,
+            plugin_path: None,
+            config_path: None,
+            env_id: None,
+            max_updates_per_second: server_config.max_updates_per_second(),
+            idle_timeout: idle_timeout(server_config.idle_timeout()),
+            max_upload_size: server_config.max_upload_size(),
+            concurrency_limit: ConcurrencyLimit::new(server_config.max_concurrent_updates(), server_config.max_queued_updates()),
+            auto_register: server_config.auto_register()
+        }));
     }
 
     #[cfg(feature = "uinteger_default_plugin")] {
@@ -170,9 +1532,19 @@ fn load_plugins() -> Vec<BoxedManagerAndData> {
 
         let uinteger_manager = Box::new(UIntegerManagerAndData::new(
             Box::new(DefaultUIntegerDataManager{}) as _,
-            10
-        ));
-        plugins.push(uinteger_manager);
+            server_config.history_size()
+        ).with_timeout(default_timeout).with_raw_history_size(server_config.raw_history_size())) as BoxedManagerAndData;
+        plugins.push(PendingPlugin::created(LoadedPlugin {
+            manager_and_data: uinteger_manager,
+            plugin_path: None,
+            config_path: None,
+            env_id: None,
+            max_updates_per_second: server_config.max_updates_per_second(),
+            idle_timeout: idle_timeout(server_config.idle_timeout()),
+            max_upload_size: server_config.max_upload_size(),
+            concurrency_limit: ConcurrencyLimit::new(server_config.max_concurrent_updates(), server_config.max_queued_updates()),
+            auto_register: server_config.auto_register()
+        }));
     }
 
     #[cfg(feature = "float_default_plugin")] {
@@ -180,226 +1552,585 @@ fn load_plugins() -> Vec<BoxedManagerAndData> {
 
         let float_manager = Box::new(FloatManagerAndData::new(
             Box::new(DefaultFloatDataManager{}) as _,
-            10
-        ));
-        plugins.push(float_manager);
+            server_config.history_size()
+        ).with_timeout(default_timeout).with_raw_history_size(server_config.raw_history_size())) as BoxedManagerAndData;
+        plugins.push(PendingPlugin::created(LoadedPlugin {
+            manager_and_data: float_manager,
+            plugin_path: None,
+            config_path: None,
+            env_id: None,
+            max_updates_per_second: server_config.max_updates_per_second(),
+            idle_timeout: idle_timeout(server_config.idle_timeout()),
+            max_upload_size: server_config.max_upload_size(),
+            concurrency_limit: ConcurrencyLimit::new(server_config.max_concurrent_updates(), server_config.max_queued_updates()),
+            auto_register: server_config.auto_register()
+        }));
     }
 
     info!("Checking for custom plugins");
-    let custom_plugin_dirs = match read_dir("plugins/") {
+    let plugins_dir = server_config.plugins_dir();
+    let custom_plugins = match read_dir(plugins_dir) {
         Ok(entries) => entries,
         Err(_) => {
-            info!("Plugins dir not found, not loading any plugins");
+            info!("Plugins dir ({}) not found, not loading any plugins", plugins_dir);
             return plugins;
         }
     };
 
-    for plugin_dir in custom_plugin_dirs {
-        // Only process entries which are dirs
-        let plugin_dir = match plugin_dir {
-            Ok(pd) => {
-                let metadata = match pd.metadata() {
-                    Ok(md) => md,
-                    Err(err) => {
-                        warn!("Failed to get metadata for entry in plugins dir: {}", err);
-                        continue
-                    },
-                };
-
-                if !metadata.is_dir() {
-                    continue;
-                }
-
-                pd
-            }
-            Err(_) => continue
-        };
-
-        let plugin_dir_path = plugin_dir.path();
-
-        // Path pointing to plugin.toml file
-        let plugin_config_path = {
-            let mut tmp = plugin_dir_path.clone();
-            tmp.push("/plugin.toml");
-            tmp
-        };
-
-        let plugin_config_file = match read_to_string(&plugin_config_path) {
-            Ok(str) => str,
+    for entry in custom_plugins {
+        let entry = match entry {
+            Ok(entry) => entry,
             Err(err) => {
-                warn!("Failed to open plugin.toml inside of dir found in plugins dir: {}", err);
+                warn!("Failed to read entry in plugins dir: {}", err);
                 continue;
             }
         };
 
-        let mut toml = match plugin_config_file.parse::<Table>() {
-            Ok(toml) => toml,
+        let metadata = match entry.metadata() {
+            Ok(md) => md,
             Err(err) => {
-                warn!("Failed to parse plugin.toml: {}", err);
-                continue;
-            }
+                warn!("Failed to get metadata for entry in plugins dir: {}", err);
+                continue
+            },
         };
 
-        // Get config section we are interested in
-        let Some(config_raw) = toml.remove("plugin") else {
-            warn!(
-                "Plugin config doesn't contain mandated plugin section, file: {}",
-                plugin_config_path.to_string_lossy()
-            );
-            continue;
+        // Dirs hold a plugin along with its config, while shared libraries placed directly in the plugins dir
+        // are loaded without one.
+        let path = entry.path();
+        if metadata.is_dir() {
+            plugins.extend(load_plugin_dir(server_config, &path, false, failures));
+        }
+        else if path.extension().is_some_and(|extension| extension == DLL_EXTENSION) {
+            plugins.extend(load_plugin_lib(server_config, &path, default_timeout, false, failures));
+        }
+    }
+
+    plugins
+}
+
+/// Opens the plugin in the dir at `plugin_dir_path`, which holds the plugin's `plugin.toml` config along with its
+/// library, without creating its managers. Returns `None` if it fails to open, adding why to `failures`. `reload`
+/// is set when the plugin is already loaded, see [`open_library`].
+fn load_plugin_dir(
+    server_config: &FlorustServerConfig,
+    plugin_dir_path: &Path,
+    reload: bool,
+    failures: &mut Vec<FlorustServerPluginError>
+) -> Option<PendingPlugin> {
+    // Path pointing to plugin.toml file
+    let plugin_config_path = plugin_dir_path.join("plugin.toml");
+
+    let plugin_config_file = match read_to_string(&plugin_config_path) {
+        Ok(str) => str,
+        Err(err) => {
+            failures.push(load_failed(&plugin_config_path, format!("plugin.toml couldn't be opened: {}", err)));
+            return None;
+        }
+    };
+
+    let mut toml = match plugin_config_file.parse::<Table>() {
+        Ok(toml) => toml,
+        Err(err) => {
+            failures.push(load_failed(&plugin_config_path, format!("plugin.toml couldn't be parsed: {}", err)));
+            return None;
+        }
+    };
+
+    // Get config section we are interested in
+    let Some(config_raw) = toml.remove("plugin") else {
+        failures.push(load_failed(&plugin_config_path, "plugin config doesn't contain mandated plugin section"));
+        return None;
+    };
+
+    let toml = config_watcher::extra_config_sections(toml);
+
+    if !config_raw.is_table() {
+        failures.push(load_failed(&plugin_config_path, "plugin config contains key for \"plugin\", but it isn't a table"));
+        return None;
+    };
+
+    // Parse the config
+    let config = match config_raw.try_into::<FlorustServerPluginConfig>() {
+        Ok(c) => c,
+        Err(err) => {
+            failures.push(load_failed(&plugin_config_path, format!("plugin config couldn't be parsed: {}", err)));
+            return None;
+        },
+    };
+
+    let max_data = config.max_data().unwrap_or(server_config.history_size());
+    let max_raw_data = config.max_raw_data().unwrap_or(server_config.raw_history_size());
+    let max_updates_per_second = config.max_updates_per_second().or(server_config.max_updates_per_second());
+    let max_upload_size = config.max_upload_size().unwrap_or(server_config.max_upload_size());
+    let idle_timeout = idle_timeout(config.idle_timeout().or(server_config.idle_timeout()));
+    let max_concurrent_updates = config.max_concurrent_updates().or(server_config.max_concurrent_updates());
+    let max_queued_updates = config.max_queued_updates().unwrap_or(server_config.max_queued_updates());
+    let auto_register = config.auto_register().unwrap_or(server_config.auto_register());
+    let Some(timeout) = manager_timeout(config.manager_timeout().unwrap_or(server_config.manager_timeout())) else {
+        failures.push(load_failed(&plugin_config_path, "plugin config has an invalid manager_timeout"));
+        return None;
+    };
+    let transforms = match Transforms::from_names(config.transforms(), server_config.max_decompressed_size()) {
+        Ok(transforms) => Arc::new(transforms),
+        Err(err) => {
+            failures.push(load_failed(&plugin_config_path, format!("plugin config has invalid transforms: {}", err)));
+            return None;
+        }
+    };
+    if config.monotonic() && !NUMERIC_DATA_TYPES.contains(&config.data_type()) {
+        failures.push(load_failed(&plugin_config_path, format!("plugin config sets monotonic, but its data type ({}) isn't numeric", config.data_type())));
+        return None;
+    }
+
+    // Get library file path from config
+    let plugin_lib_path = plugin_dir_path.join(config.lib());
+
+    let (lib, manager_ids, dependencies) = unsafe {
+        let lib = match open_library(&plugin_lib_path, reload) {
+            Ok(l) => Arc::new(l),
+            Err(err) => {
+                failures.push(err);
+                return None;
+            },
         };
 
-        let toml = if toml.len() > 0 {
-            Some(toml)
+        if let Err(err) = check_abi_version(&lib, &plugin_lib_path) {
+            failures.push(err);
+            return None;
         }
-        else {
-            None
+
+        let manager_ids = match config.create_func() {
+            Some(_) => Some(Vec::new()),
+            None => listed_manager_ids(&lib, config.data_type())
+        };
+        let Some(manager_ids) = manager_ids else {
+            failures.push(load_failed(&plugin_config_path, format!("plugin config has unsupported data type: {}", config.data_type())));
+            return None;
         };
 
-        if !config_raw.is_table() {
-            warn!(
-                "Plugin config contains key for \"plugin\", but it isn't a table, file: {}",
-                plugin_config_path.to_string_lossy()
-            );
-            continue;
+        let dependencies = plugin_dependencies(&lib);
+        (lib, manager_ids, dependencies)
+    };
+
+    let (name, env_ids) = match manager_ids.is_empty() {
+        true => (config.name().to_string(), vec![config.name().to_string()]),
+        false => (manager_ids.join(", "), manager_ids.clone())
+    };
+    let plugin_dir_path = plugin_dir_path.to_path_buf();
+    let create = move |failures: &mut Vec<FlorustServerPluginError>| {
+        let managers_and_data = unsafe {
+            create_managers_of_type(
+                &lib,
+                config.data_type(),
+                config.create_func(),
+                config.name(),
+                &plugin_lib_path,
+                toml,
+                max_data,
+                max_raw_data,
+                transforms,
+                config.monotonic(),
+                timeout,
+                failures
+            ).unwrap_or_default()
         };
 
-        // Parse the config
-        let config = match toml::from_str::<FlorustServerPluginConfig>(&config_raw.to_string()) {
-            Ok(c) => c,
+        info!("Loaded plugin: {}", plugin_dir_path.to_string_lossy());
+        managers_and_data
+            .into_iter()
+            .map(|(env_id, manager_and_data)| LoadedPlugin {
+                manager_and_data,
+                plugin_path: plugin_dir_path.canonicalize().ok(),
+                config_path: Some(plugin_config_path.clone()),
+                env_id: Some(env_id),
+                max_updates_per_second,
+                idle_timeout,
+                max_upload_size,
+                concurrency_limit: ConcurrencyLimit::new(max_concurrent_updates, max_queued_updates),
+                auto_register
+            })
+            .collect()
+    };
+
+    Some(PendingPlugin {
+        manager_ids,
+        name,
+        env_ids,
+        dependencies,
+        create: Box::new(create)
+    })
+}
+
+/// Opens the plugin library at `plugin_lib_path`, which was placed in the plugins dir without a config, without
+/// creating its managers. Its data type is determined by which of the default create functions it exports, and
+/// its manager uses the server's defaults for everything a `plugin.toml` would configure. Returns `None` if it
+/// fails to open, adding why to `failures`. `reload` is set when the plugin is already loaded, see
+/// [`open_library`].
+fn load_plugin_lib(
+    server_config: &FlorustServerConfig,
+    plugin_lib_path: &Path,
+    timeout: Duration,
+    reload: bool,
+    failures: &mut Vec<FlorustServerPluginError>
+) -> Option<PendingPlugin> {
+    // The file name stands in for the plugin's name, for the environment variables overriding its config.
+    let plugin_name = plugin_lib_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let (lib, data_type, create_func, manager_ids, dependencies) = unsafe {
+        let lib = match open_library(plugin_lib_path, reload) {
+            Ok(l) => Arc::new(l),
             Err(err) => {
-                warn!(
-                    "Plugin config (file: {}) couldn't be parsed: {}",
-                    plugin_config_path.to_string_lossy(),
-                    err
-                );
-                continue;
+                failures.push(err);
+                return None;
             },
         };
 
-        // Get library file path from config
-        let plugin_lib_path = {
-            let mut tmp = plugin_dir_path.clone();
-            tmp.push(config.lib());
-            tmp
+        if let Err(err) = check_abi_version(&lib, plugin_lib_path) {
+            failures.push(err);
+            return None;
+        }
+
+        let Some((data_type, create_func)) = DEFAULT_CREATE_FUNCS
+            .into_iter()
+            .find(|(_, create_func)| lib.get::<unsafe extern "C" fn()>(create_func.as_bytes()).is_ok())
+        else {
+            failures.push(load_failed(plugin_lib_path, "plugin doesn't export any of the default create functions, it needs a plugin.toml to be loaded"));
+            return None;
         };
 
-        // Get manager from library
-        let manager_and_data = unsafe {
-            let lib = match libloading::Library::new(plugin_lib_path.clone()) {
-                Ok(l) => l,
-                Err(err) => {
-                    warn!(
-                        "Failed to open library (path: {}) for plugin (config file: {}) with error: {}",
-                        plugin_lib_path.to_string_lossy(),
-                        plugin_config_path.to_string_lossy(),
-                        err
-                    );
-                    continue;
-                },
-            };
+        let manager_ids = listed_manager_ids(&lib, data_type).unwrap_or_default();
+        let dependencies = plugin_dependencies(&lib);
+        (lib, data_type, create_func, manager_ids, dependencies)
+    };
 
-            match config.data_type() {
-                "i64" => {
-                    let create_func_name = if let Some(name) = config.create_func() {
-                        name
-                    }
-                    else {
-                        "create_iinteger_data_source_manager"
-                    };
-
-                    let create_func: libloading::Symbol<CreateIIntegerDataSourceManager> = match lib.get(create_func_name.as_bytes()) {
-                        Ok(m) => m,
-                        Err(err) => {
-                            warn!(
-                                "Failed to retrieve create function ({}) for plugin (path: {}) with error: {}",
-                                create_func_name,
-                                plugin_dir_path.to_string_lossy(),
-                                err
-                            );
-                            continue;
-                        },
-                    };
-
-                    match *create_func(Box::new(toml)) {
-                        Ok(m) => Box::new(
-                            IIntegerManagerAndData::new(m, config.max_data())
-                        ) as BoxedManagerAndData,
-                        Err(err) => {
-                            warn!("Failed to create manager for plugin (path: {}) with error: {}", plugin_dir_path.to_string_lossy(), err);
-                            continue;
-                        },
-                    }
-                },
-                "u64" => {
-                    let create_func_name = if let Some(name) = config.create_func() {
-                        name
-                    }
-                    else {
-                        "create_uinteger_data_source_manager"
-                    };
-
-                    let create_func: libloading::Symbol<CreateUIntegerDataSourceManager> = match lib.get(create_func_name.as_bytes()) {
-                        Ok(m) => m,
-                        Err(err) => {
-                            warn!(
-                                "Failed to retrieve create function ({}) for plugin (path: {}) with error: {}",
-                                create_func_name,
-                                plugin_dir_path.to_string_lossy(),
-                                err
-                            );
-                            continue;
-                        },
-                    };
-
-                    match *create_func(Box::new(toml)) {
-                        Ok(m) => Box::new(
-                            UIntegerManagerAndData::new(m, config.max_data())
-                        ),
-                        Err(err) => {
-                            warn!("Failed to create manager for plugin (path: {}) with error: {}", plugin_dir_path.to_string_lossy(), err);
-                            continue;
-                        },
-                    }
-                },
-                "f64" => {
-                    let create_func_name = if let Some(name) = config.create_func() {
-                        name
-                    }
-                    else {
-                        "create_float_data_source_manager"
-                    };
-
-                    let create_func: libloading::Symbol<CreateFloatDataSourceManager> = match lib.get(create_func_name.as_bytes()) {
-                        Ok(m) => m,
-                        Err(err) => {
-                            warn!(
-                                "Failed to retrieve create function ({}) for plugin (path: {}) with error: {}",
-                                create_func_name,
-                                plugin_dir_path.to_string_lossy(),
-                                err
-                            );
-                            continue;
-                        },
-                    };
-
-                    match *create_func(Box::new(toml)) {
-                        Ok(m) => Box::new(
-                            FloatManagerAndData::new(m, config.max_data())
-                        ),
-                        Err(err) => {
-                            warn!("Failed to create manager for plugin (path: {}) with error: {}", plugin_dir_path.to_string_lossy(), err);
-                            continue;
-                        },
-                    }
-                },
-                _ => unreachable!("data type config value no longer valid despite being valid a bit ago")
-            }
+    let name = match manager_ids.is_empty() {
+        true => plugin_name.clone(),
+        false => manager_ids.join(", ")
+    };
+    let history_size = server_config.history_size();
+    let raw_history_size = server_config.raw_history_size();
+    let max_updates_per_second = server_config.max_updates_per_second();
+    let idle_timeout = idle_timeout(server_config.idle_timeout());
+    let max_upload_size = server_config.max_upload_size();
+    let max_concurrent_updates = server_config.max_concurrent_updates();
+    let max_queued_updates = server_config.max_queued_updates();
+    let auto_register = server_config.auto_register();
+    let plugin_lib_path = plugin_lib_path.to_path_buf();
+    let create = move |failures: &mut Vec<FlorustServerPluginError>| {
+        let managers_and_data = unsafe {
+            create_managers_of_type(
+                &lib,
+                data_type,
+                Some(create_func),
+                &plugin_name,
+                &plugin_lib_path,
+                None,
+                history_size,
+                raw_history_size,
+                SharedTransforms::default(),
+                false,
+                timeout,
+                failures
+            ).unwrap_or_default()
         };
 
-        plugins.push(manager_and_data);
-        info!("Loaded plugin: {}", plugin_dir_path.to_string_lossy());
+        info!("Loaded plugin: {}", plugin_lib_path.to_string_lossy());
+        managers_and_data
+            .into_iter()
+            .map(|(_, manager_and_data)| LoadedPlugin {
+                manager_and_data,
+                plugin_path: plugin_lib_path.canonicalize().ok(),
+                config_path: None,
+                env_id: None,
+                max_updates_per_second,
+                idle_timeout,
+                max_upload_size,
+                concurrency_limit: ConcurrencyLimit::new(max_concurrent_updates, max_queued_updates),
+                auto_register
+            })
+            .collect()
+    };
+
+    Some(PendingPlugin {
+        manager_ids,
+        name,
+        env_ids: Vec::new(),
+        dependencies,
+        create: Box::new(create)
+    })
+}
+
+/// The default create function of every supported data type, paired with the name of the data type, as used
+/// for `data_type` in plugin configs.
+const DEFAULT_CREATE_FUNCS: [(&str, &str); 9] = [
+    ("i64", "create_iinteger_data_source_manager"),
+    ("u64", "create_uinteger_data_source_manager"),
+    ("f64", "create_float_data_source_manager"),
+    ("f32", "create_float32_data_source_manager"),
+    ("bool", "create_boolean_data_source_manager"),
+    ("string", "create_string_data_source_manager"),
+    ("f64_vec", "create_float_vec_data_source_manager"),
+    ("i128", "create_i128_data_source_manager"),
+    ("u128", "create_u128_data_source_manager"),
+];
+
+/// The data types whose values can be ordered numerically, which managers can be made monotonic for.
+const NUMERIC_DATA_TYPES: [&str; 6] = ["i64", "u64", "f64", "f32", "i128", "u128"];
+
+/// Creates every manager offered by `lib` producing values of type `data_type`, see [`create_managers`], using
+/// the default create function of that type unless `create_func` is given. Returns `None` if `data_type` isn't
+/// supported.
+#[allow(clippy::too_many_arguments)]
+unsafe fn create_managers_of_type(
+    lib: &Arc<libloading::Library>,
+    data_type: &str,
+    create_func: Option<&str>,
+    plugin_name: &str,
+    plugin_lib_path: &Path,
+    toml: Option<Table>,
+    max_data: usize,
+    max_raw_data: usize,
+    transforms: SharedTransforms,
+    monotonic: bool,
+    timeout: Duration,
+    failures: &mut Vec<FlorustServerPluginError>
+) -> Option<Vec<(String, BoxedManagerAndData)>> {
+    let (_, default_create_func) = DEFAULT_CREATE_FUNCS.into_iter().find(|(name, _)| *name == data_type)?;
+    let default_config = default_config(lib);
+    let toml = config::with_defaults(default_config.as_ref(), toml);
+
+    let managers = match data_type {
+        "i64" => create_managers::<IIntegerDataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(IIntegerManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        "u64" => create_managers::<UIntegerDataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(UIntegerManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        "f64" => create_managers::<FloatDataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(FloatManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        "f32" => create_managers::<Float32DataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(Float32ManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        "bool" => create_managers::<BooleanDataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(BooleanManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        "string" => create_managers::<StringDataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(StringManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        "f64_vec" => create_managers::<FloatVecDataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(FloatVecManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        "i128" => create_managers::<I128DataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(I128ManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        "u128" => create_managers::<U128DataSourceManager>(lib, create_func, default_create_func, plugin_name, plugin_lib_path, toml, failures)
+            .into_iter()
+            .map(|(env_id, m)| (env_id, Box::new(U128ManagerAndData::new(m, max_data).with_timeout(timeout).with_raw_history_size(max_raw_data).with_transforms(transforms.clone()).with_monotonic(monotonic).with_library(lib.clone()).with_default_config(default_config.clone())) as BoxedManagerAndData))
+            .collect(),
+        _ => return None
+    };
+
+    Some(managers)
+}
+
+/// Checks that the ABI version exported by `lib` matches the one the server was compiled against.
+unsafe fn check_abi_version(lib: &libloading::Library, plugin_lib_path: &Path) -> Result<(), FlorustServerPluginError> {
+    let abi_version: libloading::Symbol<FlorustAbiVersion> = lib.get(b"florust_abi_version")
+        .map_err(|_| FlorustServerPluginError::AbiVersionMissing(plugin_lib_path.to_string_lossy().to_string()))?;
+
+    let found = abi_version();
+    if found != FLORUST_ABI_VERSION {
+        return Err(FlorustServerPluginError::AbiVersionMismatch {
+            path: plugin_lib_path.to_string_lossy().to_string(),
+            expected: FLORUST_ABI_VERSION,
+            found
+        });
     }
 
-    plugins
+    Ok(())
+}
+
+/// Returns the default config exported by `lib` as `florust_default_config`, if it exports one.
+unsafe fn default_config(lib: &libloading::Library) -> Option<Table> {
+    let default_config: libloading::Symbol<DefaultConfig> = lib.get(b"florust_default_config").ok()?;
+    Some(*default_config())
+}
+
+/// Returns the ids of the managers of type `data_type` that `lib` lists as `florust_data_source_managers`,
+/// without creating them, or no ids if it doesn't list them. Returns `None` if `data_type` isn't supported.
+unsafe fn listed_manager_ids(lib: &libloading::Library, data_type: &str) -> Option<Vec<String>> {
+    unsafe fn listed<M: ?Sized>(lib: &libloading::Library) -> Vec<String> {
+        match lib.get::<DataSourceManagerEntries<M>>(b"florust_data_source_managers") {
+            Ok(entries) => entries().into_iter().map(|(manager_id, _)| manager_id).collect(),
+            Err(_) => Vec::new()
+        }
+    }
+
+    Some(match data_type {
+        "i64" => listed::<IIntegerDataSourceManager>(lib),
+        "u64" => listed::<UIntegerDataSourceManager>(lib),
+        "f64" => listed::<FloatDataSourceManager>(lib),
+        "f32" => listed::<Float32DataSourceManager>(lib),
+        "bool" => listed::<BooleanDataSourceManager>(lib),
+        "string" => listed::<StringDataSourceManager>(lib),
+        "f64_vec" => listed::<FloatVecDataSourceManager>(lib),
+        "i128" => listed::<I128DataSourceManager>(lib),
+        "u128" => listed::<U128DataSourceManager>(lib),
+        _ => return None
+    })
+}
+
+/// Returns the ids of the managers `lib` exports as `florust_plugin_dependencies`, if it exports them.
+unsafe fn plugin_dependencies(lib: &libloading::Library) -> Vec<String> {
+    match lib.get::<PluginDependencies>(b"florust_plugin_dependencies") {
+        Ok(dependencies) => *dependencies(),
+        Err(_) => Vec::new()
+    }
+}
+
+/// The error reported for the plugin at `path` failing to load because of `reason`.
+fn load_failed(path: &Path, reason: impl Into<String>) -> FlorustServerPluginError {
+    FlorustServerPluginError::PluginLoadFailed {
+        path: path.to_string_lossy().to_string(),
+        reason: reason.into()
+    }
+}
+
+/// Opens the dynamic library at `plugin_lib_path`. If `reload` is set, a copy of the library is opened instead,
+/// as the dynamic loader hands back the library that's already loaded when the same path is opened again.
+unsafe fn open_library(plugin_lib_path: &Path, reload: bool) -> Result<libloading::Library, FlorustServerPluginError> {
+    static RELOADS: AtomicUsize = AtomicUsize::new(0);
+
+    if !reload {
+        return libloading::Library::new(plugin_lib_path).map_err(|err| load_failed(plugin_lib_path, err.to_string()));
+    }
+
+    // The copy is made in a dir only the server can access, which is created fresh rather than reused, so other
+    // users can't plant a library of their own, or a link to one, where the copy is opened from.
+    let copy_dir = env::temp_dir().join(format!("florust-{}-{}", process::id(), RELOADS.fetch_add(1, Ordering::Relaxed)));
+    create_private_dir(&copy_dir)
+        .map_err(|err| load_failed(plugin_lib_path, format!("dir to copy the library to couldn't be created: {}", err)))?;
+    let copy_path = copy_dir.join(format!("plugin.{}", DLL_EXTENSION));
+
+    let copied = File::open(plugin_lib_path).and_then(|mut lib| {
+        let mut copy = OpenOptions::new().write(true).create_new(true).open(&copy_path)?;
+        io::copy(&mut lib, &mut copy)
+    });
+    let lib = match copied {
+        Ok(_) => libloading::Library::new(&copy_path).map_err(|err| load_failed(plugin_lib_path, err.to_string())),
+        Err(err) => Err(load_failed(plugin_lib_path, format!("library couldn't be copied to be reloaded: {}", err)))
+    };
+
+    // Once opened, the copy stays loaded after it's removed. Platforms that don't allow removing it leave it
+    // behind in the temp dir.
+    let _ = fs::remove_file(&copy_path);
+    let _ = fs::remove_dir(&copy_dir);
+    lib
+}
+
+/// Creates the dir at `path`, which only the current user may access. Fails if anything already exists at
+/// `path`, including a link.
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)] {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path)
+}
+
+/// Creates every manager offered by `lib`. Plugins offering several managers export a list of them named
+/// `florust_data_source_managers`, which is used unless the plugin's config names a create function.
+/// Otherwise the single manager created by `create_func_name`, or `default_create_func_name` if that isn't
+/// given, is used. Managers that fail to be created are skipped, adding why to `failures`.
+///
+/// Every manager is returned along with the id its config is overridden by environment variables with,
+/// which is the id of the manager if the plugin lists its managers, or `plugin_name` otherwise, as the id of a
+/// single manager isn't known until it's created.
+unsafe fn create_managers<M: ?Sized>(
+    lib: &libloading::Library,
+    create_func_name: Option<&str>,
+    default_create_func_name: &str,
+    plugin_name: &str,
+    plugin_lib_path: &Path,
+    toml: Option<Table>,
+    failures: &mut Vec<FlorustServerPluginError>
+) -> Vec<(String, Box<M>)> {
+    if create_func_name.is_none() {
+        if let Ok(entries) = lib.get::<DataSourceManagerEntries<M>>(b"florust_data_source_managers") {
+            return entries()
+                .into_iter()
+                .filter_map(|(manager_id, create_func)| match *create_func(Box::new(config::with_env_overrides(&manager_id, toml.clone()))) {
+                    Ok(m) => Some((manager_id, m)),
+                    Err(err) => {
+                        failures.push(FlorustServerPluginError::ConfigInvalid {
+                            manager_id,
+                            detail: err.to_string()
+                        });
+                        None
+                    }
+                })
+                .collect();
+        }
+    }
+
+    let create_func_name = create_func_name.unwrap_or(default_create_func_name);
+    match create_manager::<M>(lib, create_func_name, plugin_name, plugin_lib_path, config::with_env_overrides(plugin_name, toml)) {
+        Ok(m) => vec![(plugin_name.to_string(), m)],
+        Err(err) => {
+            failures.push(err);
+            Vec::new()
+        }
+    }
+}
+
+/// Retrieves the create function named `create_func_name` from `lib` and uses it to create a data source
+/// manager, passing along any extra config sections from the plugin's config file.
+///
+/// Returns an error if the function couldn't be found, or [`FlorustServerPluginError::ConfigInvalid`] carrying
+/// the plugin's own error if the manager couldn't be created. `plugin_name` stands in for the id of the
+/// manager, which isn't known until it's created.
+unsafe fn create_manager<M: ?Sized>(
+    lib: &libloading::Library,
+    create_func_name: &str,
+    plugin_name: &str,
+    plugin_lib_path: &Path,
+    toml: Option<Table>
+) -> Result<Box<M>, FlorustServerPluginError> {
+    let create_func: libloading::Symbol<CreateDataSourceManager<M>> = lib.get(create_func_name.as_bytes())
+        .map_err(|err| FlorustServerPluginError::PluginLoadFailed {
+            path: plugin_lib_path.to_string_lossy().to_string(),
+            reason: format!("failed to retrieve create function ({}): {}", create_func_name, err)
+        })?;
+
+    (*create_func(Box::new(toml))).map_err(|err| FlorustServerPluginError::ConfigInvalid {
+        manager_id: plugin_name.to_string(),
+        detail: err.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_positive_finite_rate_limits_are_used() {
+        assert_eq!(max_updates_per_second(Some(2.5)), Some(2.5));
+        assert_eq!(max_updates_per_second(None), None);
+        for invalid in [0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(max_updates_per_second(Some(invalid)), None);
+        }
+    }
 }
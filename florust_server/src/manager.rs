@@ -0,0 +1,359 @@
+use std::{collections::{BTreeSet, HashMap}, num::NonZeroUsize, time::Duration};
+
+use rocket::{Either, get, post, put, State, http::ContentType, response::stream::TextStream, serde::json::Json};
+use rocket_ws::{Channel, WebSocket};
+
+use crate::{
+    FlorustState,
+    aggregate::{AggregationQuery, Downsample, Downsampling},
+    auth::{ReadAccess, WriteAccess},
+    client_auth::ClientIdentity,
+    data_source::{DataSourceError, OkResponder, OptionalResponder, state_op_to_responder, optional_state_op_to_responder},
+    etag::{IfNoneMatch, LatestResponder},
+    known_manager::KnownManager,
+    manager_and_data::{BulkRegistration, BulkRegistrationResult, DataPoint, DataType, DeregisterAllReport, LastError, LatestBulkResult, ManagerInfo, ManagerStats, ManagerSummary, NamespacedId, ReplayedUpdate, ServedValue, UntaggedServedValue},
+    pagination::{HistoryPage, PageQuery, Param},
+    stream::{DEFAULT_WAIT_TIMEOUT, StreamedDataPoint, stream_data_points},
+    upload::{UploadBody, UploadError, optional_upload}
+};
+
+#[get("/managers")]
+pub async fn managers(
+    _access: ReadAccess,
+    state: &State<FlorustState>
+) -> Result<OkResponder<Vec<ManagerSummary>>, DataSourceError> {
+    state_op_to_responder(Ok(state.managers().await))
+}
+
+#[get("/<manager_id>/info")]
+pub async fn info(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String
+) -> Result<OkResponder<ManagerInfo>, DataSourceError> {
+    state_op_to_responder(state.manager_info(&manager_id))
+}
+
+#[get("/<manager_id>/stats")]
+pub async fn stats(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String
+) -> Result<OkResponder<ManagerStats>, DataSourceError> {
+    state_op_to_responder(state.manager_stats(&manager_id).await)
+}
+
+/// Lists the data sources registered under a namespace. Given one or more `tag` parameters, only data sources
+/// tagged with all of them are listed.
+#[get("/<manager_id>/sources?<namespace>&<tag>")]
+pub async fn registered_sources(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    namespace: Option<String>,
+    tag: Vec<String>
+) -> Result<OkResponder<Vec<String>>, DataSourceError> {
+    state_op_to_responder(state.registered_sources(&manager_id, &namespace.unwrap_or_default(), &tag).await)
+}
+
+#[post("/<manager_id>/register_bulk", format = "json", data = "<registrations>")]
+pub async fn register_bulk(
+    _access: WriteAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    identity: ClientIdentity,
+    manager_id: String,
+    registrations: Json<Vec<BulkRegistration>>
+) -> Result<OkResponder<Vec<BulkRegistrationResult>>, DataSourceError> {
+    state_op_to_responder(state.register_bulk(&manager_id, &identity, registrations.into_inner()).await)
+}
+
+#[post("/<manager_id>/can_register/<data_source_id..>", data = "<data>")]
+pub async fn can_register(
+    _access: WriteAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    identity: ClientIdentity,
+    manager_id: String,
+    data_source_id: NamespacedId,
+    data: Result<UploadBody, UploadError>
+) -> Result<OkResponder<()>, DataSourceError> {
+    let data = optional_upload(data)?;
+    let data = data.as_ref().map(|data| data.data.as_slice());
+
+    state_op_to_responder(state.can_register(&manager_id, &identity, &data_source_id, data).await)
+}
+
+#[post("/<manager_id>/deregister_all?<confirm>")]
+pub async fn deregister_all(
+    _access: WriteAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    identity: ClientIdentity,
+    manager_id: String,
+    confirm: Option<bool>
+) -> Result<OkResponder<DeregisterAllReport>, DataSourceError> {
+    state_op_to_responder(state.deregister_all(&manager_id, &identity, confirm.unwrap_or(false)).await)
+}
+
+#[post("/<manager_id>/source/<data_source_id>/reset?<namespace>")]
+pub async fn reset(
+    _access: WriteAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    identity: ClientIdentity,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>
+) -> Result<OkResponder<()>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    state.check_client(&manager_id, &identity, &data_source_id)?;
+    state_op_to_responder(state.reset(&manager_id, &data_source_id).await)
+}
+
+#[post("/<manager_id>/source/<data_source_id>/replay?<namespace>")]
+pub async fn replay(
+    _access: WriteAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    identity: ClientIdentity,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>
+) -> Result<OkResponder<Vec<ReplayedUpdate>>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    state.check_client(&manager_id, &identity, &data_source_id)?;
+    state_op_to_responder(state.replay(&manager_id, &data_source_id).await)
+}
+
+#[get("/<manager_id>/source/<data_source_id>/tags?<namespace>")]
+pub async fn tags(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>
+) -> Result<OkResponder<BTreeSet<String>>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    state_op_to_responder(state.tags(&manager_id, &data_source_id).await)
+}
+
+/// Replaces the tags of a data source with the JSON array of strings sent.
+#[put("/<manager_id>/source/<data_source_id>/tags?<namespace>", format = "json", data = "<tags>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn set_tags(
+    _access: WriteAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    identity: ClientIdentity,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>,
+    tags: Json<BTreeSet<String>>
+) -> Result<OkResponder<()>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    state.check_client(&manager_id, &identity, &data_source_id)?;
+    state_op_to_responder(state.set_tags(&manager_id, &data_source_id, tags.into_inner()).await)
+}
+
+#[get("/<manager_id>/source/<data_source_id>/exists?<namespace>")]
+pub async fn source_exists(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>
+) -> Result<OkResponder<()>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    state_op_to_responder(state.source_exists(&manager_id, &data_source_id).await)
+}
+
+/// Responds with the latest value of a data source along with its `ETag`. Requests sent with the same tag in
+/// `If-None-Match` get `304 Not Modified` without a body instead, as long as the value hasn't changed. While the
+/// manager reloads, the data source's last known good value is sent with a `Florust-Stale` header.
+#[get("/<manager_id>/source/<data_source_id>/latest?<namespace>")]
+pub async fn latest_data(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    if_none_match: IfNoneMatch,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>
+) -> Result<LatestResponder, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    let latest = state.latest_data(&manager_id, &data_source_id).await?;
+    Ok(LatestResponder::new(latest, &if_none_match))
+}
+
+/// Responds with the latest values of every data source registered under a namespace, keyed by id.
+#[get("/<manager_id>/latest_bulk?<namespace>")]
+pub async fn latest_bulk(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    namespace: Option<String>
+) -> Result<OkResponder<HashMap<String, LatestBulkResult>>, DataSourceError> {
+    state_op_to_responder(state.latest_bulk(&manager_id, &namespace.unwrap_or_default(), None).await)
+}
+
+/// Responds with the latest values of the data sources whose ids are sent as a JSON array, keyed by id. Data
+/// sources that can't be read, like ones that aren't registered, are keyed to the error reading them failed
+/// with instead.
+#[post("/<manager_id>/latest_bulk?<namespace>", format = "json", data = "<sources>")]
+pub async fn latest_bulk_of(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    namespace: Option<String>,
+    sources: Json<Vec<String>>
+) -> Result<OkResponder<HashMap<String, LatestBulkResult>>, DataSourceError> {
+    state_op_to_responder(state.latest_bulk(&manager_id, &namespace.unwrap_or_default(), Some(sources.into_inner())).await)
+}
+
+/// Responds with the first value a data source logs with a sequence number greater than `since`, waiting up to
+/// `timeout` seconds for it, for clients that can't open a websocket to `stream`. Responds right away if such a
+/// value was already logged, and with `204 No Content` if none is logged in time.
+#[get("/<manager_id>/source/<data_source_id>/wait?<namespace>&<since>&<timeout>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn wait(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>,
+    since: Option<u64>,
+    timeout: Option<f64>
+) -> Result<OptionalResponder<StreamedDataPoint>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    let timeout = timeout
+        .and_then(|timeout| Duration::try_from_secs_f64(timeout).ok())
+        .unwrap_or(DEFAULT_WAIT_TIMEOUT);
+
+    optional_state_op_to_responder(state.wait_for_data(&manager_id, &data_source_id, since.unwrap_or(0), timeout).await)
+}
+
+/// Responds with every value retained for a data source, or with a page of them along with the cursor of the
+/// page before it if `limit` or `before` is given. The values are reduced to at most `downsample` data points
+/// representing them, picked using `method`, if `downsample` is given.
+#[get("/<manager_id>/source/<data_source_id>/history?<namespace>&<downsample>&<method>&<page..>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn history(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>,
+    downsample: Param<NonZeroUsize>,
+    method: Param<Downsampling>,
+    page: PageQuery
+) -> Result<Either<OkResponder<Vec<DataPoint<ServedValue>>>, OkResponder<HistoryPage<ServedValue>>>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    let downsample = downsample.0.map(|max_points| Downsample {
+        max_points,
+        method: method.0.unwrap_or_default()
+    });
+
+    if page.is_paged() {
+        state_op_to_responder(state.history_page(&manager_id, &data_source_id, &page, downsample).await).map(Either::Right)
+    }
+    else if let Some(downsample) = downsample {
+        state_op_to_responder(state.downsampled_history(&manager_id, &data_source_id, downsample).await).map(Either::Left)
+    }
+    else {
+        state_op_to_responder(state.history(&manager_id, &data_source_id).await).map(Either::Left)
+    }
+}
+
+#[get("/<manager_id>/source/<data_source_id>/last_error?<namespace>")]
+pub async fn last_error(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>
+) -> Result<OptionalResponder<LastError>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    optional_state_op_to_responder(state.last_error(&manager_id, &data_source_id).await)
+}
+
+#[get("/<manager_id>/source/<data_source_id>/aggregate?<namespace>&<query..>")]
+pub async fn aggregate(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>,
+    query: AggregationQuery
+) -> Result<OptionalResponder<UntaggedServedValue>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    optional_state_op_to_responder(state.aggregate(&manager_id, &data_source_id, query.function).await)
+}
+
+#[get("/<manager_id>/source/<data_source_id>/rate?<namespace>")]
+pub async fn rate(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>
+) -> Result<OkResponder<DataPoint<f64>>, DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    state_op_to_responder(state.rate(&manager_id, &data_source_id).await)
+}
+
+#[get("/<manager_id>/aggregate_sources?<namespace>")]
+pub async fn aggregate_sources(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    namespace: Option<String>
+) -> Result<OptionalResponder<DataPoint<DataType>>, DataSourceError> {
+    optional_state_op_to_responder(state.aggregate_sources(&manager_id, &namespace.unwrap_or_default()).await)
+}
+
+#[get("/<manager_id>/source/<data_source_id>/export.csv?<namespace>&<page..>")]
+pub async fn export_csv(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    manager_id: String,
+    data_source_id: String,
+    namespace: Option<String>,
+    page: PageQuery
+) -> Result<(ContentType, TextStream![String + '_]), DataSourceError> {
+    let data_source_id = NamespacedId::new(namespace.unwrap_or_default(), data_source_id);
+    Ok((ContentType::CSV, state.export_csv(&manager_id, &data_source_id, &page).await?))
+}
+
+#[get("/<manager_id>/stream?<namespace>&<source>")]
+pub fn stream(
+    _access: ReadAccess,
+    _manager: KnownManager,
+    state: &State<FlorustState>,
+    ws: WebSocket,
+    manager_id: String,
+    namespace: Option<String>,
+    source: Option<String>
+) -> Result<Channel<'static>, DataSourceError> {
+    let receiver = state.subscribe(&manager_id)?;
+    let namespace = namespace.unwrap_or_default();
+    let source = source
+        .map(|source| state.normalize_id(&manager_id, &NamespacedId::new(namespace.clone(), source)).map(|id| id.id))
+        .transpose()?;
+
+    Ok(stream_data_points(ws, receiver, namespace, source))
+}
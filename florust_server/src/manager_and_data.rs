@@ -1,15 +1,58 @@
-use std::{collections::HashMap, result};
+use std::{
+    any::Any,
+    borrow::Cow,
+    cmp,
+    collections::{BTreeSet, HashMap},
+    fmt::{self, Display, Formatter},
+    future::Future,
+    panic::AssertUnwindSafe,
+    result,
+    sync::{Arc, Mutex, atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering}},
+    thread,
+    time::{Duration, Instant}
+};
 
-use florust_common::server::{IIntegerDataSourceManager, UIntegerDataSourceManager, FloatDataSourceManager, FlorustServerPluginError};
-use rocket::{async_trait, tokio::sync::RwLock, serde::{Serialize, Deserialize}};
+use chrono::{DateTime, Utc};
+use florust_common::server::{self, capabilities, DataSourceManager, DataSourceManagerError, FormatValue, IIntegerDataSourceManager, UIntegerDataSourceManager, FloatDataSourceManager, Float32DataSourceManager, BooleanDataSourceManager, StringDataSourceManager, FloatVecDataSourceManager, I128DataSourceManager, U128DataSourceManager, FlorustServerPluginError};
+use tracing::warn;
+use rocket::{async_trait, futures::FutureExt, tokio::{io::{AsyncRead, AsyncReadExt}, sync::RwLock, time}, serde::{Serialize, Serializer, Deserialize}, request::FromSegments, http::uri::{Segments, fmt::Path}};
 use thiserror::Error;
+use toml::Table;
+use libloading::Library;
 
-use crate::circular_vec::CircularVec;
+use crate::{circular_vec::CircularVec, config, large_integers, non_finite, schema::RegistrationSchema, transform::SharedTransforms};
+
+/// A single value logged for a data source, along with when it was measured. If the data source manager
+/// didn't report a measurement time, the time at which the server received the data is used instead.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct DataPoint<T> {
+    pub value: T,
+    pub timestamp: DateTime<Utc>
+}
+
+impl DataPoint<DataType> {
+    /// Prepares the data point to be sent to clients, see [`ManagerAndData::serve_value`].
+    pub fn served_by(self, manager: &dyn ManagerAndData) -> DataPoint<ServedValue> {
+        DataPoint {
+            value: manager.serve_value(self.value),
+            timestamp: self.timestamp
+        }
+    }
+}
+
+/// The most recent error a manager failed an update from a data source with, along with when it happened.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct LastError {
+    pub error: DataSourceManagerError,
+    pub occurred_at: DateTime<Utc>
+}
 
 enum DataSourceStatus<T> where T: Send + Sync {
-    Registered(CircularVec<T>),
+    Registered(CircularVec<DataPoint<T>>),
     RegisteredNoData,
-    Deregistered(CircularVec<T>)
+    Deregistered(CircularVec<DataPoint<T>>)
 }
 
 impl<T> DataSourceStatus<T> where T: Send + Sync {
@@ -20,15 +63,224 @@ impl<T> DataSourceStatus<T> where T: Send + Sync {
         }
     }
 
-    fn data_or_err<O: FnOnce() -> ManagerAndDataError>(&self, op: O) -> Result<&CircularVec<T>> {
+    fn data_or_err<O: FnOnce() -> ManagerAndDataError>(&self, op: O) -> Result<&CircularVec<DataPoint<T>>> {
         match self {
             Self::Registered(data) | Self::Deregistered(data) => Ok(data),
             Self::RegisteredNoData => Err(op())
         }
     }
+
+    /// Logs a new data point for a registered data source, creating its logged data if this is its first
+    /// data point. Deregistered data sources are left untouched.
+    fn log_data(&mut self, data_point: DataPoint<T>, max_logged_data_size: usize) {
+        match self {
+            Self::RegisteredNoData => {
+                let mut logged_data = CircularVec::new(max_logged_data_size);
+                logged_data.append(data_point);
+                *self = Self::Registered(logged_data);
+            },
+            Self::Registered(logged_data) => logged_data.append(data_point),
+            Self::Deregistered(_) => ()
+        }
+    }
+
+    /// The value most recently logged for a registered data source, if it logged any yet.
+    fn latest_value(&self) -> Option<&T> {
+        match self {
+            Self::Registered(logged_data) => logged_data.latest().map(|data_point| &data_point.value),
+            Self::RegisteredNoData | Self::Deregistered(_) => None
+        }
+    }
+}
+
+/// Fails if any of `values` sent by `id` is lower than the value before it, or can't be compared to it like NaN,
+/// the first being compared against `previous`, the value last logged for `id`. `wrap` turns values into a
+/// [`DataType`] to report them.
+fn check_monotonic<'a, T: PartialOrd + Clone + 'a>(
+    id: &NamespacedId,
+    mut previous: Option<&'a T>,
+    values: impl IntoIterator<Item = &'a T>,
+    wrap: impl Fn(T) -> DataType
+) -> Result<()> {
+    for value in values {
+        let regressed = |previous: &&T| matches!(value.partial_cmp(*previous), None | Some(cmp::Ordering::Less));
+        if let Some(previous) = previous.filter(regressed) {
+            return Err(ManagerAndDataError::NonMonotonic {
+                data_source_id: id.to_string(),
+                previous: wrap(previous.clone()).to_string(),
+                value: wrap(value.clone()).to_string()
+            });
+        }
+        previous = Some(value);
+    }
+
+    Ok(())
+}
+
+/// Everything the server keeps track of for a single data source.
+struct DataSource<T> where T: Send + Sync {
+    status: DataSourceStatus<T>,
+    /// The data the data source provided when it registered, if any. Passed along to the data source manager
+    /// with every update.
+    registration_data: Option<Vec<u8>>,
+    /// When the data source last registered or logged an update, used to find data sources that went idle.
+    last_active: Instant,
+    /// The most recent error the manager failed an update from the data source with, if it ever did.
+    last_error: Option<LastError>,
+    /// The most recent updates the data source sent, as it sent them, if the server is configured to retain
+    /// them.
+    raw_updates: CircularVec<RawUpdate>,
+    /// The tags the data source is organized by. They're only kept by the server, and never passed to the
+    /// manager.
+    tags: BTreeSet<String>
+}
+
+impl<T> DataSource<T> where T: Send + Sync {
+    fn new(registration_data: Option<Vec<u8>>, max_raw_updates: usize, tags: BTreeSet<String>) -> DataSource<T> {
+        DataSource {
+            status: DataSourceStatus::RegisteredNoData,
+            registration_data,
+            last_active: Instant::now(),
+            last_error: None,
+            raw_updates: CircularVec::new(max_raw_updates),
+            tags
+        }
+    }
+
+    /// Remembers the error the manager failed an update from the data source with, if it failed it, passing
+    /// `result` on either way. Errors that didn't come from the manager, like updates rejected before reaching
+    /// it, aren't remembered.
+    fn record_error<R>(&mut self, result: Result<R>) -> Result<R> {
+        let error = match &result {
+            Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::DataSourceManager(err))) => err.clone(),
+            Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::PluginPanicked { .. })) => DataSourceManagerError::Panicked,
+            _ => return result
+        };
+
+        self.last_error = Some(LastError {
+            error,
+            occurred_at: Utc::now()
+        });
+        result
+    }
+
+    /// Retains `data`, an update received at `received_at`, as the data source sent it, if updates are retained.
+    /// `batch` is whether it was a batch of updates.
+    fn retain_update(&mut self, data: &[u8], received_at: DateTime<Utc>, batch: bool) {
+        if self.raw_updates.max_size() == 0 {
+            return;
+        }
+
+        self.raw_updates.append(RawUpdate {
+            data: data.to_vec(),
+            received_at,
+            batch
+        });
+    }
+}
+
+/// An update retained as the data source sent it, so it can be passed to the manager again later.
+struct RawUpdate {
+    data: Vec<u8>,
+    received_at: DateTime<Utc>,
+    /// Whether the update was a batch of updates, which is replayed as a batch.
+    batch: bool
+}
+
+type LoggedData<T> = RwLock<DataSource<T>>;
+
+/// How long calls into a manager may take before they're abandoned, unless configured otherwise.
+pub const DEFAULT_MANAGER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many calls in a row may time out before a manager is considered unhealthy.
+pub const MAX_CONSECUTIVE_TIMEOUTS: u32 = 3;
+
+/// How many of its manager's expected intervals a data source may go without an update before it's stale.
+pub const STALE_INTERVALS: u32 = 3;
+
+/// How long a data source whose manager expects an update every `interval` may go without one before it's stale,
+/// or `None` if that's too long to represent, in which case it never goes stale.
+fn stale_after(interval: Duration) -> Option<Duration> {
+    interval.checked_mul(STALE_INTERVALS)
+}
+
+/// Awaits `call`, a call into the manager with the id `manager_id` made on behalf of the data source `source`,
+/// failing with [`DataSourceManagerError::Timeout`] if it takes longer than `timeout`. This keeps a hanging
+/// manager from tying up the server, as long as the manager hangs while awaiting rather than by blocking the
+/// thread it runs on.
+async fn time_limited<T>(
+    timeout: Duration,
+    manager_id: &str,
+    source: &NamespacedId,
+    call: impl Future<Output = server::Result<T>>
+) -> server::Result<T> {
+    match time::timeout(timeout, call).await {
+        Ok(result) => result,
+        Err(_) => {
+            warn!(
+                "Manager (id: {}) timed out handling data source (id: {}) after {:?}",
+                manager_id,
+                source,
+                timeout
+            );
+            Err(DataSourceManagerError::Timeout)
+        }
+    }
+}
+
+/// Identifies a data source. Every data source belongs to a namespace, which isolates it from data sources
+/// with the same id in other namespaces, like those of another tenant. Data sources registered without a
+/// namespace belong to the empty namespace.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct NamespacedId {
+    pub namespace: String,
+    pub id: String
+}
+
+impl NamespacedId {
+    pub fn new(namespace: impl Into<String>, id: impl Into<String>) -> NamespacedId {
+        NamespacedId {
+            namespace: namespace.into(),
+            id: id.into()
+        }
+    }
+}
+
+impl Display for NamespacedId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.namespace.is_empty() {
+            write!(f, "{}", self.id)
+        }
+        else {
+            write!(f, "{}/{}", self.namespace, self.id)
+        }
+    }
+}
+
+/// Parses either `<id>`, for data sources in the empty namespace, or `<namespace>/<id>`.
+impl<'r> FromSegments<'r> for NamespacedId {
+    type Error = &'static str;
+
+    fn from_segments(segments: Segments<'r, Path>) -> result::Result<Self, Self::Error> {
+        match (segments.get(0), segments.get(1), segments.len()) {
+            (Some(id), None, 1) => Ok(NamespacedId::new("", id)),
+            (Some(namespace), Some(id), 2) => Ok(NamespacedId::new(namespace, id)),
+            _ => Err("expected a data source id, optionally preceded by a namespace")
+        }
+    }
 }
 
-type LoggedData<T> = RwLock<DataSourceStatus<T>>;
+/// The body of an update, as passed to the manager.
+enum Update<'a> {
+    /// The whole update, along with the `traceparent` header it was sent with, if any.
+    Buffered {
+        data: &'a [u8],
+        trace_id: Option<&'a str>
+    },
+    /// The update as it's read, for managers that parse updates incrementally.
+    Streamed(&'a mut (dyn AsyncRead + Send + Unpin))
+}
 
 type IIntegerDataManager = Box<IIntegerDataSourceManager>;
 type IIntegerLoggedData = LoggedData<i64>;
@@ -39,12 +291,158 @@ type UIntegerLoggedData = LoggedData<u64>;
 type FloatDataManager = Box<FloatDataSourceManager>;
 type FloatLoggedData = LoggedData<f64>;
 
-#[derive(Serialize, Deserialize)]
+type Float32DataManager = Box<Float32DataSourceManager>;
+type Float32LoggedData = LoggedData<f32>;
+
+type BooleanDataManager = Box<BooleanDataSourceManager>;
+type BooleanLoggedData = LoggedData<bool>;
+
+type StringDataManager = Box<StringDataSourceManager>;
+type StringLoggedData = LoggedData<String>;
+
+type FloatVecDataManager = Box<FloatVecDataSourceManager>;
+type FloatVecLoggedData = LoggedData<Vec<f64>>;
+
+type I128DataManager = Box<I128DataSourceManager>;
+type I128LoggedData = LoggedData<i128>;
+
+type U128DataManager = Box<U128DataSourceManager>;
+type U128LoggedData = LoggedData<u128>;
+
+/// (De)serializes 128-bit integers as strings, as most JSON parsers read numbers as doubles, which can't hold
+/// them without losing precision.
+mod string_value {
+    use std::{fmt::Display, result, str::FromStr};
+
+    use rocket::serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> result::Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>
+    {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(crate = "rocket::serde")]
 pub enum DataType {
-    IInteger(i64),
-    UInteger(u64),
-    Float(f64)
+    IInteger(#[serde(with = "large_integers")] i64),
+    UInteger(#[serde(with = "large_integers")] u64),
+    Float(#[serde(with = "non_finite::float")] f64),
+    Float32(#[serde(with = "non_finite::float32")] f32),
+    Boolean(bool),
+    String(String),
+    FloatVec(#[serde(with = "non_finite::float_vec")] Vec<f64>),
+    I128(#[serde(with = "string_value")] i128),
+    U128(#[serde(with = "string_value")] u128)
+}
+
+/// Serializes a [`DataType`] as just its value, like `2.0` rather than `{"Float": 2.0}`.
+pub struct UntaggedDataType(pub DataType);
+
+impl Serialize for UntaggedDataType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        match &self.0 {
+            DataType::IInteger(value) => large_integers::serialize(value, serializer),
+            DataType::UInteger(value) => large_integers::serialize(value, serializer),
+            DataType::Float(value) => non_finite::float::serialize(value, serializer),
+            DataType::Float32(value) => non_finite::float32::serialize(value, serializer),
+            DataType::Boolean(value) => value.serialize(serializer),
+            DataType::String(value) => value.serialize(serializer),
+            DataType::FloatVec(value) => non_finite::float_vec::serialize(value, serializer),
+            DataType::I128(value) => string_value::serialize(value, serializer),
+            DataType::U128(value) => string_value::serialize(value, serializer)
+        }
+    }
+}
+
+/// Serializes a [`ServedValue`] as just its value, like [`UntaggedDataType`] does for logged values.
+pub struct UntaggedServedValue(pub ServedValue);
+
+impl Serialize for UntaggedServedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        match &self.0 {
+            ServedValue::Logged(value) => UntaggedDataType(value.clone()).serialize(serializer),
+            ServedValue::Serialized(_, serialized) => serialized.serialize(serializer)
+        }
+    }
+}
+
+/// Formats the value using [`FormatValue`], for values that aren't formatted by their manager.
+impl Display for DataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Self::IInteger(value) => value.format_value(),
+            Self::UInteger(value) => value.format_value(),
+            Self::Float(value) => value.format_value(),
+            Self::Float32(value) => value.format_value(),
+            Self::Boolean(value) => value.format_value(),
+            Self::String(value) => value.format_value(),
+            Self::FloatVec(value) => value.format_value(),
+            Self::I128(value) => value.format_value(),
+            Self::U128(value) => value.format_value()
+        };
+
+        f.write_str(&value)
+    }
+}
+
+impl DataType {
+    /// The value as a float, if it's numeric.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::IInteger(value) => Some(*value as f64),
+            Self::UInteger(value) => Some(*value as f64),
+            Self::Float(value) => Some(*value),
+            Self::Float32(value) => Some(*value as f64),
+            Self::I128(value) => Some(*value as f64),
+            Self::U128(value) => Some(*value as f64),
+            Self::Boolean(_) | Self::String(_) | Self::FloatVec(_) => None
+        }
+    }
+
+    /// The index and name of the variant the value is serialized as.
+    fn variant(&self) -> (u32, &'static str) {
+        match self {
+            Self::IInteger(_) => (0, "IInteger"),
+            Self::UInteger(_) => (1, "UInteger"),
+            Self::Float(_) => (2, "Float"),
+            Self::Float32(_) => (3, "Float32"),
+            Self::Boolean(_) => (4, "Boolean"),
+            Self::String(_) => (5, "String"),
+            Self::FloatVec(_) => (6, "FloatVec"),
+            Self::I128(_) => (7, "I128"),
+            Self::U128(_) => (8, "U128")
+        }
+    }
+}
+
+/// A value as it's sent to clients by the endpoints that read values back. Values of managers that serialize
+/// their values themselves are sent the way their manager serialized them, under the same variant as the
+/// [`DataType`] they were logged as.
+#[derive(Clone)]
+pub enum ServedValue {
+    Logged(DataType),
+    Serialized(DataType, serde_json::Value)
+}
+
+impl Serialize for ServedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        match self {
+            Self::Logged(value) => value.serialize(serializer),
+            Self::Serialized(value, serialized) => {
+                let (index, variant) = value.variant();
+                serializer.serialize_newtype_variant("DataType", index, variant, serialized)
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Error, Debug)]
@@ -55,144 +453,1156 @@ pub enum ManagerAndDataError {
     #[error("Attempted to access data from a data source but it has no reported data")]
     NoData,
     #[error("Attempted to access data from a data source but an out of bounds index was used")]
-    IndexOutOfBounds
+    IndexOutOfBounds,
+    #[error("Destructive operation attempted without being confirmed")]
+    ConfirmationRequired,
+    #[error("Data source ({source_id}) exceeded its rate limit, retry in {retry_after} seconds")]
+    RateLimited {
+        source_id: String,
+        retry_after: u64
+    },
+    #[error("Manager ({manager_id}) is handling too many updates at once, retry in {retry_after} seconds")]
+    Overloaded {
+        manager_id: String,
+        retry_after: u64
+    },
+    #[error("Uploaded data couldn't be read: {0}")]
+    InvalidUpload(String),
+    #[error("Registration data doesn't match the manager's schema: {}", .0.join("; "))]
+    InvalidRegistration(Vec<String>),
+    #[error("Uploaded data exceeds the size limit of {0} bytes")]
+    PayloadTooLarge(usize),
+    #[error("Client isn't allowed to act on behalf of data source ({0})")]
+    Forbidden(String),
+    #[error("Data source ({data_source_id}) isn't allowed to register with manager ({manager_id})")]
+    RegistrationDenied {
+        manager_id: String,
+        data_source_id: String
+    },
+    #[error("Manager expects updates of type {expected}, but was sent {}", .actual.as_deref().unwrap_or("no content type"))]
+    UnsupportedContentType {
+        expected: String,
+        actual: Option<String>
+    },
+    #[error("Update from data source ({0}) wasn't signed with its secret")]
+    InvalidSignature(String),
+    #[error("Aggregation ({0}) can only be used on numeric values")]
+    UnsupportedAggregation(String),
+    #[error("Rate of change can't be computed: {0}")]
+    RateUndefined(String),
+    #[error("No loaded plugin was loaded from {0}")]
+    PluginNotLoaded(String),
+    #[error("Plugin couldn't be reloaded, the loaded one is kept: {0}")]
+    ReloadFailed(String),
+    #[error("Data source ({data_source_id}) sent {value}, which is lower than its last value {previous}, resend it with allow_reset to accept it")]
+    NonMonotonic {
+        data_source_id: String,
+        previous: String,
+        value: String
+    },
+    #[error("Manager ({manager_id}) lacks capabilities the data source requires: {}", .missing.join(", "))]
+    MissingCapabilities {
+        manager_id: String,
+        missing: Vec<String>
+    }
 }
 
 pub type Result<T> = result::Result<T, ManagerAndDataError>;
 
+/// Checks whether something may be done to a data source, failing with why not.
+pub type SourceCheck<'a> = dyn Fn(&NamespacedId) -> Result<()> + Sync + 'a;
+
+/// The outcome of deregistering every data source of a manager at once.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DeregisterAllReport {
+    pub deregistered: Vec<NamespacedId>,
+    /// Data sources that couldn't be deregistered, like ones the client may not act on behalf of, or ones the
+    /// manager failed to deregister, which remain registered.
+    pub failed: Vec<DeregistrationFailure>
+}
+
+/// A data source that couldn't be deregistered, along with why.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DeregistrationFailure {
+    pub source: NamespacedId,
+    pub error: ManagerAndDataError
+}
+
+/// The outcome of reloading the plugin of a single manager.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReloadReport {
+    pub manager_id: String,
+    /// Data sources that were registered with the reloaded manager again.
+    pub reregistered: Vec<NamespacedId>,
+    /// Data sources the reloaded manager failed to register again, which are left deregistered.
+    pub failed: Vec<RegistrationFailure>
+}
+
+/// A data source that couldn't be registered, along with why.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RegistrationFailure {
+    pub source: NamespacedId,
+    pub error: ManagerAndDataError
+}
+
+/// A single data source to be registered by a bulk registration.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct BulkRegistration {
+    #[serde(default)]
+    pub namespace: String,
+    pub id: String,
+    pub data: Option<Vec<u8>>,
+    /// The secret the data source signs its updates with, if it signs them.
+    #[serde(default)]
+    pub signing_secret: Option<String>,
+    /// The capabilities the data source needs its manager to have.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// The tags the data source is registered with, none of which may be empty.
+    #[serde(default)]
+    pub tags: BTreeSet<String>
+}
+
+/// The outcome of registering a single data source of a bulk registration. `error` is set if the data source
+/// wasn't registered.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct BulkRegistrationResult {
+    pub source: NamespacedId,
+    pub registered: bool,
+    pub error: Option<ManagerAndDataError>
+}
+
+/// The latest value of a single data source read by a bulk read. `error` is set if the data source couldn't be
+/// read, like when it isn't registered.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LatestBulkResult {
+    pub value: Option<DataPoint<ServedValue>>,
+    /// Whether `value` is the last known good value, sent while the manager reloads.
+    pub stale: bool,
+    pub error: Option<ManagerAndDataError>
+}
+
+/// The outcome of passing a single retained update to the manager again. `value` is set if the manager parsed
+/// the update, `error` otherwise. Retained batches have an outcome for every value the manager parses from them.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReplayedUpdate {
+    /// When the server originally received the update.
+    pub received_at: DateTime<Utc>,
+    pub value: Option<DataPoint<DataType>>,
+    pub error: Option<ManagerAndDataError>
+}
+
+/// Information about a loaded data source manager, for frontends to describe the data it produces.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ManagerInfo {
+    pub manager_id: &'static str,
+    pub data_type: &'static str,
+    pub unit: Option<&'static str>,
+    pub description: Option<&'static str>,
+    pub capabilities: Vec<&'static str>,
+    /// The content type of the updates the manager parses, if it only parses one.
+    pub expected_content_type: Option<&'static str>,
+    /// How often, in seconds, data sources are expected to send updates, if the manager expects them on a
+    /// schedule.
+    pub expected_interval: Option<f64>,
+    /// How many data sources may be registered with the manager at once, if it limits them.
+    pub max_sources: Option<usize>
+}
+
+/// A loaded data source manager, as listed by `/managers` for clients discovering which managers exist.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ManagerSummary {
+    pub manager_id: &'static str,
+    pub value_type: &'static str,
+    pub registered_source_count: usize
+}
+
+/// An overview of what a manager has ingested since the server started, across all of its data sources.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ManagerStats {
+    pub manager_id: &'static str,
+    pub total_updates: u64,
+    pub update_errors: u64,
+    pub registered_sources: usize,
+    pub updates_last_minute: u64,
+    /// How long, in seconds, data sources usually go between updates, going by the data points logged for
+    /// them. `None` if no data source has logged more than one data point yet.
+    pub average_update_interval: Option<f64>
+}
+
 #[async_trait]
 pub trait ManagerAndData: Send + Sync {
     fn manager_id(&self) -> &'static str;
 
-    async fn register(&self, id: String) -> Result<()>;
+    /// The name of the type of values the manager produces, as used for `data_type` in plugin configs.
+    fn data_type(&self) -> &'static str;
+
+    fn unit(&self) -> Option<&'static str>;
+
+    fn description(&self) -> Option<&'static str>;
+
+    /// The optional methods the manager implements, see [`capabilities`].
+    fn capabilities(&self) -> u32;
+
+    fn expected_content_type(&self) -> Option<&'static str>;
+
+    fn expected_interval(&self) -> Option<Duration>;
+
+    fn max_sources(&self) -> Option<usize>;
+
+    /// Whether the manager has behaved so far. Managers become unhealthy once they panic, or while their calls
+    /// keep timing out.
+    fn is_healthy(&self) -> bool;
+
+    /// Whether the manager was loaded from a plugin's library, which stays loaded for as long as the manager
+    /// exists. Managers built into the server have no library.
+    fn library_loaded(&self) -> bool;
+
+    /// Returns the canonical form of the data source id `id`, as decided by the manager. Every other method
+    /// expects to be given normalized ids.
+    fn normalize_id(&self, id: &NamespacedId) -> NamespacedId;
+
+    /// Returns the textual form of `value`, as decided by the manager. Values of a different type than the
+    /// manager produces are formatted the default way.
+    fn value_to_string(&self, value: &DataType) -> String;
+
+    /// Prepares `value` to be sent to clients, serialized by the manager if it serializes its values itself.
+    /// Values the manager fails to serialize are sent as they were logged.
+    fn serve_value(&self, value: DataType) -> ServedValue;
+
+    /// Registers the data source `id` tagged with `tags`, returning the value it starts out with, if its manager
+    /// produced one.
+    async fn register(&self, id: NamespacedId, tags: BTreeSet<String>) -> Result<Option<DataPoint<DataType>>>;
+
+    /// Registers the data source `id` with `data`, see [`register`](ManagerAndData::register).
+    async fn register_with_data(&self, id: NamespacedId, data: &[u8], tags: BTreeSet<String>) -> Result<Option<DataPoint<DataType>>>;
+
+    /// Registers the data source `id` with `data` and tagged with `tags`, and logs `data` as its first update.
+    /// The data source is only registered if the update succeeds, and no other request sees it in between.
+    async fn register_with_initial_value(&self, id: NamespacedId, data: &[u8], tags: BTreeSet<String>) -> Result<DataPoint<DataType>>;
+
+    /// Checks whether the data source `id` could register with `data`, without registering it.
+    async fn can_register(&self, id: &NamespacedId, data: Option<&[u8]>) -> Result<()>;
+
+    /// Deregisters the data source `id`. If the manager fails to deregister it, the data source stays registered
+    /// and the error is returned, unless `force` is set, in which case the error is only logged and the data
+    /// source is deregistered regardless.
+    async fn deregister(&self, id: &NamespacedId, force: bool) -> Result<()>;
+
+    /// Deregisters the data source `id` with `data`, see [`deregister`](ManagerAndData::deregister).
+    async fn deregister_with_data(&self, id: &NamespacedId, data: &[u8], force: bool) -> Result<()>;
+
+    /// Deregisters every registered data source `allowed` accepts, forgetting everything logged for them. Data
+    /// sources `allowed` rejects stay registered, and are reported as failed with its error.
+    async fn deregister_all(&self, allowed: &SourceCheck<'_>) -> DeregisterAllReport;
+
+    /// Deregisters every data source that hasn't registered or logged an update within `timeout`, keeping what
+    /// was logged for them like a regular deregistration.
+    async fn deregister_idle(&self, timeout: Duration) -> DeregisterAllReport;
+
+    /// Passes an update from `id` to the manager, along with the `traceparent` header it was sent with, if any.
+    /// Monotonic managers reject values lower than the last one logged for `id`, unless `allow_reset` is set.
+    async fn update_data(&self, id: &NamespacedId, data: &[u8], trace_id: Option<&str>, allow_reset: bool) -> Result<DataPoint<DataType>>;
+
+    /// Passes an update from `id` to the manager as it's read from `reader`, for managers that parse updates
+    /// incrementally. Updates to other managers are read in full first, then passed on like any other update.
+    async fn update_data_stream(&self, id: &NamespacedId, reader: &mut (dyn AsyncRead + Send + Unpin), allow_reset: bool) -> Result<DataPoint<DataType>>;
+
+    async fn batch_update_data(&self, id: &NamespacedId, data: &[u8], allow_reset: bool) -> Result<Vec<DataPoint<DataType>>>;
+
+    async fn get_data(&self, id: &NamespacedId, index: usize) -> Result<DataPoint<DataType>>;
+
+    async fn latest_data(&self, id: &NamespacedId) -> Result<Option<DataPoint<DataType>>>;
+
+    async fn history(&self, id: &NamespacedId) -> Result<Vec<DataPoint<DataType>>>;
+
+    /// Passes every update retained for the data source `id` to the manager again, oldest first, returning what
+    /// the manager parses them into now. Nothing is logged, so the data source's history is left untouched.
+    async fn replay(&self, id: &NamespacedId) -> Result<Vec<ReplayedUpdate>>;
+
+    /// Returns the most recent error the manager failed an update from the data source `id` with, if it ever did.
+    async fn last_error(&self, id: &NamespacedId) -> Result<Option<LastError>>;
+
+    /// Asks the manager to reset the running state it keeps for the data source `id`, keeping its history.
+    async fn reset(&self, id: &NamespacedId) -> Result<()>;
+
+    /// Whether the data source `id` is currently registered.
+    async fn is_registered(&self, id: &NamespacedId) -> bool;
+
+    /// Returns the ids of every data source registered under `namespace` that's tagged with every tag in `tags`.
+    async fn registered_sources(&self, namespace: &str, tags: &[String]) -> Vec<String>;
+
+    /// Returns the tags the data source `id` is tagged with.
+    async fn tags(&self, id: &NamespacedId) -> Result<BTreeSet<String>>;
+
+    /// Replaces the tags the data source `id` is tagged with by `tags`.
+    async fn set_tags(&self, id: &NamespacedId, tags: BTreeSet<String>) -> Result<()>;
+
+    /// Returns how many data sources are registered, across every namespace.
+    async fn registered_count(&self) -> usize;
+
+    /// How long registered data sources usually go between updates, averaged over every data source with more
+    /// than one logged data point, or `None` if there are none.
+    async fn average_update_interval(&self) -> Option<Duration>;
+
+    /// Returns every registered data source that hasn't registered or sent an update in [`STALE_INTERVALS`]
+    /// times the manager's expected interval, or none if the manager doesn't expect one.
+    async fn stale_sources(&self) -> Vec<NamespacedId>;
+
+    /// Has the manager combine the latest values of every data source registered under `namespace` into a
+    /// single value, which is returned along with when the most recent of those values was measured. Returns
+    /// `None` if none of the data sources has logged a value yet.
+    async fn aggregate_sources(&self, namespace: &str) -> Result<Option<DataPoint<DataType>>>;
+
+    async fn reconfigure(&self, config: Option<Table>) -> Result<()>;
 
-    async fn register_with_data(&self, id: String, data: &[u8]) -> Result<()>;
+    async fn shutdown(&self) -> Result<()>;
 
-    async fn deregister(&self, id: &str) -> Result<()>;
+    /// Takes the plugin out of a newly loaded manager, to be handed to the running manager with the same id by
+    /// [`replace_plugin`](ManagerAndData::replace_plugin).
+    fn into_plugin(self: Box<Self>) -> Box<dyn Any + Send>;
 
-    async fn deregister_with_data(&self, id: &str, data: &[u8]) -> Result<()>;
+    /// Swaps the manager's plugin for `plugin`, taken from a newly loaded build of it by
+    /// [`into_plugin`](ManagerAndData::into_plugin), keeping everything logged for its data sources. Every
+    /// registered data source is deregistered from the old manager, which is then shut down, and registered
+    /// with the new one using the data it was registered with. The old library is unloaded once calls still
+    /// using it are done.
+    async fn replace_plugin(&self, plugin: Box<dyn Any + Send>) -> Result<ReloadReport>;
+}
+
+/// Copies `value` into memory that's never freed, so it stays valid after the library it came from is unloaded.
+fn leak(value: &str) -> &'static str {
+    Box::leak(value.into())
+}
+
+/// A data source manager along with everything else that comes from its plugin, which is replaced as a whole
+/// when the plugin is reloaded.
+struct Plugin<M> {
+    manager: M,
+    /// Copies of what the manager describes itself with, which stay valid after its library is unloaded.
+    unit: Option<&'static str>,
+    description: Option<&'static str>,
+    expected_content_type: Option<&'static str>,
+    expected_interval: Option<Duration>,
+    max_sources: Option<usize>,
+    /// The schema registration data must match, if the manager has one.
+    registration_schema: Option<RegistrationSchema>,
+    /// The default config exported by the manager's plugin, which new configs are merged over before they're
+    /// passed to the manager.
+    default_config: Option<Table>,
+    /// The library the manager was loaded from, if it came from a plugin. This must be declared after
+    /// `manager` so that the manager is dropped before the library is unloaded. The library is shared by
+    /// every manager loaded from it, and is only unloaded once all of them are dropped.
+    library: Option<Arc<Library>>
+}
 
-    async fn update_data(&self, id: &str, data: &[u8]) -> Result<()>;
+impl<T> Plugin<Box<dyn DataSourceManager<T>>> {
+    fn new(manager: Box<dyn DataSourceManager<T>>) -> Plugin<Box<dyn DataSourceManager<T>>> {
+        let registration_schema = manager
+            .registration_schema()
+            .and_then(|schema| RegistrationSchema::compile(manager.manager_id(), schema));
 
-    async fn get_data(&self, id: &str, index: usize) -> Result<DataType>;
+        Plugin {
+            unit: manager.unit().map(leak),
+            description: manager.description().map(leak),
+            expected_content_type: manager.expected_content_type().map(leak),
+            expected_interval: manager.expected_interval(),
+            max_sources: manager.max_sources(),
+            registration_schema,
+            default_config: None,
+            library: None,
+            manager
+        }
+    }
 }
 
 pub struct IIntegerManagerAndData {
-    manager: IIntegerDataManager,
-    logged_data: RwLock<HashMap<String, IIntegerLoggedData>>,
-    max_logged_data_size: usize
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<IIntegerDataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, IIntegerLoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
 }
 
 pub struct UIntegerManagerAndData {
-    manager: UIntegerDataManager,
-    logged_data: RwLock<HashMap<String, UIntegerLoggedData>>,
-    max_logged_data_size: usize
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<UIntegerDataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, UIntegerLoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
 }
 
 pub struct FloatManagerAndData {
-    manager: FloatDataManager,
-    logged_data: RwLock<HashMap<String, FloatLoggedData>>,
-    max_logged_data_size: usize
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<FloatDataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, FloatLoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
+}
+
+pub struct Float32ManagerAndData {
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<Float32DataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, Float32LoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
+}
+
+pub struct BooleanManagerAndData {
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<BooleanDataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, BooleanLoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
+}
+
+pub struct StringManagerAndData {
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<StringDataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, StringLoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
+}
+
+pub struct FloatVecManagerAndData {
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<FloatVecDataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, FloatVecLoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
+}
+
+pub struct I128ManagerAndData {
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<I128DataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, I128LoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
+}
+
+pub struct U128ManagerAndData {
+    /// The id of the manager, which stays the same when its plugin is reloaded.
+    manager_id: &'static str,
+    /// The manager along with the library it was loaded from, replaced whenever its plugin is reloaded.
+    plugin: Mutex<Arc<Plugin<U128DataManager>>>,
+    logged_data: RwLock<HashMap<NamespacedId, U128LoggedData>>,
+    /// How many of the data sources in `logged_data` are registered, only changed while `logged_data` is
+    /// locked for writing.
+    registered: AtomicUsize,
+    max_logged_data_size: usize,
+    /// How many updates to retain for each data source as they were sent, so they can be replayed.
+    max_raw_updates: usize,
+    /// The transforms updates go through before they reach the manager.
+    transforms: SharedTransforms,
+    /// Whether updates lower than the last value logged for their data source are rejected.
+    monotonic: bool,
+    /// How long calls into the manager may take before they're abandoned.
+    timeout: Duration,
+    /// Whether the manager has behaved so far, this is cleared once the manager panics.
+    healthy: AtomicBool,
+    /// How many calls into the manager in a row have timed out.
+    consecutive_timeouts: AtomicU32
 }
 
 macro_rules! manager_and_data_impl {
-    ($impl_for:ident, $data_manager:ty, $default_val:literal, $data_type:path) => {
+    ($impl_for:ident, $data_manager:ty, $data_type:path, $data_type_name:literal) => {
         impl $impl_for {
             pub fn new(manager: $data_manager, max_logged_data_size: usize) -> $impl_for {
                 $impl_for {
-                    manager,
+                    manager_id: leak(manager.manager_id()),
+                    plugin: Mutex::new(Arc::new(Plugin::new(manager))),
                     logged_data: RwLock::new(HashMap::new()),
-                    max_logged_data_size
+                    registered: AtomicUsize::new(0),
+                    max_logged_data_size,
+                    max_raw_updates: 0,
+                    transforms: SharedTransforms::default(),
+                    monotonic: false,
+                    timeout: DEFAULT_MANAGER_TIMEOUT,
+                    healthy: AtomicBool::new(true),
+                    consecutive_timeouts: AtomicU32::new(0)
                 }
             }
-        }
 
-        #[async_trait]
-        impl ManagerAndData for $impl_for {
-            fn manager_id(&self) -> &'static str {
-                self.manager.manager_id()
+            /// Sets how long calls into the manager may take before they're abandoned.
+            pub fn with_timeout(self, timeout: Duration) -> $impl_for {
+                $impl_for {
+                    timeout,
+                    ..self
+                }
             }
 
-            async fn register(&self, id: String) -> Result<()> {
-                let mut lock = self.logged_data.write().await;
-                match lock.get(&id) {
-                    Some(data_source) => {
-                        let mut data_source = data_source.write().await;
+            /// Sets how many updates to retain for each data source as they were sent, so they can be replayed.
+            /// None are retained unless this is set.
+            pub fn with_raw_history_size(self, max_raw_updates: usize) -> $impl_for {
+                $impl_for {
+                    max_raw_updates,
+                    ..self
+                }
+            }
 
-                        if data_source.is_registered() {
-                            return Err(
-                                ManagerAndDataError::DataSourceManager(
-                                    FlorustServerPluginError::DataSourceAlreadyExists(id)
-                                )
-                            )
-                        }
+            /// Sets the transforms updates go through before they reach the manager. Updates reach the manager as
+            /// they were sent unless this is set.
+            pub fn with_transforms(self, transforms: SharedTransforms) -> $impl_for {
+                $impl_for {
+                    transforms,
+                    ..self
+                }
+            }
 
-                        self.manager.register(id).await.map_err(|err| {
-                            ManagerAndDataError::DataSourceManager(
-                                FlorustServerPluginError::DataSourceManager(err)
-                            )
-                        })?;
-                        *data_source = DataSourceStatus::RegisteredNoData;
-                    }
-                    None => {
-                        self.manager.register(id.clone()).await.map_err(|err| {
-                            ManagerAndDataError::DataSourceManager(
-                                FlorustServerPluginError::DataSourceManager(err)
-                            )
-                        })?;
-                        lock.insert(id, RwLock::new(DataSourceStatus::RegisteredNoData));
-                    }
+            /// Rejects updates lower than the last value logged for their data source, unless they're sent with
+            /// `allow_reset`. Updates may go down freely unless this is set.
+            pub fn with_monotonic(self, monotonic: bool) -> $impl_for {
+                $impl_for {
+                    monotonic,
+                    ..self
                 }
+            }
 
-                Ok(())
+            /// Keeps `library` loaded for as long as the manager exists. Must be called with the library the
+            /// manager was created from.
+            pub fn with_library(mut self, library: Arc<Library>) -> $impl_for {
+                // The plugin can't be shared yet, as the manager hasn't been used.
+                if let Some(plugin) = Arc::get_mut(self.plugin.get_mut().unwrap_or_else(|err| err.into_inner())) {
+                    plugin.library = Some(library);
+                }
+                self
             }
 
-            async fn register_with_data(&self, id: String, data: &[u8]) -> Result<()> {
-                let mut lock = self.logged_data.write().await;
-                match lock.get(&id) {
-                    Some(data_source) => {
-                        let mut data_source = data_source.write().await;
+            /// Sets the default config exported by the manager's plugin, which configs passed to
+            /// [`reconfigure`](ManagerAndData::reconfigure) are merged over.
+            pub fn with_default_config(mut self, default_config: Option<Table>) -> $impl_for {
+                // The plugin can't be shared yet, as the manager hasn't been used.
+                if let Some(plugin) = Arc::get_mut(self.plugin.get_mut().unwrap_or_else(|err| err.into_inner())) {
+                    plugin.default_config = default_config;
+                }
+                self
+            }
 
-                        if data_source.is_registered() {
-                            return Err(
-                                ManagerAndDataError::DataSourceManager(
-                                    FlorustServerPluginError::DataSourceAlreadyExists(id)
-                                )
-                            )
-                        }
+            /// Returns the manager's current plugin. Calls into the manager hold on to the plugin they were made
+            /// with, so its library stays loaded until they're done, even if the plugin is reloaded meanwhile.
+            fn plugin(&self) -> Arc<Plugin<$data_manager>> {
+                self.plugin.lock().unwrap_or_else(|err| err.into_inner()).clone()
+            }
 
-                        self.manager.register_with_data(id, data).await.map_err(|err| {
-                            ManagerAndDataError::DataSourceManager(
-                                FlorustServerPluginError::DataSourceManager(err)
+            /// Awaits `call`, a call into the manager made on behalf of the data source `source`, failing if it
+            /// takes too long or panics. A manager that panics is marked unhealthy, but is still called afterwards.
+            async fn call_manager<T>(&self, source: &NamespacedId, call: impl Future<Output = server::Result<T>>) -> Result<T> {
+                let result = AssertUnwindSafe(time_limited(self.timeout, self.manager_id, source, call)).catch_unwind().await;
+                self.check_panicked(Some(source), result)
+            }
+
+            /// Awaits `call`, a call into the manager that registers, deregisters, or updates the data source
+            /// `source`, like [`call_manager`](Self::call_manager). If the call fails, the manager is told about
+            /// the error before it's returned, if the manager observes its errors.
+            async fn call_manager_observed<T>(&self, source: &NamespacedId, call: impl Future<Output = server::Result<T>>) -> Result<T> {
+                let result = self.call_manager(source, call).await;
+                if let Err(err) = &result {
+                    self.observe_error(source, err).await;
+                }
+                result
+            }
+
+            /// Passes `err`, which a call on behalf of the data source `source` failed with, to the manager's
+            /// [`on_error`](DataSourceManager::on_error), if the manager observes its errors. Errors that didn't
+            /// come from the manager, like calls for data sources that don't exist, aren't passed on.
+            async fn observe_error(&self, source: &NamespacedId, err: &ManagerAndDataError) {
+                if !self.supports(capabilities::ON_ERROR) {
+                    return;
+                }
+
+                let error = match err {
+                    ManagerAndDataError::DataSourceManager(FlorustServerPluginError::DataSourceManager(err)) => err.clone(),
+                    ManagerAndDataError::DataSourceManager(FlorustServerPluginError::PluginPanicked { .. }) => DataSourceManagerError::Panicked,
+                    _ => return
+                };
+
+                // The call isn't made through `call_manager`, as observing an error the manager timed out with
+                // mustn't count as the manager responding in time again.
+                let plugin = self.plugin();
+                let call = time_limited(self.timeout, self.manager_id, source, async {
+                    plugin.manager.on_error(&source.id, &error).await;
+                    Ok(())
+                });
+                match AssertUnwindSafe(call).catch_unwind().await {
+                    Ok(Ok(())) => (),
+                    Ok(Err(err)) => warn!("Manager (id: {}) failed to observe an error of data source (id: {}): {}", self.manager_id, source, err),
+                    Err(_) => {
+                        self.panicked(Some(source));
+                    }
+                }
+            }
+
+            /// Converts the result of a call into the manager that may have panicked while handling `source`. Panics
+            /// are caught by plugins that use [`CatchPanics`](server::CatchPanics), or by
+            /// [`catch_unwind`](FutureExt::catch_unwind) for managers built into the server.
+            fn check_panicked<T>(&self, source: Option<&NamespacedId>, result: thread::Result<server::Result<T>>) -> Result<T> {
+                match &result {
+                    Ok(Err(DataSourceManagerError::Timeout)) => {
+                        self.consecutive_timeouts.fetch_add(1, Ordering::Relaxed);
+                    },
+                    Ok(Ok(_)) => self.consecutive_timeouts.store(0, Ordering::Relaxed),
+                    _ => ()
+                }
+
+                match result {
+                    Ok(Err(DataSourceManagerError::Panicked)) | Err(_) => Err(self.panicked(source)),
+                    Ok(result) => result.map_err(|err| {
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceManager(err)
+                        )
+                    })
+                }
+            }
+
+            /// Marks the manager unhealthy after it panicked while handling `source`, returning the error to report.
+            fn panicked(&self, source: Option<&NamespacedId>) -> ManagerAndDataError {
+                self.healthy.store(false, Ordering::Relaxed);
+                match source {
+                    Some(source) => warn!("Manager (id: {}) panicked handling data source (id: {})", self.manager_id, source),
+                    None => warn!("Manager (id: {}) panicked", self.manager_id)
+                }
+
+                ManagerAndDataError::DataSourceManager(
+                    FlorustServerPluginError::PluginPanicked {
+                        manager_id: self.manager_id.to_string(),
+                        source_id: source.map(NamespacedId::to_string)
+                    }
+                )
+            }
+
+            /// Passes on the outcome of the manager deregistering `source`, unless `force` is set, in which case a
+            /// failure is logged and the deregistration goes ahead regardless, so a manager that keeps failing to
+            /// clean up can't leave the data source's id stuck.
+            fn force_if_failed(&self, source: &NamespacedId, result: Result<()>, force: bool) -> Result<()> {
+                match result {
+                    Err(err) if force => {
+                        warn!(
+                            "Manager (id: {}) failed to deregister data source (id: {}), deregistering it anyway: {}",
+                            self.manager_id,
+                            source,
+                            err
+                        );
+                        Ok(())
+                    },
+                    result => result
+                }
+            }
+
+            /// Passes `data` through the manager's transforms, failing the update if any of them fail.
+            fn transform<'a>(&self, data: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+                self.transforms.apply(data).map_err(|err| ManagerAndDataError::InvalidUpload(err.to_string()))
+            }
+
+            fn supports(&self, capability: u32) -> bool {
+                self.plugin().manager.capabilities() & capability != 0
+            }
+
+            /// Passes an update from `id` to the manager and logs the value it parsed from it.
+            async fn log_update(&self, id: &NamespacedId, update: Update<'_>, allow_reset: bool) -> Result<DataPoint<DataType>> {
+                let received_at = Utc::now();
+                let lock = self.logged_data.read().await;
+
+                let mut data_source = lock
+                    .get(id)
+                    .ok_or(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    )?
+                    .write()
+                    .await;
+
+                if !data_source.status.is_registered() {
+                    return Err(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    );
+                }
+
+                // Updates are retained before the manager sees them, so updates it fails can be replayed once
+                // it's fixed.
+                if let Update::Buffered { data, .. } = &update {
+                    data_source.retain_update(data, received_at, false);
+                }
+
+                let registration_data = data_source.registration_data.as_deref();
+                let val = async {
+                    Ok(match update {
+                        Update::Buffered { data, trace_id } => {
+                            let data = &*self.transform(data)?;
+                            self.validate_data(id, data).await?;
+
+                            let manager = &self.plugin().manager;
+                            if self.supports(capabilities::TRACING) {
+                                self.call_manager_observed(id, manager.update_data_traced(&id.namespace, &id.id, data, registration_data, trace_id)).await?
+                            }
+                            else {
+                                self.call_manager_observed(id, manager.update_data_in_namespace(&id.namespace, &id.id, data, registration_data)).await?
+                            }
+                        },
+                        Update::Streamed(reader) => {
+                            self.call_manager_observed(id, self.plugin().manager.update_data_stream(&id.namespace, &id.id, reader, registration_data)).await?
+                        }
+                    })
+                }.await;
+                let val = data_source.record_error(val)?;
+                if self.monotonic && !allow_reset {
+                    check_monotonic(id, data_source.status.latest_value(), [&val.value], $data_type)?;
+                }
+
+                let data_point = DataPoint {
+                    value: val.value,
+                    timestamp: val.measured_at.unwrap_or(received_at)
+                };
+                let logged = DataPoint {
+                    value: $data_type(data_point.value.clone()),
+                    timestamp: data_point.timestamp
+                };
+                data_source.status.log_data(data_point, self.max_logged_data_size);
+                data_source.last_active = Instant::now();
+
+                Ok(logged)
+            }
+
+            /// Logs the value the data source `id` starts out with as its first value, right after it registered,
+            /// if the manager produces initial values, returning the value logged. `initial_value` is the call into
+            /// the manager producing it, and `wrap` turns it into a [`DataType`]. A failure leaves the data source
+            /// registered without a value, as it did register.
+            async fn log_initial_value<T: Clone + Send + Sync>(
+                &self,
+                id: &NamespacedId,
+                data_source: &mut DataSource<T>,
+                initial_value: impl Future<Output = server::Result<Option<T>>>,
+                wrap: fn(T) -> DataType
+            ) -> Option<DataPoint<DataType>> {
+                if !self.supports(capabilities::INITIAL_VALUE) {
+                    return None;
+                }
+
+                let value = match self.call_manager_observed(id, initial_value).await {
+                    Ok(value) => value?,
+                    Err(err) => {
+                        warn!("Manager (id: {}) failed to produce an initial value for data source (id: {}): {}", self.manager_id, id, err);
+                        return None;
+                    }
+                };
+
+                let data_point = DataPoint {
+                    value,
+                    timestamp: Utc::now()
+                };
+                let logged = DataPoint {
+                    value: wrap(data_point.value.clone()),
+                    timestamp: data_point.timestamp
+                };
+                data_source.status.log_data(data_point, self.max_logged_data_size);
+                Some(logged)
+            }
+
+            /// Checks `data` against the manager's registration schema, then asks the manager whether `id` may
+            /// register with `data`, if the manager validates registrations.
+            async fn validate_registration(&self, id: &NamespacedId, data: Option<&[u8]>) -> Result<()> {
+                if let Some(schema) = &self.plugin().registration_schema {
+                    schema.validate(data)?;
+                }
+
+                if !self.supports(capabilities::VALIDATE_REGISTRATION) {
+                    return Ok(());
+                }
+
+                self.call_manager(id, self.plugin().manager.validate_registration(&id.id, data)).await
+            }
+
+            /// Checks that another data source may register, if the manager limits how many may be registered at
+            /// once.
+            fn check_capacity(&self) -> Result<()> {
+                let Some(max_sources) = self.plugin().max_sources else {
+                    return Ok(());
+                };
+
+                if self.registered.load(Ordering::Relaxed) >= max_sources {
+                    return Err(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceManager(DataSourceManagerError::AtCapacity(max_sources))
+                        )
+                    );
+                }
+                Ok(())
+            }
+
+            /// Asks the manager whether `data` is a valid update from `id`, if the manager validates updates.
+            async fn validate_data(&self, id: &NamespacedId, data: &[u8]) -> Result<()> {
+                if !self.supports(capabilities::VALIDATE_DATA) {
+                    return Ok(());
+                }
+
+                self.call_manager(id, self.plugin().manager.validate_data(&id.id, data)).await
+            }
+        }
+
+        #[async_trait]
+        impl ManagerAndData for $impl_for {
+            fn manager_id(&self) -> &'static str {
+                self.manager_id
+            }
+
+            fn data_type(&self) -> &'static str {
+                $data_type_name
+            }
+
+            fn unit(&self) -> Option<&'static str> {
+                self.plugin().unit
+            }
+
+            fn description(&self) -> Option<&'static str> {
+                self.plugin().description
+            }
+
+            fn capabilities(&self) -> u32 {
+                self.plugin().manager.capabilities()
+            }
+
+            fn expected_content_type(&self) -> Option<&'static str> {
+                self.plugin().expected_content_type
+            }
+
+            fn expected_interval(&self) -> Option<Duration> {
+                self.plugin().expected_interval
+            }
+
+            fn max_sources(&self) -> Option<usize> {
+                self.plugin().max_sources
+            }
+
+            fn is_healthy(&self) -> bool {
+                self.healthy.load(Ordering::Relaxed)
+                    && self.consecutive_timeouts.load(Ordering::Relaxed) < MAX_CONSECUTIVE_TIMEOUTS
+            }
+
+            fn library_loaded(&self) -> bool {
+                self.plugin().library.is_some()
+            }
+
+            fn normalize_id(&self, id: &NamespacedId) -> NamespacedId {
+                NamespacedId::new(id.namespace.clone(), self.plugin().manager.normalize_id(&id.id))
+            }
+
+            fn value_to_string(&self, value: &DataType) -> String {
+                match value {
+                    $data_type(value) => self.plugin().manager.value_to_string(value),
+                    value => value.to_string()
+                }
+            }
+
+            fn serve_value(&self, value: DataType) -> ServedValue {
+                if !self.supports(capabilities::SERIALIZE_VALUE) {
+                    return ServedValue::Logged(value);
+                }
+
+                let serialized = match &value {
+                    $data_type(inner) => self.plugin().manager.serialize_value(inner),
+                    _ => return ServedValue::Logged(value)
+                };
+                match serialized {
+                    Ok(serialized) => ServedValue::Serialized(value, serialized),
+                    Err(err) => {
+                        warn!("Manager (id: {}) failed to serialize a value: {}", self.manager_id, err);
+                        ServedValue::Logged(value)
+                    }
+                }
+            }
+
+            async fn register(&self, id: NamespacedId, tags: BTreeSet<String>) -> Result<Option<DataPoint<DataType>>> {
+                let mut lock = self.logged_data.write().await;
+                match lock.get(&id) {
+                    Some(data_source) => {
+                        let mut data_source = data_source.write().await;
+
+                        if data_source.status.is_registered() {
+                            return Err(
+                                ManagerAndDataError::DataSourceManager(
+                                    FlorustServerPluginError::DataSourceAlreadyExists(id.to_string())
+                                )
+                            )
+                        }
+
+                        self.check_capacity()?;
+                        self.validate_registration(&id, None).await?;
+                        self.call_manager_observed(&id, self.plugin().manager.register_in_namespace(&id.namespace, id.id.clone(), None)).await?;
+                        *data_source = DataSource::new(None, self.max_raw_updates, tags);
+                    }
+                    None => {
+                        self.check_capacity()?;
+                        self.validate_registration(&id, None).await?;
+                        self.call_manager_observed(&id, self.plugin().manager.register_in_namespace(&id.namespace, id.id.clone(), None)).await?;
+                        lock.insert(id.clone(), RwLock::new(DataSource::new(None, self.max_raw_updates, tags)));
+                    }
+                }
+
+                self.registered.fetch_add(1, Ordering::Relaxed);
+
+                let mut data_source = lock[&id].write().await;
+                Ok(self.log_initial_value(&id, &mut data_source, self.plugin().manager.initial_value(&id.id, None), $data_type).await)
+            }
+
+            async fn register_with_data(&self, id: NamespacedId, data: &[u8], tags: BTreeSet<String>) -> Result<Option<DataPoint<DataType>>> {
+                let mut lock = self.logged_data.write().await;
+                match lock.get(&id) {
+                    Some(data_source) => {
+                        let mut data_source = data_source.write().await;
+
+                        if data_source.status.is_registered() {
+                            return Err(
+                                ManagerAndDataError::DataSourceManager(
+                                    FlorustServerPluginError::DataSourceAlreadyExists(id.to_string())
+                                )
                             )
-                        })?;
-                        *data_source = DataSourceStatus::RegisteredNoData;
+                        }
+
+                        self.check_capacity()?;
+                        self.validate_registration(&id, Some(data)).await?;
+                        self.call_manager_observed(&id, self.plugin().manager.register_in_namespace(&id.namespace, id.id.clone(), Some(data))).await?;
+                        *data_source = DataSource::new(Some(data.to_vec()), self.max_raw_updates, tags);
                     }
                     None => {
-                        self.manager.register_with_data(id.clone(), data).await.map_err(|err| {
+                        // The data source is only recorded once the manager accepts it, so a rejected registration
+                        // leaves nothing behind for later updates to be routed to.
+                        self.check_capacity()?;
+                        self.validate_registration(&id, Some(data)).await?;
+                        self.call_manager_observed(&id, self.plugin().manager.register_in_namespace(&id.namespace, id.id.clone(), Some(data))).await?;
+                        lock.insert(id.clone(), RwLock::new(DataSource::new(Some(data.to_vec()), self.max_raw_updates, tags)));
+                    }
+                }
+
+                self.registered.fetch_add(1, Ordering::Relaxed);
+
+                let mut data_source = lock[&id].write().await;
+                Ok(self.log_initial_value(&id, &mut data_source, self.plugin().manager.initial_value(&id.id, Some(data)), $data_type).await)
+            }
+
+            async fn register_with_initial_value(&self, id: NamespacedId, data: &[u8], tags: BTreeSet<String>) -> Result<DataPoint<DataType>> {
+                let received_at = Utc::now();
+                let mut lock = self.logged_data.write().await;
+                if let Some(data_source) = lock.get(&id) {
+                    if data_source.read().await.status.is_registered() {
+                        return Err(
                             ManagerAndDataError::DataSourceManager(
-                                FlorustServerPluginError::DataSourceManager(err)
+                                FlorustServerPluginError::DataSourceAlreadyExists(id.to_string())
                             )
-                        })?;
-                        lock.insert(id, RwLock::new(DataSourceStatus::RegisteredNoData));
+                        );
                     }
                 }
 
-                Ok(())
+                self.check_capacity()?;
+                self.validate_registration(&id, Some(data)).await?;
+                self.call_manager_observed(&id, self.plugin().manager.register_in_namespace(&id.namespace, id.id.clone(), Some(data))).await?;
+
+                let update = async {
+                    let transformed = self.transform(data)?;
+                    self.validate_data(&id, &transformed).await?;
+                    self.call_manager_observed(&id, self.plugin().manager.update_data_in_namespace(&id.namespace, &id.id, &transformed, Some(data))).await
+                }.await;
+                let val = match update {
+                    Ok(val) => val,
+                    Err(err) => {
+                        // The registration is rolled back, so the data source is left the way it was before.
+                        if let Err(rollback_err) = self.call_manager_observed(&id, self.plugin().manager.deregister_in_namespace(&id.namespace, &id.id, Some(data))).await {
+                            warn!(
+                                "Manager (id: {}) failed to roll back the registration of data source (id: {}): {}",
+                                self.manager_id,
+                                id,
+                                rollback_err
+                            );
+                        }
+                        return Err(err);
+                    }
+                };
+
+                let data_point = DataPoint {
+                    value: val.value,
+                    timestamp: val.measured_at.unwrap_or(received_at)
+                };
+                let logged = DataPoint {
+                    value: $data_type(data_point.value.clone()),
+                    timestamp: data_point.timestamp
+                };
+                let mut data_source = DataSource::new(Some(data.to_vec()), self.max_raw_updates, tags);
+                data_source.status.log_data(data_point, self.max_logged_data_size);
+                data_source.retain_update(data, received_at, false);
+                lock.insert(id, RwLock::new(data_source));
+                self.registered.fetch_add(1, Ordering::Relaxed);
+
+                Ok(logged)
             }
 
-            async fn deregister(&self, id: &str) -> Result<()> {
+            async fn can_register(&self, id: &NamespacedId, data: Option<&[u8]>) -> Result<()> {
+                let lock = self.logged_data.read().await;
+                if let Some(data_source) = lock.get(id) {
+                    if data_source.read().await.status.is_registered() {
+                        return Err(
+                            ManagerAndDataError::DataSourceManager(
+                                FlorustServerPluginError::DataSourceAlreadyExists(id.to_string())
+                            )
+                        );
+                    }
+                }
+
+                self.check_capacity()?;
+                self.validate_registration(id, data).await
+            }
+
+            async fn deregister(&self, id: &NamespacedId, force: bool) -> Result<()> {
                 let mut lock = self.logged_data.write().await;
                 let mut status = lock
                     .get(id)
                     .ok_or(
                         ManagerAndDataError::DataSourceManager(
-                            FlorustServerPluginError::DataSourceManagerDoesntExist(id.to_string())
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
                         )
                     )?
                     .write().await;
 
-                if !status.is_registered() {
+                if !status.status.is_registered() {
                     return Err(
                         ManagerAndDataError::DataSourceManager(
                             FlorustServerPluginError::DataSourceAlreadyDeregistered(id.to_string())
@@ -200,21 +1610,17 @@ macro_rules! manager_and_data_impl {
                     );
                 }
 
-                self.manager.deregister(&id).await
-                    .map_err(|err| {
-                        ManagerAndDataError::DataSourceManager(
-                            FlorustServerPluginError::DataSourceManager(err)
-                        )
-                    })?;
+                self.force_if_failed(id, self.call_manager_observed(id, self.plugin().manager.deregister_in_namespace(&id.namespace, &id.id, None)).await, force)?;
 
-                let tmp = std::mem::replace(&mut *status, DataSourceStatus::RegisteredNoData);
-                *status = match tmp {
+                let tmp = std::mem::replace(&mut status.status, DataSourceStatus::RegisteredNoData);
+                status.status = match tmp {
                     DataSourceStatus::Registered(data) => DataSourceStatus::Deregistered(data),
                     DataSourceStatus::RegisteredNoData => DataSourceStatus::RegisteredNoData,
                     DataSourceStatus::Deregistered(_) => unreachable!("DataSourceStatus is Deregistered despite check saying it isn't.")
                 };
+                self.registered.fetch_sub(1, Ordering::Relaxed);
 
-                if let DataSourceStatus::RegisteredNoData = *status {
+                if let DataSourceStatus::RegisteredNoData = status.status {
                     drop(status);
                     lock.remove(id);
                 }
@@ -222,18 +1628,18 @@ macro_rules! manager_and_data_impl {
                 Ok(())
             }
 
-            async fn deregister_with_data(&self, id: &str, data: &[u8]) -> Result<()> {
+            async fn deregister_with_data(&self, id: &NamespacedId, data: &[u8], force: bool) -> Result<()> {
                 let mut lock = self.logged_data.write().await;
                 let mut status = lock
                     .get(id)
                     .ok_or(
                         ManagerAndDataError::DataSourceManager(
-                            FlorustServerPluginError::DataSourceManagerDoesntExist(id.to_string())
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
                         )
                     )?
                     .write().await;
 
-                if !status.is_registered() {
+                if !status.status.is_registered() {
                     return Err(
                         ManagerAndDataError::DataSourceManager(
                             FlorustServerPluginError::DataSourceAlreadyDeregistered(id.to_string())
@@ -241,21 +1647,17 @@ macro_rules! manager_and_data_impl {
                     );
                 }
 
-                self.manager.deregister_with_data(&id, data).await
-                    .map_err(|err| {
-                        ManagerAndDataError::DataSourceManager(
-                            FlorustServerPluginError::DataSourceManager(err)
-                        )
-                    })?;
+                self.force_if_failed(id, self.call_manager_observed(id, self.plugin().manager.deregister_in_namespace(&id.namespace, &id.id, Some(data))).await, force)?;
 
-                let tmp = std::mem::replace(&mut *status, DataSourceStatus::RegisteredNoData);
-                *status = match tmp {
+                let tmp = std::mem::replace(&mut status.status, DataSourceStatus::RegisteredNoData);
+                status.status = match tmp {
                     DataSourceStatus::Registered(data) => DataSourceStatus::Deregistered(data),
                     DataSourceStatus::RegisteredNoData => DataSourceStatus::RegisteredNoData,
                     DataSourceStatus::Deregistered(_) => unreachable!("DataSourceStatus is Deregistered despite check saying it isn't.")
                 };
+                self.registered.fetch_sub(1, Ordering::Relaxed);
 
-                if let DataSourceStatus::RegisteredNoData = *status {
+                if let DataSourceStatus::RegisteredNoData = status.status {
                     drop(status);
                     lock.remove(id);
                 }
@@ -263,7 +1665,112 @@ macro_rules! manager_and_data_impl {
                 Ok(())
             }
 
-            async fn update_data(&self, id: &str , data: &[u8]) -> Result<()> {
+            async fn deregister_all(&self, allowed: &SourceCheck<'_>) -> DeregisterAllReport {
+                let mut lock = self.logged_data.write().await;
+                let mut report = DeregisterAllReport {
+                    deregistered: Vec::new(),
+                    failed: Vec::new()
+                };
+
+                for (id, data_source) in lock.iter() {
+                    if !data_source.read().await.status.is_registered() {
+                        continue;
+                    }
+                    if let Err(error) = allowed(id) {
+                        report.failed.push(DeregistrationFailure {
+                            source: id.clone(),
+                            error
+                        });
+                        continue;
+                    }
+
+                    match self.call_manager_observed(id, self.plugin().manager.deregister_in_namespace(&id.namespace, &id.id, None)).await {
+                        Ok(()) => report.deregistered.push(id.clone()),
+                        Err(error) => report.failed.push(DeregistrationFailure {
+                            source: id.clone(),
+                            error
+                        })
+                    }
+                }
+
+                for id in &report.deregistered {
+                    lock.remove(id);
+                }
+                self.registered.fetch_sub(report.deregistered.len(), Ordering::Relaxed);
+
+                report
+            }
+
+            async fn deregister_idle(&self, timeout: Duration) -> DeregisterAllReport {
+                let mut lock = self.logged_data.write().await;
+                let mut report = DeregisterAllReport {
+                    deregistered: Vec::new(),
+                    failed: Vec::new()
+                };
+
+                for (id, data_source) in lock.iter() {
+                    let mut data_source = data_source.write().await;
+                    if !data_source.status.is_registered() || data_source.last_active.elapsed() < timeout {
+                        continue;
+                    }
+
+                    if let Err(error) = self.call_manager_observed(id, self.plugin().manager.deregister_in_namespace(&id.namespace, &id.id, None)).await {
+                        report.failed.push(DeregistrationFailure {
+                            source: id.clone(),
+                            error
+                        });
+                        continue;
+                    }
+
+                    let tmp = std::mem::replace(&mut data_source.status, DataSourceStatus::RegisteredNoData);
+                    data_source.status = match tmp {
+                        DataSourceStatus::Registered(data) => DataSourceStatus::Deregistered(data),
+                        status => status
+                    };
+                    report.deregistered.push(id.clone());
+                }
+                self.registered.fetch_sub(report.deregistered.len(), Ordering::Relaxed);
+
+                // Data sources that never logged anything have nothing left to keep.
+                let mut empty = Vec::new();
+                for id in &report.deregistered {
+                    if let Some(data_source) = lock.get(id) {
+                        if let DataSourceStatus::RegisteredNoData = data_source.read().await.status {
+                            empty.push(id.clone());
+                        }
+                    }
+                }
+                for id in &empty {
+                    lock.remove(id);
+                }
+
+                report
+            }
+
+            async fn update_data(&self, id: &NamespacedId, data: &[u8], trace_id: Option<&str>, allow_reset: bool) -> Result<DataPoint<DataType>> {
+                self.log_update(id, Update::Buffered { data, trace_id }, allow_reset).await
+            }
+
+            async fn update_data_stream(&self, id: &NamespacedId, reader: &mut (dyn AsyncRead + Send + Unpin), allow_reset: bool) -> Result<DataPoint<DataType>> {
+                let streamed = self.supports(capabilities::STREAM)
+                    && !self.supports(capabilities::VALIDATE_DATA)
+                    && self.transforms.is_empty()
+                    && self.max_raw_updates == 0;
+                if streamed {
+                    return self.log_update(id, Update::Streamed(reader), allow_reset).await;
+                }
+
+                // Managers that don't parse updates incrementally are given the whole update, the same way as
+                // if it was uploaded in one piece. So are managers that validate data, have transforms, or retain
+                // updates, as validating, transforming, and retaining updates needs the whole update.
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data).await
+                    .map_err(|err| ManagerAndDataError::InvalidUpload(err.to_string()))?;
+                self.update_data(id, &data, None, allow_reset).await
+            }
+
+            async fn batch_update_data(&self, id: &NamespacedId, data: &[u8], allow_reset: bool) -> Result<Vec<DataPoint<DataType>>> {
+                let received_at = Utc::now();
                 let lock = self.logged_data.read().await;
 
                 let mut data_source = lock
@@ -276,52 +1783,799 @@ macro_rules! manager_and_data_impl {
                     .write()
                     .await;
 
-                let val = self.manager.update_data(id, data).await.map_err(|e| {
-                    ManagerAndDataError::DataSourceManager(
-                        FlorustServerPluginError::DataSourceManager(e)
-                    )
-                })?;
-
-                match &mut *data_source {
-                    DataSourceStatus::RegisteredNoData => {
-                        let mut logged_data = CircularVec::new(self.max_logged_data_size, $default_val);
-                        logged_data.append(val);
-                        *data_source = DataSourceStatus::Registered(logged_data);
-                    },
-                    DataSourceStatus::Registered(logged_data) => {
-                        logged_data.append(val);
-                    },
-                    DataSourceStatus::Deregistered(_) => return Err(
+                if !data_source.status.is_registered() {
+                    return Err(
                         ManagerAndDataError::DataSourceManager(
                             FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
                         )
-                    ),
+                    );
                 }
 
-                Ok(())
-            }
+                data_source.retain_update(data, received_at, true);
 
-            async fn get_data(&self, id: &str, index: usize) -> Result<DataType> {
-                Ok(
-                    $data_type(
-                        *self.logged_data.read().await
-                            .get(id)
-                            .ok_or(
-                                ManagerAndDataError::DataSourceManager(
-                                    FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
-                                )
-                            )?
-                            .read().await
-                            .data_or_err(|| ManagerAndDataError::NoData)?
-                            .get(index)
-                            .ok_or(ManagerAndDataError::IndexOutOfBounds)?
-                    )
-                )
+                let vals = async {
+                    let data = &*self.transform(data)?;
+                    self.validate_data(id, data).await?;
+                    self.call_manager_observed(id, self.plugin().manager.batch_update_data(&id.id, data)).await
+                }.await;
+                let vals = data_source.record_error(vals)?;
+                // The whole batch is rejected if any of it goes down, so none of it is logged.
+                if self.monotonic && !allow_reset {
+                    check_monotonic(id, data_source.status.latest_value(), &vals, $data_type)?;
+                }
+                data_source.last_active = Instant::now();
+
+                let mut logged = Vec::with_capacity(vals.len());
+                for val in vals {
+                    logged.push(DataPoint {
+                        value: $data_type(val.clone()),
+                        timestamp: received_at
+                    });
+                    data_source.status.log_data(
+                        DataPoint {
+                            value: val,
+                            timestamp: received_at
+                        },
+                        self.max_logged_data_size
+                    );
+                }
+
+                Ok(logged)
             }
-        }
-    };
-}
 
-manager_and_data_impl!(IIntegerManagerAndData, IIntegerDataManager, 0, DataType::IInteger);
-manager_and_data_impl!(UIntegerManagerAndData, UIntegerDataManager, 0, DataType::UInteger);
-manager_and_data_impl!(FloatManagerAndData, FloatDataManager, 0.0, DataType::Float);
+            async fn get_data(&self, id: &NamespacedId, index: usize) -> Result<DataPoint<DataType>> {
+                let lock = self.logged_data.read().await;
+                let data_source = lock
+                    .get(id)
+                    .ok_or(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    )?
+                    .read().await;
+                let data_point = data_source
+                    .status
+                    .data_or_err(|| ManagerAndDataError::NoData)?
+                    .get(index)
+                    .ok_or(ManagerAndDataError::IndexOutOfBounds)?;
+
+                Ok(DataPoint {
+                    value: $data_type(data_point.value.clone()),
+                    timestamp: data_point.timestamp
+                })
+            }
+
+            async fn latest_data(&self, id: &NamespacedId) -> Result<Option<DataPoint<DataType>>> {
+                let lock = self.logged_data.read().await;
+                let data_source = lock
+                    .get(id)
+                    .ok_or(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    )?
+                    .read().await;
+
+                let Ok(logged_data) = data_source.status.data_or_err(|| ManagerAndDataError::NoData) else {
+                    return Ok(None);
+                };
+
+                Ok(
+                    logged_data.latest().map(|data_point| DataPoint {
+                        value: $data_type(data_point.value.clone()),
+                        timestamp: data_point.timestamp
+                    })
+                )
+            }
+
+            async fn history(&self, id: &NamespacedId) -> Result<Vec<DataPoint<DataType>>> {
+                let lock = self.logged_data.read().await;
+                let data_source = lock
+                    .get(id)
+                    .ok_or(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    )?
+                    .read().await;
+
+                let Ok(logged_data) = data_source.status.data_or_err(|| ManagerAndDataError::NoData) else {
+                    return Ok(Vec::new());
+                };
+
+                Ok(
+                    (0..logged_data.len())
+                        .filter_map(|index| logged_data.get(index))
+                        .map(|data_point| DataPoint {
+                            value: $data_type(data_point.value.clone()),
+                            timestamp: data_point.timestamp
+                        })
+                        .collect()
+                )
+            }
+
+            async fn replay(&self, id: &NamespacedId) -> Result<Vec<ReplayedUpdate>> {
+                let lock = self.logged_data.read().await;
+                // The data source is kept locked for the whole replay, so its updates aren't interleaved with the
+                // replayed ones.
+                let data_source = lock
+                    .get(id)
+                    .ok_or(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    )?
+                    .read().await;
+
+                if !data_source.status.is_registered() {
+                    return Err(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    );
+                }
+
+                let plugin = self.plugin();
+                let registration_data = data_source.registration_data.as_deref();
+                let mut replayed = Vec::with_capacity(data_source.raw_updates.len());
+                for update in (0..data_source.raw_updates.len()).filter_map(|index| data_source.raw_updates.get(index)) {
+                    if update.batch {
+                        let vals = async {
+                            let data = &*self.transform(&update.data)?;
+                            self.validate_data(id, data).await?;
+                            self.call_manager_observed(id, plugin.manager.batch_update_data(&id.id, data)).await
+                        }.await;
+
+                        match vals {
+                            Ok(vals) => replayed.extend(vals.into_iter().map(|val| ReplayedUpdate {
+                                received_at: update.received_at,
+                                value: Some(DataPoint {
+                                    value: $data_type(val),
+                                    timestamp: update.received_at
+                                }),
+                                error: None
+                            })),
+                            Err(err) => replayed.push(ReplayedUpdate {
+                                received_at: update.received_at,
+                                value: None,
+                                error: Some(err)
+                            })
+                        }
+                        continue;
+                    }
+
+                    let val = async {
+                        let data = &*self.transform(&update.data)?;
+                        self.validate_data(id, data).await?;
+
+                        if self.supports(capabilities::TRACING) {
+                            self.call_manager_observed(id, plugin.manager.update_data_traced(&id.namespace, &id.id, data, registration_data, None)).await
+                        }
+                        else {
+                            self.call_manager_observed(id, plugin.manager.update_data_in_namespace(&id.namespace, &id.id, data, registration_data)).await
+                        }
+                    }.await;
+
+                    replayed.push(match val {
+                        Ok(val) => ReplayedUpdate {
+                            received_at: update.received_at,
+                            value: Some(DataPoint {
+                                value: $data_type(val.value),
+                                timestamp: val.measured_at.unwrap_or(update.received_at)
+                            }),
+                            error: None
+                        },
+                        Err(err) => ReplayedUpdate {
+                            received_at: update.received_at,
+                            value: None,
+                            error: Some(err)
+                        }
+                    });
+                }
+
+                Ok(replayed)
+            }
+
+            async fn last_error(&self, id: &NamespacedId) -> Result<Option<LastError>> {
+                let lock = self.logged_data.read().await;
+                let data_source = lock
+                    .get(id)
+                    .ok_or(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    )?
+                    .read().await;
+
+                Ok(data_source.last_error.clone())
+            }
+
+            async fn reset(&self, id: &NamespacedId) -> Result<()> {
+                let lock = self.logged_data.read().await;
+                let registered = match lock.get(id) {
+                    Some(data_source) => data_source.read().await.status.is_registered(),
+                    None => false
+                };
+
+                if !registered {
+                    return Err(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                        )
+                    );
+                }
+
+                if !self.supports(capabilities::RESET) {
+                    return Ok(());
+                }
+
+                self.call_manager(id, self.plugin().manager.reset(&id.id)).await
+            }
+
+            async fn is_registered(&self, id: &NamespacedId) -> bool {
+                let lock = self.logged_data.read().await;
+                match lock.get(id) {
+                    Some(data_source) => data_source.read().await.status.is_registered(),
+                    None => false
+                }
+            }
+
+            async fn registered_sources(&self, namespace: &str, tags: &[String]) -> Vec<String> {
+                let lock = self.logged_data.read().await;
+                let mut registered = Vec::new();
+                for (id, data_source) in lock.iter() {
+                    if id.namespace != namespace {
+                        continue;
+                    }
+
+                    let data_source = data_source.read().await;
+                    if data_source.status.is_registered() && tags.iter().all(|tag| data_source.tags.contains(tag)) {
+                        registered.push(id.id.clone());
+                    }
+                }
+
+                registered
+            }
+
+            async fn tags(&self, id: &NamespacedId) -> Result<BTreeSet<String>> {
+                let lock = self.logged_data.read().await;
+                if let Some(data_source) = lock.get(id) {
+                    let data_source = data_source.read().await;
+                    if data_source.status.is_registered() {
+                        return Ok(data_source.tags.clone());
+                    }
+                }
+
+                Err(
+                    ManagerAndDataError::DataSourceManager(
+                        FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                    )
+                )
+            }
+
+            async fn set_tags(&self, id: &NamespacedId, tags: BTreeSet<String>) -> Result<()> {
+                let lock = self.logged_data.read().await;
+                if let Some(data_source) = lock.get(id) {
+                    let mut data_source = data_source.write().await;
+                    if data_source.status.is_registered() {
+                        data_source.tags = tags;
+                        return Ok(());
+                    }
+                }
+
+                Err(
+                    ManagerAndDataError::DataSourceManager(
+                        FlorustServerPluginError::DataSourceDoesntExist(id.to_string())
+                    )
+                )
+            }
+
+            async fn aggregate_sources(&self, namespace: &str) -> Result<Option<DataPoint<DataType>>> {
+                if !self.supports(capabilities::AGGREGATE_SOURCES) {
+                    return Err(
+                        ManagerAndDataError::DataSourceManager(
+                            FlorustServerPluginError::DataSourceManager(
+                                DataSourceManagerError::Unsupported("aggregating data sources".to_string())
+                            )
+                        )
+                    );
+                }
+
+                let lock = self.logged_data.read().await;
+                let mut values = Vec::new();
+                let mut measured_at = None;
+                for (id, data_source) in lock.iter() {
+                    if id.namespace != namespace {
+                        continue;
+                    }
+
+                    let data_source = data_source.read().await;
+                    if let DataSourceStatus::Registered(logged_data) = &data_source.status {
+                        if let Some(data_point) = logged_data.latest() {
+                            values.push((id.id.clone(), data_point.value.clone()));
+                            measured_at = measured_at.max(Some(data_point.timestamp));
+                        }
+                    }
+                }
+                drop(lock);
+
+                let Some(timestamp) = measured_at else {
+                    return Ok(None);
+                };
+
+                let plugin = self.plugin();
+                let call = time::timeout(self.timeout, plugin.manager.aggregate_sources(&values));
+                let result = AssertUnwindSafe(call).catch_unwind().await.map(|result| result.unwrap_or_else(|_| {
+                    warn!("Manager (id: {}) timed out aggregating data sources after {:?}", self.manager_id, self.timeout);
+                    Err(DataSourceManagerError::Timeout)
+                }));
+
+                Ok(Some(DataPoint {
+                    value: $data_type(self.check_panicked(None, result)?),
+                    timestamp
+                }))
+            }
+
+            async fn registered_count(&self) -> usize {
+                self.registered.load(Ordering::Relaxed)
+            }
+
+            async fn average_update_interval(&self) -> Option<Duration> {
+                let lock = self.logged_data.read().await;
+                let mut intervals = Vec::new();
+                for data_source in lock.values() {
+                    let data_source = data_source.read().await;
+                    let DataSourceStatus::Registered(logged_data) = &data_source.status else {
+                        continue;
+                    };
+
+                    // Managers can report when updates were measured, so the oldest and newest data points
+                    // aren't necessarily the first and last ones logged.
+                    let timestamps = (0..logged_data.len()).filter_map(|index| logged_data.get(index)).map(|data_point| data_point.timestamp);
+                    let (Some(oldest), Some(newest)) = (timestamps.clone().min(), timestamps.max()) else {
+                        continue;
+                    };
+                    if logged_data.len() > 1 {
+                        intervals.push((newest - oldest).to_std().unwrap_or_default() / (logged_data.len() - 1) as u32);
+                    }
+                }
+
+                (!intervals.is_empty()).then(|| intervals.iter().sum::<Duration>() / intervals.len() as u32)
+            }
+
+            async fn stale_sources(&self) -> Vec<NamespacedId> {
+                let Some(stale_after) = self.expected_interval().and_then(stale_after) else {
+                    return Vec::new();
+                };
+
+                let lock = self.logged_data.read().await;
+                let mut stale = Vec::new();
+                for (id, data_source) in lock.iter() {
+                    let data_source = data_source.read().await;
+                    if data_source.status.is_registered() && data_source.last_active.elapsed() > stale_after {
+                        stale.push(id.clone());
+                    }
+                }
+
+                stale.sort_unstable();
+                stale
+            }
+
+            async fn reconfigure(&self, config: Option<Table>) -> Result<()> {
+                if !self.supports(capabilities::RECONFIGURE) {
+                    return Ok(());
+                }
+
+                let plugin = self.plugin();
+                let config = config::with_defaults(plugin.default_config.as_ref(), config);
+                let result = AssertUnwindSafe(plugin.manager.reconfigure(config)).catch_unwind().await;
+                self.check_panicked(None, result)
+            }
+
+            async fn shutdown(&self) -> Result<()> {
+                if !self.supports(capabilities::SHUTDOWN) {
+                    return Ok(());
+                }
+
+                let result = AssertUnwindSafe(self.plugin().manager.shutdown()).catch_unwind().await;
+                self.check_panicked(None, result)
+            }
+
+            fn into_plugin(self: Box<Self>) -> Box<dyn Any + Send> {
+                Box::new(self.plugin.into_inner().unwrap_or_else(|err| err.into_inner()))
+            }
+
+            async fn replace_plugin(&self, plugin: Box<dyn Any + Send>) -> Result<ReloadReport> {
+                let plugin = plugin
+                    .downcast::<Arc<Plugin<$data_manager>>>()
+                    .map_err(|_| ManagerAndDataError::ReloadFailed(
+                        format!("reloaded manager ({}) no longer produces values of type {}", self.manager_id, $data_type_name)
+                    ))?;
+                if plugin.manager.manager_id() != self.manager_id {
+                    return Err(ManagerAndDataError::ReloadFailed(
+                        format!("reloaded manager ({}) doesn't have the id {}", plugin.manager.manager_id(), self.manager_id)
+                    ));
+                }
+
+                // Holding the lock keeps updates out until every data source is registered with the new manager.
+                let mut lock = self.logged_data.write().await;
+                for (id, data_source) in lock.iter() {
+                    if !data_source.read().await.status.is_registered() {
+                        continue;
+                    }
+
+                    if let Err(err) = self.call_manager_observed(id, self.plugin().manager.deregister_in_namespace(&id.namespace, &id.id, None)).await {
+                        warn!("Manager (id: {}) failed to deregister data source (id: {}) before being reloaded: {}", self.manager_id, id, err);
+                    }
+                }
+                if let Err(err) = self.shutdown().await {
+                    warn!("Manager (id: {}) failed to shut down before being reloaded: {}", self.manager_id, err);
+                }
+
+                *self.plugin.lock().unwrap_or_else(|err| err.into_inner()) = *plugin;
+                self.healthy.store(true, Ordering::Relaxed);
+                self.consecutive_timeouts.store(0, Ordering::Relaxed);
+
+                let mut report = ReloadReport {
+                    manager_id: self.manager_id.to_string(),
+                    reregistered: Vec::new(),
+                    failed: Vec::new()
+                };
+                for (id, data_source) in lock.iter() {
+                    let mut data_source = data_source.write().await;
+                    if !data_source.status.is_registered() {
+                        continue;
+                    }
+
+                    let registration_data = data_source.registration_data.as_deref();
+                    match self.call_manager_observed(id, self.plugin().manager.register_in_namespace(&id.namespace, id.id.clone(), registration_data)).await {
+                        Ok(()) => report.reregistered.push(id.clone()),
+                        Err(error) => {
+                            let tmp = std::mem::replace(&mut data_source.status, DataSourceStatus::RegisteredNoData);
+                            data_source.status = match tmp {
+                                DataSourceStatus::Registered(data) => DataSourceStatus::Deregistered(data),
+                                status => status
+                            };
+                            report.failed.push(RegistrationFailure {
+                                source: id.clone(),
+                                error
+                            });
+                        }
+                    }
+                }
+                self.registered.fetch_sub(report.failed.len(), Ordering::Relaxed);
+
+                // Data sources that never logged anything have nothing left to keep.
+                let mut empty = Vec::new();
+                for failure in &report.failed {
+                    if let Some(data_source) = lock.get(&failure.source) {
+                        if let DataSourceStatus::RegisteredNoData = data_source.read().await.status {
+                            empty.push(failure.source.clone());
+                        }
+                    }
+                }
+                for id in &empty {
+                    lock.remove(id);
+                }
+
+                Ok(report)
+            }
+        }
+    };
+}
+
+manager_and_data_impl!(IIntegerManagerAndData, IIntegerDataManager, DataType::IInteger, "i64");
+manager_and_data_impl!(UIntegerManagerAndData, UIntegerDataManager, DataType::UInteger, "u64");
+manager_and_data_impl!(FloatManagerAndData, FloatDataManager, DataType::Float, "f64");
+manager_and_data_impl!(Float32ManagerAndData, Float32DataManager, DataType::Float32, "f32");
+manager_and_data_impl!(BooleanManagerAndData, BooleanDataManager, DataType::Boolean, "bool");
+manager_and_data_impl!(StringManagerAndData, StringDataManager, DataType::String, "string");
+manager_and_data_impl!(FloatVecManagerAndData, FloatVecDataManager, DataType::FloatVec, "f64_vec");
+manager_and_data_impl!(I128ManagerAndData, I128DataManager, DataType::I128, "i128");
+manager_and_data_impl!(U128ManagerAndData, U128DataManager, DataType::U128, "u128");
+
+#[cfg(test)]
+mod tests {
+    use florust_common::server::TimestampedData;
+
+    use super::*;
+
+    /// A float manager that parses updates as big endian floats, and parses streamed updates into the length of
+    /// the data their data source registered with, so tests can tell whether an update was streamed. Panics on
+    /// updates reading `panic`.
+    struct TestManager {
+        capabilities: u32,
+        max_sources: Option<usize>
+    }
+
+    #[async_trait]
+    impl DataSourceManager<f64> for TestManager {
+        fn manager_id(&self) -> &'static str {
+            "test"
+        }
+
+        fn capabilities(&self) -> u32 {
+            self.capabilities
+        }
+
+        fn max_sources(&self) -> Option<usize> {
+            self.max_sources
+        }
+
+        fn serialize_value(&self, value: &f64) -> server::Result<serde_json::Value> {
+            Ok(serde_json::Value::String(format!("{} °C", value)))
+        }
+
+        async fn register(&self, _id: String) -> server::Result<()> {
+            Ok(())
+        }
+
+        async fn register_with_data(&self, _id: String, data: &[u8]) -> server::Result<()> {
+            match data {
+                b"malformed" => Err(DataSourceManagerError::RegistrationRejected("malformed calibration".to_string())),
+                _ => Ok(())
+            }
+        }
+
+        async fn deregister(&self, _id: &str) -> server::Result<()> {
+            Ok(())
+        }
+
+        async fn deregister_with_data(&self, _id: &str, _data: &[u8]) -> server::Result<()> {
+            Ok(())
+        }
+
+        async fn validate_data(&self, _id: &str, data: &[u8]) -> server::Result<()> {
+            match data.len() {
+                8 => Ok(()),
+                _ => Err(DataSourceManagerError::InvalidData("expected 8 bytes".to_string()))
+            }
+        }
+
+        async fn update_data(&self, _id: &str, data: &[u8]) -> server::Result<f64> {
+            if data == b"panic" {
+                panic!("sensor driver crashed");
+            }
+            let bytes = data.try_into().map_err(|_| DataSourceManagerError::InvalidData("expected 8 bytes".to_string()))?;
+            Ok(f64::from_be_bytes(bytes))
+        }
+
+        async fn batch_update_data(&self, id: &str, data: &[u8]) -> server::Result<Vec<f64>> {
+            let mut values = Vec::new();
+            for chunk in data.chunks(8) {
+                values.push(self.update_data(id, chunk).await?);
+            }
+            Ok(values)
+        }
+
+        async fn update_data_stream(
+            &self,
+            _namespace: &str,
+            _id: &str,
+            _reader: &mut (dyn AsyncRead + Send + Unpin),
+            registration_data: Option<&[u8]>
+        ) -> server::Result<TimestampedData<f64>> {
+            Ok(TimestampedData {
+                value: registration_data.map_or(0, <[u8]>::len) as f64,
+                measured_at: None
+            })
+        }
+    }
+
+    async fn registered(capabilities: u32, raw_history_size: usize) -> (FloatManagerAndData, NamespacedId) {
+        let manager = FloatManagerAndData::new(Box::new(TestManager { capabilities, max_sources: None }), 10)
+            .with_raw_history_size(raw_history_size);
+        let id = NamespacedId::new("", "kitchen");
+        manager.register_with_data(id.clone(), b"calibration", BTreeSet::new()).await.unwrap();
+        (manager, id)
+    }
+
+    #[rocket::async_test]
+    async fn streamed_updates_are_given_the_registration_data() {
+        let (manager, id) = registered(capabilities::STREAM, 0).await;
+        let data_point = manager.update_data_stream(&id, &mut &21.5f64.to_be_bytes()[..], false).await.unwrap();
+        assert!(matches!(data_point.value, DataType::Float(value) if value == 11.0));
+    }
+
+    #[rocket::async_test]
+    async fn streamed_updates_are_validated() {
+        let (manager, id) = registered(capabilities::STREAM | capabilities::VALIDATE_DATA, 0).await;
+        assert!(manager.update_data_stream(&id, &mut &[0x01, 0x02][..], false).await.is_err());
+
+        let data_point = manager.update_data_stream(&id, &mut &21.5f64.to_be_bytes()[..], false).await.unwrap();
+        assert!(matches!(data_point.value, DataType::Float(value) if value == 21.5));
+    }
+
+    #[rocket::async_test]
+    async fn streamed_updates_are_retained() {
+        let (manager, id) = registered(capabilities::STREAM, 5).await;
+        manager.update_data_stream(&id, &mut &21.5f64.to_be_bytes()[..], false).await.unwrap();
+
+        let replayed = manager.replay(&id).await.unwrap();
+        assert_eq!(replayed.len(), 1);
+        assert!(matches!(replayed[0].value, Some(DataPoint { value: DataType::Float(value), .. }) if value == 21.5));
+    }
+
+    #[rocket::async_test]
+    async fn batches_are_retained_and_replayed_as_batches() {
+        let (manager, id) = registered(0, 5).await;
+        let batch = [1.5f64.to_be_bytes(), 2.5f64.to_be_bytes()].concat();
+        manager.batch_update_data(&id, &batch, false).await.unwrap();
+        manager.update_data(&id, &3.5f64.to_be_bytes(), None, false).await.unwrap();
+
+        let replayed = manager.replay(&id).await.unwrap()
+            .into_iter()
+            .map(|update| match update.value {
+                Some(DataPoint { value: DataType::Float(value), .. }) => value,
+                _ => panic!("update failed to replay")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(replayed, [1.5, 2.5, 3.5]);
+    }
+
+    #[rocket::async_test]
+    async fn rejected_registrations_leave_the_source_unregistered() {
+        let manager = FloatManagerAndData::new(Box::new(TestManager { capabilities: 0, max_sources: None }), 10);
+        let id = NamespacedId::new("", "kitchen");
+
+        assert!(matches!(
+            manager.register_with_data(id.clone(), b"malformed", BTreeSet::new()).await,
+            Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::DataSourceManager(
+                DataSourceManagerError::RegistrationRejected(_)
+            )))
+        ));
+        assert!(matches!(
+            manager.update_data(&id, &21.5f64.to_be_bytes(), None, false).await,
+            Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::DataSourceDoesntExist(_)))
+        ));
+        assert_eq!(manager.registered_count().await, 0);
+
+        manager.register_with_data(id.clone(), b"calibration", BTreeSet::new()).await.unwrap();
+        assert!(manager.update_data(&id, &21.5f64.to_be_bytes(), None, false).await.is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn sources_are_tagged_as_they_register() {
+        let manager = FloatManagerAndData::new(Box::new(TestManager { capabilities: 0, max_sources: None }), 10);
+        let tags = BTreeSet::from(["outdoor".to_string()]);
+        let garden = NamespacedId::new("", "garden");
+        let porch = NamespacedId::new("", "porch");
+
+        manager.register(garden.clone(), tags.clone()).await.unwrap();
+        manager.register_with_initial_value(porch.clone(), &21.5f64.to_be_bytes(), tags.clone()).await.unwrap();
+        assert!(manager.register_with_data(NamespacedId::new("", "shed"), b"malformed", tags.clone()).await.is_err());
+
+        assert_eq!(manager.tags(&garden).await.unwrap(), tags);
+        assert_eq!(manager.tags(&porch).await.unwrap(), tags);
+        let mut outdoor = manager.registered_sources("", &["outdoor".to_string()]).await;
+        outdoor.sort();
+        assert_eq!(outdoor, ["garden", "porch"]);
+    }
+
+    #[rocket::async_test]
+    async fn panicking_managers_fail_the_update() {
+        let managers = [
+            FloatManagerAndData::new(Box::new(TestManager { capabilities: 0, max_sources: None }), 10),
+            FloatManagerAndData::new(Box::new(server::CatchPanics(TestManager { capabilities: 0, max_sources: None })), 10)
+        ];
+
+        for manager in managers {
+            let id = NamespacedId::new("", "kitchen");
+            manager.register(id.clone(), BTreeSet::new()).await.unwrap();
+
+            assert!(matches!(
+                manager.update_data(&id, b"panic", None, false).await,
+                Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::PluginPanicked {
+                    source_id: Some(source_id),
+                    ..
+                })) if source_id == id.to_string()
+            ));
+            assert!(!manager.is_healthy());
+
+            let data_point = manager.update_data(&id, &21.5f64.to_be_bytes(), None, false).await.unwrap();
+            assert!(matches!(data_point.value, DataType::Float(value) if value == 21.5));
+        }
+    }
+
+    #[rocket::async_test]
+    async fn monotonic_sources_reject_regressions_unless_reset() {
+        let manager = FloatManagerAndData::new(Box::new(TestManager { capabilities: 0, max_sources: None }), 10)
+            .with_monotonic(true);
+        let id = NamespacedId::new("", "meter");
+        manager.register(id.clone(), BTreeSet::new()).await.unwrap();
+        let update = |value: f64, allow_reset| {
+            let (manager, id) = (&manager, &id);
+            async move { manager.update_data(id, &value.to_be_bytes(), None, allow_reset).await }
+        };
+
+        for value in [1.0, 2.0, 2.0, 3.0] {
+            update(value, false).await.unwrap();
+        }
+
+        assert!(matches!(update(2.5, false).await, Err(ManagerAndDataError::NonMonotonic { .. })));
+        let batch = [4.0f64.to_be_bytes(), 3.5f64.to_be_bytes()].concat();
+        assert!(matches!(manager.batch_update_data(&id, &batch, false).await, Err(ManagerAndDataError::NonMonotonic { .. })));
+        assert!(update(3.0, false).await.is_ok());
+
+        assert!(matches!(update(f64::NAN, false).await, Err(ManagerAndDataError::NonMonotonic { .. })));
+        assert!(matches!(update(2.5, false).await, Err(ManagerAndDataError::NonMonotonic { .. })));
+
+        update(0.5, true).await.unwrap();
+        assert!(update(0.25, false).await.is_err());
+        assert!(update(1.0, false).await.is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn registrations_past_the_limit_are_rejected() {
+        let manager = FloatManagerAndData::new(Box::new(TestManager { capabilities: 0, max_sources: Some(1) }), 10);
+        let kitchen = NamespacedId::new("", "kitchen");
+        let garage = NamespacedId::new("", "garage");
+
+        manager.register(kitchen.clone(), BTreeSet::new()).await.unwrap();
+        assert!(matches!(
+            manager.register(garage.clone(), BTreeSet::new()).await,
+            Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::DataSourceManager(
+                DataSourceManagerError::AtCapacity(1)
+            )))
+        ));
+
+        manager.deregister(&kitchen, false).await.unwrap();
+        manager.register(garage.clone(), BTreeSet::new()).await.unwrap();
+        assert_eq!(manager.registered_count().await, 1);
+
+        manager.deregister_idle(Duration::ZERO).await;
+        assert_eq!(manager.registered_count().await, 0);
+        manager.register(kitchen, BTreeSet::new()).await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn deregistering_every_source_skips_the_ones_not_allowed() {
+        let manager = FloatManagerAndData::new(Box::new(TestManager { capabilities: 0, max_sources: None }), 10);
+        let kitchen = NamespacedId::new("", "kitchen");
+        let garage = NamespacedId::new("", "garage");
+        manager.register(kitchen.clone(), BTreeSet::new()).await.unwrap();
+        manager.register(garage.clone(), BTreeSet::new()).await.unwrap();
+
+        let report = manager.deregister_all(&|source| match source == &garage {
+            true => Err(ManagerAndDataError::Forbidden(source.to_string())),
+            false => Ok(())
+        }).await;
+
+        assert_eq!(report.deregistered, [NamespacedId::new("", "kitchen")]);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].source == garage && matches!(report.failed[0].error, ManagerAndDataError::Forbidden(_)));
+        assert!(!manager.is_registered(&kitchen).await);
+        assert!(manager.is_registered(&garage).await);
+    }
+
+    #[rocket::async_test]
+    async fn deregistering_an_unknown_source_fails_as_a_missing_source() {
+        let manager = FloatManagerAndData::new(Box::new(TestManager { capabilities: 0, max_sources: None }), 10);
+        let id = NamespacedId::new("", "kitchen");
+
+        assert!(matches!(
+            manager.deregister(&id, false).await,
+            Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::DataSourceDoesntExist(_)))
+        ));
+        assert!(matches!(
+            manager.deregister_with_data(&id, b"data", false).await,
+            Err(ManagerAndDataError::DataSourceManager(FlorustServerPluginError::DataSourceDoesntExist(_)))
+        ));
+    }
+
+    #[rocket::async_test]
+    async fn served_values_are_serialized_by_their_manager() {
+        let manager = FloatManagerAndData::new(Box::new(TestManager { capabilities: capabilities::SERIALIZE_VALUE, max_sources: None }), 10);
+        let served = manager.serve_value(DataType::Float(21.5));
+
+        assert_eq!(serde_json::to_string(&served).unwrap(), r#"{"Float":"21.5 °C"}"#);
+        assert_eq!(serde_json::to_string(&UntaggedServedValue(served)).unwrap(), r#""21.5 °C""#);
+        assert_eq!(serde_json::to_string(&UntaggedServedValue(ServedValue::Logged(DataType::Float(21.5)))).unwrap(), "21.5");
+    }
+
+    #[test]
+    fn intervals_too_long_to_go_stale_dont_overflow() {
+        assert_eq!(stale_after(Duration::from_secs(10)), Some(Duration::from_secs(30)));
+        assert_eq!(stale_after(Duration::MAX), None);
+    }
+}
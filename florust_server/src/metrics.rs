@@ -0,0 +1,328 @@
+use std::{collections::{HashMap, VecDeque}, fmt::Write, sync::{Mutex, RwLock, atomic::{AtomicI64, AtomicU64, Ordering}}, time::{Duration, Instant}};
+
+use rocket::{get, State, http::ContentType};
+
+use crate::{FlorustState, auth::ReadAccess, manager_and_data::NamespacedId};
+
+/// The counters kept for a single manager.
+#[derive(Default)]
+struct ManagerMetrics {
+    /// Number of values logged, keyed by data source. The lock is only written to the first time a data
+    /// source logs a value, after that its counter is incremented while holding a read lock.
+    updates: RwLock<HashMap<NamespacedId, AtomicU64>>,
+    /// Number of values logged by data sources that have since deregistered, whose counters were dropped from
+    /// `updates`.
+    forgotten_updates: AtomicU64,
+    update_errors: AtomicU64,
+    registered_sources: AtomicI64,
+    reaped_sources: AtomicU64,
+    /// Number of values logged during each of the last [`RECENT_WINDOW_SECS`] seconds, keyed by how many
+    /// seconds after the metrics were created they were logged. Oldest second first.
+    recent_updates: Mutex<VecDeque<(u64, u64)>>
+}
+
+/// How many seconds back [`UpdateCounts::recent_updates`] counts values for.
+const RECENT_WINDOW_SECS: u64 = 60;
+
+/// How many values a manager has logged, and how many of its updates failed, since the server started.
+pub struct UpdateCounts {
+    pub updates: u64,
+    pub update_errors: u64,
+    /// Values logged within the last minute.
+    pub recent_updates: u64
+}
+
+/// The upper bounds, in seconds, of the buckets request durations are counted in.
+const DURATION_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// How long the requests handled by a single route with the same outcome took.
+#[derive(Default)]
+struct DurationHistogram {
+    /// Number of requests that fell in each bucket, but not in any faster one. Requests slower than the last
+    /// bucket are only counted in `count`.
+    buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    count: AtomicU64,
+    /// The total time the requests took, in nanoseconds.
+    sum_nanos: AtomicU64
+}
+
+/// The labels request durations are recorded under: the request's method, the route that handled it, and
+/// its outcome.
+type RequestLabels = (&'static str, String, &'static str);
+
+/// Counters describing what every manager has ingested, exported in the Prometheus exposition format.
+pub struct Metrics {
+    managers: HashMap<&'static str, ManagerMetrics>,
+    /// Durations of handled requests. The lock is only written to the first time a route has a request with a
+    /// given outcome, like the durations of updates.
+    request_durations: RwLock<HashMap<RequestLabels, DurationHistogram>>,
+    created: Instant
+}
+
+impl Metrics {
+    pub fn new(manager_ids: impl IntoIterator<Item = &'static str>) -> Metrics {
+        Metrics {
+            managers: manager_ids
+                .into_iter()
+                .map(|manager_id| (manager_id, ManagerMetrics::default()))
+                .collect(),
+            request_durations: RwLock::new(HashMap::new()),
+            created: Instant::now()
+        }
+    }
+
+    /// Drops the seconds that fell out of the recent window from `recent`, given that it's now `now` seconds
+    /// after the metrics were created.
+    fn trim_recent(recent: &mut VecDeque<(u64, u64)>, now: u64) {
+        while recent.front().is_some_and(|(second, _)| second + RECENT_WINDOW_SECS <= now) {
+            recent.pop_front();
+        }
+    }
+
+    /// Records that the data source `source` logged `count` values.
+    pub fn updates_logged(&self, manager_id: &str, source: &NamespacedId, count: u64) {
+        let Some(manager) = self.managers.get(manager_id) else {
+            return;
+        };
+
+        let now = self.created.elapsed().as_secs();
+        let mut recent = manager.recent_updates.lock().unwrap_or_else(|err| err.into_inner());
+        Self::trim_recent(&mut recent, now);
+        match recent.back_mut() {
+            Some((second, logged)) if *second == now => *logged += count,
+            _ => recent.push_back((now, count))
+        }
+        drop(recent);
+
+        let updates = manager.updates.read().unwrap_or_else(|err| err.into_inner());
+        if let Some(counter) = updates.get(source) {
+            counter.fetch_add(count, Ordering::Relaxed);
+            return;
+        }
+        drop(updates);
+
+        manager.updates
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .entry(source.clone())
+            .or_default()
+            .fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Drops the counter of the data source `source`, after it deregistered, so the counters of data sources
+    /// that come and go don't pile up. The values it logged still count towards its manager's totals.
+    pub fn forget(&self, manager_id: &str, source: &NamespacedId) {
+        let Some(manager) = self.managers.get(manager_id) else {
+            return;
+        };
+
+        let counter = manager.updates
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(source);
+        if let Some(counter) = counter {
+            manager.forgotten_updates.fetch_add(counter.into_inner(), Ordering::Relaxed);
+        }
+    }
+
+    /// Records that an update sent to the manager with the id `manager_id` was rejected.
+    pub fn update_failed(&self, manager_id: &str) {
+        if let Some(manager) = self.managers.get(manager_id) {
+            manager.update_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Records that `count` data sources were registered (or deregistered, if negative) with the manager with
+    /// the id `manager_id`.
+    pub fn sources_registered(&self, manager_id: &str, count: i64) {
+        if let Some(manager) = self.managers.get(manager_id) {
+            manager.registered_sources.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Records that `count` data sources of the manager with the id `manager_id` were deregistered for going
+    /// idle.
+    pub fn sources_reaped(&self, manager_id: &str, count: u64) {
+        if let Some(manager) = self.managers.get(manager_id) {
+            manager.reaped_sources.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Records that a request made with `method` and handled by the route `route` took `duration`, ending with
+    /// `outcome`.
+    pub fn request_handled(&self, method: &'static str, route: &str, outcome: &'static str, duration: Duration) {
+        let record = |histogram: &DurationHistogram| {
+            let seconds = duration.as_secs_f64();
+            if let Some(bucket) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+                histogram.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+            }
+            histogram.count.fetch_add(1, Ordering::Relaxed);
+            histogram.sum_nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+        };
+
+        let labels = (method, route.to_string(), outcome);
+        let durations = self.request_durations.read().unwrap_or_else(|err| err.into_inner());
+        if let Some(histogram) = durations.get(&labels) {
+            record(histogram);
+            return;
+        }
+        drop(durations);
+
+        record(
+            self.request_durations
+                .write()
+                .unwrap_or_else(|err| err.into_inner())
+                .entry(labels)
+                .or_default()
+        );
+    }
+
+    /// Returns how many values the manager with the id `manager_id` has logged, across all of its data
+    /// sources, and how many of its updates failed.
+    pub fn update_counts(&self, manager_id: &str) -> Option<UpdateCounts> {
+        let manager = self.managers.get(manager_id)?;
+
+        let mut recent = manager.recent_updates.lock().unwrap_or_else(|err| err.into_inner());
+        Self::trim_recent(&mut recent, self.created.elapsed().as_secs());
+        let recent_updates = recent.iter().map(|(_, logged)| logged).sum();
+        drop(recent);
+
+        let updates = manager.updates.read().unwrap_or_else(|err| err.into_inner());
+        Some(UpdateCounts {
+            updates: manager.forgotten_updates.load(Ordering::Relaxed)
+                + updates.values().map(|counter| counter.load(Ordering::Relaxed)).sum::<u64>(),
+            update_errors: manager.update_errors.load(Ordering::Relaxed),
+            recent_updates
+        })
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut managers = self.managers.iter().collect::<Vec<_>>();
+        managers.sort_by_key(|(manager_id, _)| *manager_id);
+
+        let mut out = String::new();
+
+        out.push_str("# HELP florust_updates_total Number of values logged by a data source.\n");
+        out.push_str("# TYPE florust_updates_total counter\n");
+        for (manager_id, manager) in &managers {
+            let updates = manager.updates.read().unwrap_or_else(|err| err.into_inner());
+            let mut updates = updates.iter().collect::<Vec<_>>();
+            updates.sort_by_key(|(source, _)| *source);
+
+            for (source, counter) in updates {
+                let _ = writeln!(
+                    out,
+                    "florust_updates_total{{manager_id=\"{}\",namespace=\"{}\",source_id=\"{}\"}} {}",
+                    escape_label(manager_id),
+                    escape_label(&source.namespace),
+                    escape_label(&source.id),
+                    counter.load(Ordering::Relaxed)
+                );
+            }
+        }
+
+        out.push_str("# HELP florust_update_errors_total Number of updates a manager rejected.\n");
+        out.push_str("# TYPE florust_update_errors_total counter\n");
+        for (manager_id, manager) in &managers {
+            let _ = writeln!(
+                out,
+                "florust_update_errors_total{{manager_id=\"{}\"}} {}",
+                escape_label(manager_id),
+                manager.update_errors.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str("# HELP florust_registered_sources Number of data sources currently registered with a manager.\n");
+        out.push_str("# TYPE florust_registered_sources gauge\n");
+        for (manager_id, manager) in &managers {
+            let _ = writeln!(
+                out,
+                "florust_registered_sources{{manager_id=\"{}\"}} {}",
+                escape_label(manager_id),
+                manager.registered_sources.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str("# HELP florust_reaped_sources_total Number of data sources deregistered for going idle.\n");
+        out.push_str("# TYPE florust_reaped_sources_total counter\n");
+        for (manager_id, manager) in &managers {
+            let _ = writeln!(
+                out,
+                "florust_reaped_sources_total{{manager_id=\"{}\"}} {}",
+                escape_label(manager_id),
+                manager.reaped_sources.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str("# HELP florust_request_duration_seconds How long requests took to handle, including calls into managers.\n");
+        out.push_str("# TYPE florust_request_duration_seconds histogram\n");
+        let durations = self.request_durations.read().unwrap_or_else(|err| err.into_inner());
+        let mut durations = durations.iter().collect::<Vec<_>>();
+        durations.sort_by_key(|(labels, _)| *labels);
+        for ((method, route, outcome), histogram) in durations {
+            let labels = format!(
+                "method=\"{}\",route=\"{}\",outcome=\"{}\"",
+                escape_label(method),
+                escape_label(route),
+                escape_label(outcome)
+            );
+
+            // Buckets are cumulative, each counting every request at least as fast as its bound.
+            let mut cumulative = 0;
+            for (bound, bucket) in DURATION_BUCKETS.iter().zip(&histogram.buckets) {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let _ = writeln!(out, "florust_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, cumulative);
+            }
+            let count = histogram.count.load(Ordering::Relaxed);
+            let _ = writeln!(out, "florust_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, count);
+            let _ = writeln!(
+                out,
+                "florust_request_duration_seconds_sum{{{}}} {}",
+                labels,
+                Duration::from_nanos(histogram.sum_nanos.load(Ordering::Relaxed)).as_secs_f64()
+            );
+            let _ = writeln!(out, "florust_request_duration_seconds_count{{{}}} {}", labels, count);
+        }
+
+        out
+    }
+}
+
+/// Escapes `value` so it can be used as a label value.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[get("/metrics")]
+pub fn metrics(_access: ReadAccess, state: &State<FlorustState>) -> (ContentType, String) {
+    let content_type = ContentType::new("text", "plain").with_params([("version", "0.0.4"), ("charset", "utf-8")]);
+    (content_type, state.metrics.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgotten_sources_are_dropped_but_still_counted() {
+        let metrics = Metrics::new(["Tp"]);
+        let kitchen = NamespacedId::new("", "kitchen");
+        let garage = NamespacedId::new("", "garage");
+        metrics.updates_logged("Tp", &kitchen, 3);
+        metrics.updates_logged("Tp", &garage, 2);
+
+        metrics.forget("Tp", &kitchen);
+        let rendered = metrics.render();
+        assert!(!rendered.contains("source_id=\"kitchen\""));
+        assert!(rendered.contains("florust_updates_total{manager_id=\"Tp\",namespace=\"\",source_id=\"garage\"} 2"));
+        assert_eq!(metrics.update_counts("Tp").unwrap().updates, 5);
+
+        metrics.updates_logged("Tp", &kitchen, 1);
+        assert!(metrics.render().contains("florust_updates_total{manager_id=\"Tp\",namespace=\"\",source_id=\"kitchen\"} 1"));
+        assert_eq!(metrics.update_counts("Tp").unwrap().updates, 6);
+    }
+}
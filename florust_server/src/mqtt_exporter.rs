@@ -0,0 +1,107 @@
+use std::{sync::Mutex, time::Duration};
+
+use chrono::{DateTime, Utc};
+use rocket::{async_trait, serde::Serialize, tokio::{self, time}};
+use rumqttc::{AsyncClient, EventLoop, MqttOptions, QoS};
+use tracing::warn;
+
+use crate::{
+    config::MqttConfig,
+    exporter::{ExportError, Exporter, Result},
+    manager_and_data::{DataType, NamespacedId, UntaggedDataType}
+};
+
+/// How many publishes may be waiting to be sent to the broker. Values exported while the buffer is full, like
+/// while the broker is unreachable, are dropped instead of holding up updates.
+const PUBLISH_BUFFER_SIZE: usize = 1024;
+
+/// How long to wait before reconnecting to the broker after losing the connection to it.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// The payload of every message published to the broker.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct ExportedValue<'a> {
+    value: &'a UntaggedDataType,
+    timestamp: DateTime<Utc>
+}
+
+/// An [`Exporter`] that publishes every value to an MQTT broker, as JSON on a topic made from the ids of the
+/// manager and data source that produced it.
+pub struct MqttExporter {
+    client: AsyncClient,
+    /// The connection to the broker, until it's handed off to the task that keeps it up.
+    event_loop: Mutex<Option<EventLoop>>,
+    host: String,
+    topic: String,
+    qos: QoS,
+    retain: bool
+}
+
+impl MqttExporter {
+    /// Creates an exporter for the broker described by `config`. The exporter only connects to the broker once
+    /// it's started, after which the connection is kept up in the background, reconnecting whenever it's lost.
+    pub fn connect(config: &MqttConfig) -> Result<MqttExporter> {
+        let host = config.host().ok_or_else(|| ExportError::Connect("no broker host is configured".to_string()))?;
+        let qos = match config.qos() {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            qos => return Err(ExportError::Connect(format!("invalid QoS ({}), must be 0, 1, or 2", qos)))
+        };
+
+        let mut options = MqttOptions::new(config.client_id(), host, config.port());
+        if let Some((username, password)) = config.credentials() {
+            options.set_credentials(username, password);
+        }
+
+        let (client, event_loop) = AsyncClient::new(options, PUBLISH_BUFFER_SIZE);
+        Ok(MqttExporter {
+            client,
+            event_loop: Mutex::new(Some(event_loop)),
+            host: host.to_string(),
+            topic: config.topic().to_string(),
+            qos,
+            retain: config.retain()
+        })
+    }
+
+    /// Fills in the topic template with the ids of the manager and data source that produced a value.
+    fn topic(&self, manager_id: &str, source: &NamespacedId) -> String {
+        self.topic
+            .replace("{manager_id}", manager_id)
+            .replace("{namespace}", &source.namespace)
+            .replace("{source_id}", &source.to_string())
+    }
+}
+
+/// Polls the connection to the broker, which is what actually sends published values, until the server exits.
+async fn drive_connection(mut event_loop: EventLoop, host: String) {
+    loop {
+        if let Err(err) = event_loop.poll().await {
+            warn!("Lost connection to MQTT broker ({}), reconnecting in {:?}: {}", host, RECONNECT_DELAY, err);
+            time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Exporter for MqttExporter {
+    fn start(&self) {
+        let event_loop = self.event_loop.lock().unwrap_or_else(|err| err.into_inner()).take();
+        if let Some(event_loop) = event_loop {
+            tokio::spawn(drive_connection(event_loop, self.host.clone()));
+        }
+    }
+
+    async fn export(&self, manager_id: &str, source: &NamespacedId, value: &DataType, ts: DateTime<Utc>) -> Result<()> {
+        let payload = serde_json::to_vec(&ExportedValue {
+            value: &UntaggedDataType(value.clone()),
+            timestamp: ts
+        }).map_err(|err| ExportError::Export(err.to_string()))?;
+
+        self.client
+            .try_publish(self.topic(manager_id, source), self.qos, self.retain, payload)
+            .map_err(|err| ExportError::Export(err.to_string()))
+    }
+}
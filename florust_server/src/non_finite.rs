@@ -0,0 +1,215 @@
+use std::{cell::Cell, result, sync::OnceLock};
+
+use rocket::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// How floats that aren't finite, like a `NaN` logged for a disconnected sensor, are encoded in JSON, which
+/// can't represent them. Formats that can, like CBOR, always encode them as they are.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+pub enum NonFiniteFloats {
+    /// As `null`, losing which of `NaN`, `+Inf` or `-Inf` the float was.
+    #[default]
+    Null,
+    /// As an object naming the float, like `{"float": "nan"}`, `{"float": "inf"}` or `{"float": "-inf"}`.
+    Tagged
+}
+
+/// The encoding configured for the server, set once while it starts.
+static ENCODING: OnceLock<NonFiniteFloats> = OnceLock::new();
+
+/// Sets how non-finite floats are encoded in JSON. Only the first encoding set is used.
+pub fn set_encoding(encoding: NonFiniteFloats) {
+    let _ = ENCODING.set(encoding);
+}
+
+thread_local! {
+    /// The encoding used in place of the configured one by [`with_encoding`], on the thread it's running on.
+    static OVERRIDE: Cell<Option<NonFiniteFloats>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with non-finite floats encoded as `encoding` rather than the configured encoding, for JSON that
+/// must keep which float it was, like values written to storage.
+#[cfg_attr(not(feature = "sqlite_storage"), allow(dead_code))]
+pub fn with_encoding<R>(encoding: NonFiniteFloats, f: impl FnOnce() -> R) -> R {
+    let previous = OVERRIDE.replace(Some(encoding));
+    let result = f();
+    OVERRIDE.set(previous);
+    result
+}
+
+fn encoding() -> NonFiniteFloats {
+    OVERRIDE.get()
+        .or_else(|| ENCODING.get().copied())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+enum NonFinite {
+    #[serde(rename = "nan")]
+    Nan,
+    #[serde(rename = "inf")]
+    Inf,
+    #[serde(rename = "-inf")]
+    NegInf
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Tagged {
+    float: NonFinite
+}
+
+/// Every way a float may be encoded, as any encoding is accepted when reading one back.
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+enum Encoded {
+    Number(f64),
+    Tagged(Tagged),
+    Null(())
+}
+
+/// A float that's encoded following the configured [`NonFiniteFloats`] if it isn't finite.
+pub struct Float(pub f64);
+
+impl Serialize for Float {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        let value = self.0;
+        if value.is_finite() || !serializer.is_human_readable() {
+            return value.serialize(serializer);
+        }
+
+        match encoding() {
+            NonFiniteFloats::Null => serializer.serialize_none(),
+            NonFiniteFloats::Tagged => Tagged {
+                float: if value.is_nan() {
+                    NonFinite::Nan
+                }
+                else if value.is_sign_positive() {
+                    NonFinite::Inf
+                }
+                else {
+                    NonFinite::NegInf
+                }
+            }.serialize(serializer)
+        }
+    }
+}
+
+/// Reads back floats in any encoding, `null` being read as `NaN`.
+impl<'de> Deserialize<'de> for Float {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+        Ok(Float(match Encoded::deserialize(deserializer)? {
+            Encoded::Number(value) => value,
+            Encoded::Tagged(Tagged { float: NonFinite::Nan }) | Encoded::Null(()) => f64::NAN,
+            Encoded::Tagged(Tagged { float: NonFinite::Inf }) => f64::INFINITY,
+            Encoded::Tagged(Tagged { float: NonFinite::NegInf }) => f64::NEG_INFINITY
+        }))
+    }
+}
+
+/// For `#[serde(with)]` on `f64` fields.
+pub mod float {
+    use std::result;
+
+    use rocket::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Float;
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> result::Result<S::Ok, S::Error> {
+        Float(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> result::Result<f64, D::Error> {
+        Ok(Float::deserialize(deserializer)?.0)
+    }
+}
+
+/// For `#[serde(with)]` on `f32` fields.
+pub mod float32 {
+    use std::result;
+
+    use rocket::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Float;
+
+    pub fn serialize<S: Serializer>(value: &f32, serializer: S) -> result::Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return value.serialize(serializer);
+        }
+
+        Float(f64::from(*value)).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> result::Result<f32, D::Error> {
+        Ok(Float::deserialize(deserializer)?.0 as f32)
+    }
+}
+
+/// For `#[serde(with)]` on `Vec<f64>` fields.
+pub mod float_vec {
+    use std::result;
+
+    use rocket::serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Float;
+
+    pub fn serialize<S: Serializer>(values: &[f64], serializer: S) -> result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|value| Float(*value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> result::Result<Vec<f64>, D::Error> {
+        Ok(Vec::<Float>::deserialize(deserializer)?.into_iter().map(|value| value.0).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager_and_data::DataType;
+
+    use super::*;
+
+    /// Encodes `value` as JSON with non-finite floats encoded as `encoding`, and reads it back.
+    fn round_trip(encoding: NonFiniteFloats, value: DataType) -> (String, DataType) {
+        let json = with_encoding(encoding, || serde_json::to_string(&value).unwrap());
+        let value = serde_json::from_str(&json).unwrap();
+        (json, value)
+    }
+
+    #[test]
+    fn tagged_floats_round_trip() {
+        let (json, value) = round_trip(NonFiniteFloats::Tagged, DataType::Float(f64::NAN));
+        assert_eq!(json, r#"{"Float":{"float":"nan"}}"#);
+        assert!(matches!(value, DataType::Float(value) if value.is_nan()));
+
+        let (json, value) = round_trip(NonFiniteFloats::Tagged, DataType::Float(f64::INFINITY));
+        assert_eq!(json, r#"{"Float":{"float":"inf"}}"#);
+        assert!(matches!(value, DataType::Float(f64::INFINITY)));
+
+        let (json, value) = round_trip(NonFiniteFloats::Tagged, DataType::Float32(f32::NEG_INFINITY));
+        assert_eq!(json, r#"{"Float32":{"float":"-inf"}}"#);
+        assert!(matches!(value, DataType::Float32(f32::NEG_INFINITY)));
+
+        let (_, value) = round_trip(NonFiniteFloats::Tagged, DataType::FloatVec(vec![1.5, f64::NEG_INFINITY, f64::INFINITY]));
+        assert!(matches!(value, DataType::FloatVec(values) if values == [1.5, f64::NEG_INFINITY, f64::INFINITY]));
+    }
+
+    #[test]
+    fn null_floats_are_read_back_as_nan() {
+        for float in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let (json, value) = round_trip(NonFiniteFloats::Null, DataType::Float(float));
+            assert_eq!(json, r#"{"Float":null}"#);
+            assert!(matches!(value, DataType::Float(value) if value.is_nan()));
+        }
+    }
+
+    #[test]
+    fn cbor_keeps_non_finite_floats() {
+        for float in [f64::INFINITY, f64::NEG_INFINITY] {
+            let mut cbor = Vec::new();
+            ciborium::into_writer(&DataType::Float(float), &mut cbor).unwrap();
+            let value: DataType = ciborium::from_reader(cbor.as_slice()).unwrap();
+            assert!(matches!(value, DataType::Float(value) if value == float));
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::num::NonZeroUsize;
+
+use chrono::{DateTime, Utc};
+use rocket::{FromForm, form::{self, FromFormField, ValueField}, serde::Serialize};
+
+use crate::manager_and_data::{DataPoint, DataType};
+
+/// The query of a request for a page of a data source's history, like `?limit=100&before=<timestamp>`.
+#[derive(FromForm)]
+pub struct PageQuery {
+    /// The most data points the page may hold.
+    pub limit: Param<NonZeroUsize>,
+    /// Only data points measured before this are included, used to fetch the page before one that was already
+    /// fetched.
+    pub before: Param<Cursor>
+}
+
+impl PageQuery {
+    /// Whether the request asked for a page, rather than for the whole history.
+    pub fn is_paged(&self) -> bool {
+        self.limit.0.is_some() || self.before.0.is_some()
+    }
+}
+
+/// A query parameter that may be left out. Unlike with `Option`, which treats values that can't be parsed as
+/// if they were left out, the request fails if it's given an invalid value, so a mangled cursor can't send a
+/// client back to the newest page.
+pub struct Param<T>(pub Option<T>);
+
+impl<'v, T: FromFormField<'v>> FromFormField<'v> for Param<T> {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        T::from_value(field).map(|value| Param(Some(value)))
+    }
+
+    fn default() -> Option<Self> {
+        Some(Param(None))
+    }
+}
+
+/// A timestamp in RFC 3339 format marking where a page ends, as returned in [`HistoryPage::next`].
+pub struct Cursor(pub DateTime<Utc>);
+
+impl<'v> FromFormField<'v> for Cursor {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        DateTime::parse_from_rfc3339(field.value)
+            .map(|timestamp| Cursor(timestamp.with_timezone(&Utc)))
+            .map_err(|err| form::Error::validation(err.to_string()).into())
+    }
+}
+
+/// A page of a data source's history, oldest data point first.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct HistoryPage<T = DataType> {
+    pub data: Vec<DataPoint<T>>,
+    /// The cursor to pass as `before` to fetch the page before this one, or `None` if there's no older data.
+    pub next: Option<DateTime<Utc>>
+}
+
+/// Picks the page `query` asks for out of `data_points`, the newest data points measured before its `before`,
+/// up to its `limit`.
+pub fn paginate(mut data_points: Vec<DataPoint<DataType>>, query: &PageQuery) -> HistoryPage {
+    // Managers can report when updates were measured, so data points aren't necessarily logged in the order
+    // they were measured in. Pages go by when they were measured, which is what cursors are made of.
+    data_points.sort_by_key(|data_point| data_point.timestamp);
+    if let Some(Cursor(before)) = query.before.0 {
+        data_points.retain(|data_point| data_point.timestamp < before);
+    }
+
+    let Some(limit) = query.limit.0 else {
+        return HistoryPage {
+            data: data_points,
+            next: None
+        };
+    };
+
+    // Data points measured at the same time as the oldest one on the page are kept on it, even if that goes
+    // over the limit, as the next page only holds data points measured before it.
+    let mut start = data_points.len().saturating_sub(limit.get());
+    while start > 0 && data_points[start - 1].timestamp == data_points[start].timestamp {
+        start -= 1;
+    }
+
+    HistoryPage {
+        next: (start > 0).then(|| data_points[start].timestamp),
+        data: data_points.split_off(start)
+    }
+}
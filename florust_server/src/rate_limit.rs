@@ -0,0 +1,103 @@
+use std::{collections::HashMap, sync::Mutex, time::{Duration, Instant}};
+
+use crate::manager_and_data::NamespacedId;
+
+/// A token bucket, holding up to a second's worth of updates.
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant
+}
+
+/// Limits how many updates each data source can send per second, so a misbehaving data source can't starve
+/// the others. Every data source gets its own token bucket.
+pub struct RateLimiter {
+    /// The limit of every manager that has one, in updates per second.
+    limits: HashMap<&'static str, f64>,
+    buckets: Mutex<HashMap<(&'static str, NamespacedId), Bucket>>
+}
+
+impl RateLimiter {
+    pub fn new(limits: HashMap<&'static str, f64>) -> RateLimiter {
+        RateLimiter {
+            limits,
+            buckets: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Takes a token from the bucket of the data source `source`, failing with how long it takes for the next
+    /// token to be added if the bucket is empty. Updates to managers without a limit are always allowed.
+    pub fn try_acquire(&self, manager_id: &str, source: &NamespacedId) -> Result<(), Duration> {
+        let Some((manager_id, limit)) = self.limits.get_key_value(manager_id) else {
+            return Ok(());
+        };
+
+        let capacity = limit.max(1.0);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let bucket = buckets
+            .entry((*manager_id, source.clone()))
+            .or_insert(Bucket { tokens: capacity, last_refill: now });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limit).min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens < 1.0 {
+            return Err(Duration::from_secs_f64((1.0 - bucket.tokens) / limit));
+        }
+
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// Forgets the bucket of the data source `source`, once it has deregistered.
+    pub fn forget(&self, manager_id: &str, source: &NamespacedId) {
+        let Some((manager_id, _)) = self.limits.get_key_value(manager_id) else {
+            return;
+        };
+
+        self.buckets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(&(*manager_id, source.clone()));
+    }
+}
+
+/// Converts `wait` into the whole number of seconds sent in a `Retry-After` header, rounding up so clients
+/// never retry too early.
+pub fn retry_after_secs(wait: Duration) -> u64 {
+    wait.as_secs_f64().ceil().max(1.0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_updates_past_the_limit_with_a_retry_after() {
+        let limiter = RateLimiter::new(HashMap::from([("manager", 1.0)]));
+        let source = NamespacedId::new("", "kitchen");
+
+        assert!(limiter.try_acquire("manager", &source).is_ok());
+        let wait = limiter.try_acquire("manager", &source).unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_secs(1));
+        assert_eq!(retry_after_secs(wait), 1);
+
+        // Every data source has its own bucket, and managers without a limit are never limited.
+        assert!(limiter.try_acquire("manager", &NamespacedId::new("", "garage")).is_ok());
+        for _ in 0..10 {
+            assert!(limiter.try_acquire("other", &source).is_ok());
+        }
+    }
+
+    #[test]
+    fn forgotten_sources_start_with_a_full_bucket() {
+        let limiter = RateLimiter::new(HashMap::from([("manager", 1.0)]));
+        let source = NamespacedId::new("", "kitchen");
+
+        assert!(limiter.try_acquire("manager", &source).is_ok());
+        assert!(limiter.try_acquire("manager", &source).is_err());
+        limiter.forget("manager", &source);
+        assert!(limiter.try_acquire("manager", &source).is_ok());
+    }
+}
@@ -0,0 +1,54 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use tracing::{info, warn};
+use rocket::tokio::time::interval;
+
+use crate::{BoxedManagerAndData, metrics::Metrics, rate_limit::RateLimiter};
+
+/// How often data sources are checked for having gone idle.
+const REAP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Periodically deregisters the data sources that haven't registered or logged an update within the idle
+/// timeout of their manager, like sensors that crashed without deregistering. Managers without an idle timeout
+/// are left alone.
+pub async fn reap_idle_sources(
+    managers_and_data: Arc<HashMap<&'static str, BoxedManagerAndData>>,
+    idle_timeouts: HashMap<&'static str, Duration>,
+    metrics: Arc<Metrics>,
+    rate_limiter: Arc<RateLimiter>
+) {
+    if idle_timeouts.is_empty() {
+        return;
+    }
+
+    let mut interval = interval(REAP_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        for (manager_id, timeout) in &idle_timeouts {
+            let Some(manager) = managers_and_data.get(manager_id) else {
+                continue;
+            };
+
+            let report = manager.deregister_idle(*timeout).await;
+            for data_source_id in &report.deregistered {
+                info!("Reaped data source (manager id: {}, data source id: {}) after it went idle", manager_id, data_source_id);
+                // The signing secret is kept, so a data source that comes back and is registered again by its
+                // first update still has to sign it, rather than anyone being able to update it unsigned.
+                rate_limiter.forget(manager_id, data_source_id);
+                metrics.forget(manager_id, data_source_id);
+            }
+            for failure in &report.failed {
+                warn!(
+                    "Manager (id: {}) failed to deregister idle data source (id: {}): {}",
+                    manager_id,
+                    failure.source,
+                    failure.error
+                );
+            }
+
+            metrics.sources_registered(manager_id, -(report.deregistered.len() as i64));
+            metrics.sources_reaped(manager_id, report.deregistered.len() as u64);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::{sync::Arc, time::Instant};
+
+use rocket::{Data, Request, Response, async_trait, fairing::{Fairing, Info, Kind}, http::StatusClass};
+
+use crate::metrics::Metrics;
+
+/// When the server started handling a request, cached on the request by [`RequestTimer`].
+struct RequestStart(Instant);
+
+/// A fairing that records how long every request took into [`Metrics`], from when it was received until its
+/// response was ready, so it includes the handler's calls into managers. Requests that no route matched are
+/// recorded under the route `unmatched`.
+pub struct RequestTimer {
+    metrics: Arc<Metrics>
+}
+
+impl RequestTimer {
+    pub fn new(metrics: Arc<Metrics>) -> RequestTimer {
+        RequestTimer { metrics }
+    }
+}
+
+/// The outcome a request is recorded with, going by the class of the status it was responded to with.
+fn outcome(class: StatusClass) -> &'static str {
+    match class {
+        StatusClass::Informational | StatusClass::Success | StatusClass::Redirection => "success",
+        StatusClass::ClientError => "client_error",
+        StatusClass::ServerError | StatusClass::Unknown => "server_error"
+    }
+}
+
+#[async_trait]
+impl Fairing for RequestTimer {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request timer",
+            kind: Kind::Request | Kind::Response
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestStart(start) = request.local_cache(|| RequestStart(Instant::now()));
+        let route = request.route().map_or("unmatched", |route| route.uri.path());
+
+        self.metrics.request_handled(
+            request.method().as_str(),
+            route,
+            outcome(response.status().class()),
+            start.elapsed()
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use jsonschema::Validator;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::manager_and_data::{ManagerAndDataError, Result};
+
+/// The JSON Schema a manager's registration data must match, compiled once when the manager is loaded.
+pub struct RegistrationSchema(Validator);
+
+impl RegistrationSchema {
+    /// Compiles `schema`, the registration schema of the manager with the id `manager_id`. Returns `None` if
+    /// `schema` isn't a valid JSON Schema, in which case registrations aren't validated against it.
+    pub fn compile(manager_id: &str, schema: &str) -> Option<RegistrationSchema> {
+        let schema = match serde_json::from_str(schema) {
+            Ok(schema) => schema,
+            Err(err) => {
+                warn!("Manager (id: {}) has a registration schema that isn't valid JSON, registrations won't be validated: {}", manager_id, err);
+                return None;
+            }
+        };
+
+        match jsonschema::validator_for(&schema) {
+            Ok(validator) => Some(RegistrationSchema(validator)),
+            Err(err) => {
+                warn!("Manager (id: {}) has an invalid registration schema, registrations won't be validated: {}", manager_id, err);
+                None
+            }
+        }
+    }
+
+    /// Checks that `data` is JSON matching the schema. Registrations without data are validated as `null`, so
+    /// schemas that require data reject them. Every part of the data that doesn't match is listed in the error,
+    /// along with where it is, like `/calibration/offset: "abc" is not of type "number"`.
+    pub fn validate(&self, data: Option<&[u8]>) -> Result<()> {
+        let data = match data {
+            Some(data) => serde_json::from_slice(data)
+                .map_err(|err| ManagerAndDataError::InvalidRegistration(vec![format!("not valid JSON: {}", err)]))?,
+            None => Value::Null
+        };
+
+        let errors: Vec<String> = self.0
+            .iter_errors(&data)
+            .map(|err| {
+                let path = err.instance_path().to_string();
+                format!("{}: {}", if path.is_empty() { "/" } else { &path }, err)
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(ManagerAndDataError::InvalidRegistration(errors))
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::{collections::HashMap, convert::Infallible, sync::RwLock};
+
+use florust_common::signature::{self, SIGNATURE_HEADER};
+use rocket::{Request, async_trait, request::{FromRequest, Outcome}};
+
+use crate::manager_and_data::{ManagerAndDataError, NamespacedId, Result};
+
+/// The signature an update was sent with, if any, taken from its `X-Florust-Signature` header.
+pub struct Signature(pub Option<String>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for Signature {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let signature = request.headers().get_one(SIGNATURE_HEADER).map(str::to_string);
+        Outcome::Success(Signature(signature))
+    }
+}
+
+/// The secrets data sources sign their updates with, set when they register. Secrets are only ever kept by the
+/// server, managers never see them.
+pub struct SigningSecrets {
+    secrets: RwLock<HashMap<(String, NamespacedId), String>>
+}
+
+impl SigningSecrets {
+    pub fn new() -> SigningSecrets {
+        SigningSecrets {
+            secrets: RwLock::new(HashMap::new())
+        }
+    }
+
+    /// Sets the secret of the data source `source` once it has registered, replacing the one it registered
+    /// with before. Data sources that register without a secret don't sign their updates, unless they kept the
+    /// secret they registered with before, like when they were reaped, which is only ever cleared by [`forget`].
+    ///
+    /// [`forget`]: SigningSecrets::forget
+    pub fn set(&self, manager_id: &str, source: &NamespacedId, secret: Option<&str>) {
+        let Some(secret) = secret else {
+            return;
+        };
+
+        self.secrets
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert((manager_id.to_string(), source.clone()), secret.to_string());
+    }
+
+    /// Forgets the secret of the data source `source`, once it has deregistered.
+    pub fn forget(&self, manager_id: &str, source: &NamespacedId) {
+        self.secrets
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(&(manager_id.to_string(), source.clone()));
+    }
+
+    /// Checks that `data`, an update from the data source `source`, was signed with its secret, if it has one.
+    /// Updates from data sources without a secret don't need a signature.
+    pub fn verify(&self, manager_id: &str, source: &NamespacedId, data: &[u8], signature: Option<&str>) -> Result<()> {
+        let secrets = self.secrets.read().unwrap_or_else(|err| err.into_inner());
+        let Some(secret) = secrets.get(&(manager_id.to_string(), source.clone())) else {
+            return Ok(());
+        };
+
+        match signature {
+            Some(signature) if signature::verify(secret, data, signature) => Ok(()),
+            _ => Err(ManagerAndDataError::InvalidSignature(source.to_string()))
+        }
+    }
+
+    /// Whether the data source `source` registered with a secret, so its updates must be signed.
+    pub fn requires_signature(&self, manager_id: &str, source: &NamespacedId) -> bool {
+        self.secrets
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .contains_key(&(manager_id.to_string(), source.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use florust_common::signature::sign;
+
+    use super::*;
+
+    #[test]
+    fn verifies_signatures_of_sources_with_a_secret() {
+        let secrets = SigningSecrets::new();
+        let source = NamespacedId::new("", "kitchen");
+        secrets.set("manager", &source, Some("secret"));
+        let data = 21.5f64.to_be_bytes();
+
+        assert!(secrets.verify("manager", &source, &data, Some(&sign("secret", &data))).is_ok());
+        assert!(matches!(
+            secrets.verify("manager", &source, &data, Some(&sign("other secret", &data))),
+            Err(ManagerAndDataError::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            secrets.verify("manager", &source, &data, None),
+            Err(ManagerAndDataError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn accepts_unsigned_updates_from_sources_without_a_secret() {
+        let secrets = SigningSecrets::new();
+        let source = NamespacedId::new("", "kitchen");
+        assert!(secrets.verify("manager", &source, b"data", None).is_ok());
+
+        secrets.set("manager", &source, Some("secret"));
+        secrets.forget("manager", &source);
+        assert!(secrets.verify("manager", &source, b"data", None).is_ok());
+    }
+
+    #[test]
+    fn registering_without_a_secret_keeps_a_retained_secret() {
+        let secrets = SigningSecrets::new();
+        let source = NamespacedId::new("", "kitchen");
+        secrets.set("manager", &source, Some("secret"));
+
+        // Reaping a data source keeps its secret, so registering it again without one, like over the Unix
+        // socket, still requires its updates to be signed.
+        secrets.set("manager", &source, None);
+        assert!(secrets.requires_signature("manager", &source));
+        assert!(matches!(
+            secrets.verify("manager", &source, b"data", None),
+            Err(ManagerAndDataError::InvalidSignature(_))
+        ));
+        assert!(secrets.verify("manager", &source, b"data", Some(&sign("secret", b"data"))).is_ok());
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use crate::{config::SourceFilterConfig, manager_and_data::{self, ManagerAndDataError, NamespacedId}};
+
+/// Whether `text` matches `pattern`, where `*` matches any run of characters, including none, and `?` matches
+/// any single character.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was, and where in `text` it's currently assumed to stop matching.
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            },
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            },
+            _ => match backtrack {
+                // Let the last `*` match one more character, and try again from there.
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                },
+                None => return false
+            }
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The data sources that may register with a single manager.
+struct SourceFilter {
+    allow: Vec<String>,
+    deny: Vec<String>
+}
+
+impl SourceFilter {
+    fn allows(&self, source: &str) -> bool {
+        let allowed = self.allow.is_empty() || self.allow.iter().any(|pattern| glob_matches(pattern, source));
+        allowed && !self.deny.iter().any(|pattern| glob_matches(pattern, source))
+    }
+}
+
+/// Decides which data sources may register with which managers.
+pub struct SourceFilters {
+    /// The filter of each manager that has one, keyed by manager.
+    filters: HashMap<String, SourceFilter>
+}
+
+impl SourceFilters {
+    pub fn from_config(config: &HashMap<String, SourceFilterConfig>) -> SourceFilters {
+        SourceFilters {
+            filters: config
+                .iter()
+                .map(|(manager_id, filter)| (manager_id.clone(), SourceFilter {
+                    allow: filter.allow().to_vec(),
+                    deny: filter.deny().to_vec()
+                }))
+                .collect()
+        }
+    }
+
+    /// Checks that the data source `source` may register with the manager with the id `manager_id`. Data
+    /// sources are matched by their id, preceded by their namespace and a `/` if they have one. Managers without
+    /// a filter let every data source register.
+    pub fn check(&self, manager_id: &str, source: &NamespacedId) -> manager_and_data::Result<()> {
+        match self.filters.get(manager_id) {
+            Some(filter) if !filter.allows(&source.to_string()) => Err(
+                ManagerAndDataError::RegistrationDenied {
+                    manager_id: manager_id.to_string(),
+                    data_source_id: source.to_string()
+                }
+            ),
+            _ => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(allow: &[&str], deny: &[&str]) -> SourceFilter {
+        SourceFilter {
+            allow: allow.iter().map(|pattern| pattern.to_string()).collect(),
+            deny: deny.iter().map(|pattern| pattern.to_string()).collect()
+        }
+    }
+
+    #[test]
+    fn wildcards_match_runs_and_single_characters() {
+        assert!(glob_matches("sensor_*", "sensor_"));
+        assert!(glob_matches("sensor_*", "sensor_42"));
+        assert!(!glob_matches("sensor_*", "sensor"));
+        assert!(glob_matches("sensor_??", "sensor_42"));
+        assert!(!glob_matches("sensor_??", "sensor_4"));
+        assert!(!glob_matches("sensor_??", "sensor_421"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("?", ""));
+        assert!(glob_matches("kitchen", "kitchen"));
+        assert!(!glob_matches("kitchen", "kitchens"));
+    }
+
+    #[test]
+    fn stars_backtrack_to_find_a_match() {
+        assert!(glob_matches("a*b*c", "aXbYbZc"));
+        assert!(glob_matches("a*b*c", "abc"));
+        assert!(!glob_matches("a*b*c", "aXbYbZ"));
+        assert!(glob_matches("*a?c", "abcabc"));
+        assert!(!glob_matches("*a?c", "abcabac"));
+    }
+
+    #[test]
+    fn namespaces_are_matched_with_their_id() {
+        let filters = SourceFilters {
+            filters: HashMap::from([("manager".to_string(), filter(&["tenant/*"], &[]))])
+        };
+
+        assert!(filters.check("manager", &NamespacedId::new("tenant", "kitchen")).is_ok());
+        assert!(matches!(
+            filters.check("manager", &NamespacedId::new("other", "kitchen")),
+            Err(ManagerAndDataError::RegistrationDenied { .. })
+        ));
+        assert!(filters.check("manager", &NamespacedId::new("", "kitchen")).is_err());
+        assert!(filters.check("unfiltered", &NamespacedId::new("other", "kitchen")).is_ok());
+    }
+
+    #[test]
+    fn empty_allowlists_allow_every_source() {
+        let filter = filter(&[], &[]);
+
+        assert!(filter.allows("kitchen"));
+        assert!(filter.allows("tenant/kitchen"));
+    }
+
+    #[test]
+    fn denials_override_allows() {
+        let filter = filter(&["sensor_*"], &["sensor_9?"]);
+
+        assert!(filter.allows("sensor_42"));
+        assert!(!filter.allows("sensor_93"));
+        assert!(!filter.allows("kitchen"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use rocket::{async_trait, tokio::task::spawn_blocking};
+use rusqlite::{Connection, params};
+
+use crate::{
+    manager_and_data::NamespacedId,
+    non_finite::{self, NonFiniteFloats},
+    storage::{Result, StorageBackend, StorageError, StoredRow, StoredValue}
+};
+
+/// A [`StorageBackend`] that persists values into a SQLite database. Values are stored in their tagged JSON
+/// encoding, so values of every type of manager share a single table. Floats that aren't finite are always
+/// stored tagged, like `{"float": "nan"}`, whatever the configured encoding, so they're read back as they were.
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>
+}
+
+impl SqliteStorage {
+    /// Opens the SQLite database at `path`, creating it and its schema if they don't exist.
+    pub fn open(path: &str) -> Result<SqliteStorage> {
+        let connection = Connection::open(path)
+            .map_err(|err| StorageError::Open(err.to_string()))?;
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS data_points (
+                id INTEGER PRIMARY KEY,
+                manager_id TEXT NOT NULL,
+                namespace TEXT NOT NULL DEFAULT '',
+                source_id TEXT NOT NULL,
+                value TEXT NOT NULL,
+                timestamp TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS data_points_source ON data_points (manager_id, namespace, source_id, timestamp);"
+        ).map_err(|err| StorageError::Open(err.to_string()))?;
+
+        Ok(SqliteStorage {
+            connection: Arc::new(Mutex::new(connection))
+        })
+    }
+}
+
+#[async_trait]
+impl StorageBackend for SqliteStorage {
+    async fn store(&self, manager_id: &str, source: &NamespacedId, value: StoredValue, ts: DateTime<Utc>) -> Result<()> {
+        self.store_batch(vec![StoredRow {
+            manager_id: manager_id.to_string(),
+            source: source.clone(),
+            value,
+            ts
+        }]).await
+    }
+
+    /// Writes every row in a single transaction, which is much faster than writing them one at a time.
+    async fn store_batch(&self, rows: Vec<StoredRow>) -> Result<()> {
+        let rows = non_finite::with_encoding(NonFiniteFloats::Tagged, || {
+            rows.into_iter()
+                .map(|row| serde_json::to_string(&row.value).map(|value| (row, value)))
+                .collect::<serde_json::Result<Vec<_>>>()
+        }).map_err(|err| StorageError::Store(err.to_string()))?;
+        let connection = self.connection.clone();
+
+        spawn_blocking(move || {
+            let mut connection = connection.lock()
+                .map_err(|err| StorageError::Store(err.to_string()))?;
+            let transaction = connection.transaction()
+                .map_err(|err| StorageError::Store(err.to_string()))?;
+
+            {
+                let mut insert = transaction.prepare_cached(
+                    "INSERT INTO data_points (manager_id, namespace, source_id, value, timestamp) VALUES (?1, ?2, ?3, ?4, ?5)"
+                ).map_err(|err| StorageError::Store(err.to_string()))?;
+
+                for (row, value) in rows {
+                    insert.execute(params![row.manager_id, row.source.namespace, row.source.id, value, row.ts.to_rfc3339()])
+                        .map_err(|err| StorageError::Store(err.to_string()))?;
+                }
+            }
+
+            transaction.commit().map_err(|err| StorageError::Store(err.to_string()))
+        }).await.map_err(|err| StorageError::Store(err.to_string()))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use crate::manager_and_data::DataType;
+
+    use super::*;
+
+    /// A database file only used by a single test, removed once the test is done with it.
+    struct TestDatabase(PathBuf);
+
+    impl TestDatabase {
+        fn new(name: &str) -> TestDatabase {
+            let path = env::temp_dir().join(format!("florust_{}_{}.sqlite", name, std::process::id()));
+            let _ = fs::remove_file(&path);
+            TestDatabase(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+
+        /// Reads back every row stored in the database, oldest first.
+        fn stored(&self) -> Vec<(String, NamespacedId, DataType, DateTime<Utc>)> {
+            let connection = Connection::open(&self.0).unwrap();
+            let mut select = connection.prepare("SELECT manager_id, namespace, source_id, value, timestamp FROM data_points ORDER BY id").unwrap();
+            select.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+                .unwrap()
+                .map(|row| {
+                    let (manager_id, namespace, source_id, value, timestamp): (String, String, String, String, String) = row.unwrap();
+                    (
+                        manager_id,
+                        NamespacedId::new(namespace, source_id),
+                        serde_json::from_str(&value).unwrap(),
+                        DateTime::parse_from_rfc3339(&timestamp).unwrap().with_timezone(&Utc)
+                    )
+                })
+                .collect()
+        }
+    }
+
+    impl Drop for TestDatabase {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn values_are_read_back_after_reopening() {
+        let database = TestDatabase::new("reopen");
+        let kitchen = NamespacedId::new("tenant", "kitchen");
+        let ts = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().with_timezone(&Utc);
+
+        let storage = SqliteStorage::open(database.path()).unwrap();
+        storage.store("FlorustDefaultFloatDataManager", &kitchen, DataType::Float(21.5), ts).await.unwrap();
+        drop(storage);
+
+        let storage = SqliteStorage::open(database.path()).unwrap();
+        storage.store("FlorustDefaultIIntegerDataManager", &kitchen, DataType::IInteger(-3), ts).await.unwrap();
+        drop(storage);
+
+        let stored = database.stored();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].0, "FlorustDefaultFloatDataManager");
+        assert!(stored[0].1 == kitchen);
+        assert!(matches!(stored[0].2, DataType::Float(value) if value == 21.5));
+        assert_eq!(stored[0].3, ts);
+        assert!(matches!(stored[1].2, DataType::IInteger(-3)));
+    }
+
+    #[rocket::async_test]
+    async fn non_finite_floats_are_stored_as_they_are() {
+        let database = TestDatabase::new("non_finite");
+        let kitchen = NamespacedId::new("", "kitchen");
+        let storage = SqliteStorage::open(database.path()).unwrap();
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            storage.store("FlorustDefaultFloatDataManager", &kitchen, DataType::Float(value), Utc::now()).await.unwrap();
+        }
+
+        let stored = database.stored()
+            .into_iter()
+            .map(|(_, _, value, _)| match value {
+                DataType::Float(value) => value,
+                _ => panic!("value isn't a float")
+            })
+            .collect::<Vec<_>>();
+        assert!(stored[0].is_nan());
+        assert_eq!(stored[1..], [f64::INFINITY, f64::NEG_INFINITY]);
+    }
+}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use rocket::async_trait;
+use thiserror::Error;
+
+use crate::manager_and_data::{DataType, NamespacedId};
+
+/// A value to be persisted by a [`StorageBackend`]. Values are tagged with the type of data they hold, so a
+/// single backend can store the values of every type of manager.
+pub type StoredValue = DataType;
+
+#[derive(Error, Debug)]
+#[cfg_attr(not(feature = "sqlite_storage"), allow(dead_code))]
+pub enum StorageError {
+    #[error("Storage backend failed to open: {0}")]
+    Open(String),
+    #[error("Storage backend failed to store value: {0}")]
+    Store(String)
+}
+
+pub type Result<T> = std::result::Result<T, StorageError>;
+
+/// A single value to be persisted, along with where it came from, for backends that persist several values
+/// at once.
+pub struct StoredRow {
+    pub manager_id: String,
+    pub source: NamespacedId,
+    pub value: StoredValue,
+    pub ts: DateTime<Utc>
+}
+
+/// A trait defining a place where the values produced by data source managers are persisted, so they
+/// aren't lost when the server restarts.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Persists `value`, produced by the manager with the id `manager_id` for the data source `source`, which
+    /// was measured at `ts`.
+    async fn store(&self, manager_id: &str, source: &NamespacedId, value: StoredValue, ts: DateTime<Utc>) -> Result<()>;
+
+    /// Persists every row in `rows`. Stores them one by one by default, backends that can write several
+    /// values at once more cheaply should override this.
+    async fn store_batch(&self, rows: Vec<StoredRow>) -> Result<()> {
+        for row in rows {
+            self.store(&row.manager_id, &row.source, row.value, row.ts).await?;
+        }
+        Ok(())
+    }
+
+    /// Starts the work the backend does in the background. Called once the server has started, as background
+    /// tasks can't be spawned before then. Does nothing by default.
+    fn start(&self) {}
+
+    /// Persists every value the backend is holding on to, called when the server shuts down. Does nothing by
+    /// default, as most backends persist values as soon as they're stored.
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use std::{borrow::Cow, collections::HashMap, sync::Mutex, time::Duration};
+
+use rocket::{futures::{SinkExt, StreamExt}, serde::Serialize, tokio::{select, sync::broadcast::{self, Receiver, Sender, error::RecvError}, time}};
+use rocket_ws::{Channel, Message, WebSocket, frame::{CloseCode, CloseFrame}};
+
+use crate::manager_and_data::{DataPoint, NamespacedId, ServedValue};
+
+/// How many data points are buffered for each subscriber of a manager's stream. Subscribers that fall
+/// further behind than this are disconnected.
+pub const STREAM_BUFFER_SIZE: usize = 64;
+
+/// How long a read waiting for a new data point blocks by default before giving up.
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The longest a read waiting for a new data point may block, however long it asks to.
+pub const MAX_WAIT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A data point logged by a data source, sent to every subscriber of its manager's stream the way its manager
+/// serves values.
+#[derive(Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct StreamedDataPoint {
+    pub data_source: NamespacedId,
+    pub data_point: DataPoint<ServedValue>,
+    /// The sequence number of the data point, counting up from 1 for every data point its data source logged
+    /// since the server started.
+    pub seq: u64
+}
+
+/// The stream of data points logged by the data sources of a single manager.
+pub struct ManagerStream {
+    sender: Sender<StreamedDataPoint>,
+    /// The most recent data point each data source logged, so reads waiting for a data point newer than one
+    /// they already have don't block if it was logged before they started waiting. Also the last known good
+    /// values served while the manager reloads.
+    latest: Mutex<HashMap<NamespacedId, StreamedDataPoint>>
+}
+
+impl Default for ManagerStream {
+    fn default() -> ManagerStream {
+        ManagerStream {
+            sender: broadcast::channel(STREAM_BUFFER_SIZE).0,
+            latest: Mutex::new(HashMap::new())
+        }
+    }
+}
+
+impl ManagerStream {
+    pub fn subscribe(&self) -> Receiver<StreamedDataPoint> {
+        self.sender.subscribe()
+    }
+
+    /// Numbers a data point logged by `data_source` and sends it to every subscriber.
+    pub fn publish(&self, data_source: &NamespacedId, data_point: DataPoint<ServedValue>) {
+        // The lock is held while sending, so subscribers receive the data points of a data source in the
+        // order they were numbered.
+        let mut latest = self.latest.lock().unwrap_or_else(|err| err.into_inner());
+        let seq = latest.get(data_source).map_or(0, |latest| latest.seq) + 1;
+        let streamed = StreamedDataPoint {
+            data_source: data_source.clone(),
+            data_point,
+            seq
+        };
+
+        latest.insert(data_source.clone(), streamed.clone());
+        // Sending only fails if there are no subscribers, which is fine.
+        let _ = self.sender.send(streamed);
+    }
+
+    /// Returns the most recent data point `data_source` logged since the server started, if it logged any.
+    pub fn latest(&self, data_source: &NamespacedId) -> Option<DataPoint<ServedValue>> {
+        self.latest_since(data_source, 0).map(|latest| latest.data_point)
+    }
+
+    /// Returns the most recent data point `data_source` logged, if its sequence number is greater than
+    /// `since`.
+    fn latest_since(&self, data_source: &NamespacedId, since: u64) -> Option<StreamedDataPoint> {
+        self.latest
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(data_source)
+            .filter(|latest| latest.seq > since)
+            .cloned()
+    }
+
+    /// Waits for `data_source` to log a data point with a sequence number greater than `since`, returning
+    /// right away if it already has. Returns `None` if it doesn't within `timeout`.
+    pub async fn wait(&self, data_source: &NamespacedId, since: u64, timeout: Duration) -> Option<StreamedDataPoint> {
+        // Subscribing before looking at the latest data point means one logged in between is still received.
+        let mut receiver = self.subscribe();
+        if let Some(latest) = self.latest_since(data_source, since) {
+            return Some(latest);
+        }
+
+        time::timeout(timeout, async {
+            loop {
+                match receiver.recv().await {
+                    Ok(streamed) if streamed.data_source == *data_source && streamed.seq > since => return Some(streamed),
+                    Ok(_) => continue,
+                    // Data points that were missed are looked up instead.
+                    Err(RecvError::Lagged(_)) => if let Some(latest) = self.latest_since(data_source, since) {
+                        return Some(latest);
+                    },
+                    Err(RecvError::Closed) => return None
+                }
+            }
+        }).await.ok().flatten()
+    }
+}
+
+/// Sends every data point received from `receiver` over the websocket, skipping data points that don't
+/// belong to `namespace`, or to `data_source_id` if one is given.
+///
+/// Subscribers that fall behind are disconnected with a close frame instead of being buffered for.
+pub fn stream_data_points(ws: WebSocket, mut receiver: Receiver<StreamedDataPoint>, namespace: String, data_source_id: Option<String>) -> Channel<'static> {
+    ws.channel(move |mut stream| Box::pin(async move {
+        loop {
+            select! {
+                streamed = receiver.recv() => match streamed {
+                    Ok(streamed) => {
+                        if streamed.data_source.namespace != namespace {
+                            continue;
+                        }
+
+                        if data_source_id.as_ref().is_some_and(|id| *id != streamed.data_source.id) {
+                            continue;
+                        }
+
+                        let message = serde_json::to_string(&streamed)
+                            .expect("data points should always be serializable");
+                        stream.send(Message::Text(message)).await?;
+                    },
+                    Err(RecvError::Lagged(_)) => {
+                        stream.close(Some(CloseFrame {
+                            code: CloseCode::Again,
+                            reason: Cow::Borrowed("Subscriber fell too far behind the stream")
+                        })).await?;
+                        break;
+                    },
+                    Err(RecvError::Closed) => {
+                        stream.close(None).await?;
+                        break;
+                    }
+                },
+                message = stream.next() => match message {
+                    // Subscribers have nothing to say, anything they send is ignored.
+                    Some(Ok(_)) => continue,
+                    Some(Err(err)) => return Err(err),
+                    None => break
+                }
+            }
+        }
+
+        Ok(())
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde_json::json;
+
+    use super::*;
+    use crate::manager_and_data::DataType;
+
+    #[rocket::async_test]
+    async fn waits_get_the_served_value() {
+        let stream = ManagerStream::default();
+        let kitchen = NamespacedId::new("", "kitchen");
+        stream.publish(&kitchen, DataPoint {
+            value: ServedValue::Serialized(DataType::Float(21.5), json!("21.5 °C")),
+            timestamp: Utc::now()
+        });
+
+        let streamed = stream.wait(&kitchen, 0, Duration::ZERO).await.unwrap();
+        let streamed = serde_json::to_value(&streamed).unwrap();
+        assert_eq!(streamed["data_point"]["value"], json!({"Float": "21.5 °C"}));
+        assert_eq!(streamed["seq"], json!(1));
+
+        assert!(stream.wait(&kitchen, 1, Duration::from_millis(10)).await.is_none());
+    }
+}
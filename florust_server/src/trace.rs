@@ -0,0 +1,43 @@
+use std::convert::Infallible;
+
+use rocket::{Request, async_trait, request::{FromRequest, Outcome}};
+
+/// Name of the header clients send to continue a distributed trace, as defined by W3C Trace Context.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// The `traceparent` header a request was sent with, if it was sent with a valid one. Malformed headers are
+/// ignored rather than rejected, as tracing is never required for an update to be logged.
+pub struct TraceParent(pub Option<String>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for TraceParent {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let trace_parent = request.headers()
+            .get_one(TRACEPARENT_HEADER)
+            .map(str::trim)
+            .filter(|header| is_valid(header))
+            .map(str::to_ascii_lowercase);
+
+        Outcome::Success(TraceParent(trace_parent))
+    }
+}
+
+/// Whether `header` is a `traceparent` made up of a version, trace id, parent id, and flags. Versions newer
+/// than `00` may append fields, which are kept but not checked.
+fn is_valid(header: &str) -> bool {
+    let mut fields = header.split('-');
+    let (Some(version), Some(trace_id), Some(parent_id), Some(flags)) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+        return false;
+    };
+
+    let is_hex = |field: &str, len: usize| field.len() == len && field.bytes().all(|b| b.is_ascii_hexdigit());
+    let is_zero = |field: &str| field.bytes().all(|b| b == b'0');
+
+    is_hex(version, 2) && !version.eq_ignore_ascii_case("ff")
+        && is_hex(trace_id, 32) && !is_zero(trace_id)
+        && is_hex(parent_id, 16) && !is_zero(parent_id)
+        && is_hex(flags, 2)
+        && (version != "00" || fields.next().is_none())
+}
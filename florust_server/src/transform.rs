@@ -0,0 +1,97 @@
+use std::{borrow::Cow, sync::Arc};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use thiserror::Error;
+
+use crate::upload::decompress;
+
+#[derive(Error, Debug)]
+pub enum TransformError {
+    #[error("Unknown transform ({0}), expected one of: gzip, base64")]
+    Unknown(String),
+    #[error("Transform ({name}) failed: {reason}")]
+    Failed {
+        name: &'static str,
+        reason: String
+    }
+}
+
+pub type Result<T> = std::result::Result<T, TransformError>;
+
+/// A step decoding the bytes of an update before they reach a manager, like decompressing them, so transport
+/// encodings can be undone without every manager handling them itself.
+pub trait ByteTransform: Send + Sync {
+    /// The name the transform is listed by in plugin configs.
+    fn name(&self) -> &'static str;
+
+    fn transform(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Decompresses gzip compressed data, failing if it decompresses to more than `max_size` bytes.
+pub struct GzipDecompress {
+    max_size: usize
+}
+
+impl ByteTransform for GzipDecompress {
+    fn name(&self) -> &'static str {
+        "gzip"
+    }
+
+    fn transform(&self, data: &[u8]) -> Result<Vec<u8>> {
+        decompress(data, self.max_size).map_err(|err| TransformError::Failed {
+            name: self.name(),
+            reason: err.to_string()
+        })
+    }
+}
+
+/// Decodes standard base64, ignoring whitespace around it, like a trailing newline.
+pub struct Base64Decode;
+
+impl ByteTransform for Base64Decode {
+    fn name(&self) -> &'static str {
+        "base64"
+    }
+
+    fn transform(&self, data: &[u8]) -> Result<Vec<u8>> {
+        STANDARD.decode(data.trim_ascii()).map_err(|err| TransformError::Failed {
+            name: self.name(),
+            reason: err.to_string()
+        })
+    }
+}
+
+/// The transforms every update to a manager goes through before it reaches the manager, in order.
+#[derive(Default)]
+pub struct Transforms(Vec<Box<dyn ByteTransform>>);
+
+/// Transforms shared by every manager of a plugin.
+pub type SharedTransforms = Arc<Transforms>;
+
+impl Transforms {
+    /// Creates the transforms listed by `names` in a plugin config. `max_decompressed_size` is the most bytes
+    /// decompressing an update may produce.
+    pub fn from_names(names: &[String], max_decompressed_size: usize) -> Result<Transforms> {
+        names.iter()
+            .map(|name| -> Result<Box<dyn ByteTransform>> {
+                match name.as_str() {
+                    "gzip" => Ok(Box::new(GzipDecompress { max_size: max_decompressed_size })),
+                    "base64" => Ok(Box::new(Base64Decode)),
+                    name => Err(TransformError::Unknown(name.to_string()))
+                }
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Transforms)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Passes `data` through every transform in order, returning it as is if there are none.
+    pub fn apply<'a>(&self, data: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        self.0.iter().try_fold(Cow::Borrowed(data), |data, transform| {
+            transform.transform(&data).map(Cow::Owned)
+        })
+    }
+}
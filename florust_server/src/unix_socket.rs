@@ -0,0 +1,201 @@
+use std::{collections::BTreeSet, fs::remove_file, io, path::Path};
+
+use tracing::{info, warn};
+use rocket::tokio::{self, io::{AsyncReadExt, AsyncWriteExt}, net::{UnixListener, UnixStream}};
+use thiserror::Error;
+
+use crate::{FlorustState, manager_and_data::{self, ManagerAndDataError, NamespacedId}};
+
+/// The most bytes a frame's header may take up, which is its operation, two length prefixed ids, and a length
+/// prefixed signature.
+const MAX_HEADER_SIZE: usize = 1 + 3 * (2 + u16::MAX as usize);
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+
+/// What a frame asks the server to do.
+enum Operation {
+    Register,
+    Update,
+    Deregister,
+    /// An update sent along with its signature, from a data source that registered with a signing secret.
+    SignedUpdate
+}
+
+impl TryFrom<u8> for Operation {
+    type Error = FrameError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Register),
+            1 => Ok(Self::Update),
+            2 => Ok(Self::Deregister),
+            3 => Ok(Self::SignedUpdate),
+            op => Err(FrameError::UnknownOperation(op))
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+enum FrameError {
+    #[error("Frame ends before its header does")]
+    Truncated,
+    #[error("Frame has an unknown operation: {0}")]
+    UnknownOperation(u8),
+    #[error("Frame contains an id or signature that isn't valid UTF-8")]
+    InvalidId
+}
+
+/// A single request sent over the socket.
+struct Frame<'a> {
+    operation: Operation,
+    manager_id: &'a str,
+    data_source_id: NamespacedId,
+    /// The signature of the payload, sent with signed updates.
+    signature: Option<&'a str>,
+    payload: &'a [u8]
+}
+
+impl<'a> Frame<'a> {
+    /// Parses the body of a frame, everything after its length.
+    fn parse(body: &'a [u8]) -> Result<Frame<'a>, FrameError> {
+        let (&operation, rest) = body.split_first().ok_or(FrameError::Truncated)?;
+        let (manager_id, rest) = read_str(rest)?;
+        let (data_source_id, rest) = read_str(rest)?;
+        let operation = Operation::try_from(operation)?;
+        let (signature, payload) = match operation {
+            Operation::SignedUpdate => read_str(rest).map(|(signature, payload)| (Some(signature), payload))?,
+            _ => (None, rest)
+        };
+
+        // Ids are given the same way they are in URLs, with an optional namespace.
+        let data_source_id = match data_source_id.split_once('/') {
+            Some((namespace, id)) => NamespacedId::new(namespace, id),
+            None => NamespacedId::new("", data_source_id)
+        };
+
+        Ok(Frame {
+            operation,
+            manager_id,
+            data_source_id,
+            signature,
+            payload
+        })
+    }
+}
+
+/// Reads a string prefixed by its length as a big endian `u16`, returning it along with the bytes after it.
+fn read_str(bytes: &[u8]) -> Result<(&str, &[u8]), FrameError> {
+    let (len, rest) = bytes.split_first_chunk::<2>().ok_or(FrameError::Truncated)?;
+    let len = u16::from_be_bytes(*len) as usize;
+    if rest.len() < len {
+        return Err(FrameError::Truncated);
+    }
+
+    let (str, rest) = rest.split_at(len);
+    let str = std::str::from_utf8(str).map_err(|_| FrameError::InvalidId)?;
+    Ok((str, rest))
+}
+
+/// Listens for data sources on the Unix domain socket at `path`, replacing any socket file left behind by a
+/// previous run. Every connection may send any number of frames, which are handled in order.
+pub async fn listen(state: FlorustState, path: String) {
+    if Path::new(&path).exists() {
+        if let Err(err) = remove_file(&path) {
+            warn!("Failed to remove existing Unix socket ({}), not listening on it: {}", path, err);
+            return;
+        }
+    }
+
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!("Failed to bind Unix socket ({}): {}", path, err);
+            return;
+        }
+    };
+    info!("Listening for data sources on Unix socket: {}", path);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(state.clone(), stream));
+            },
+            Err(err) => warn!("Failed to accept connection on Unix socket ({}): {}", path, err)
+        }
+    }
+}
+
+async fn handle_connection(state: FlorustState, mut stream: UnixStream) {
+    let max_frame_size = MAX_HEADER_SIZE + state.upload_limits.values().copied().fold(state.max_upload_size, usize::max);
+
+    loop {
+        let len = match stream.read_u32().await {
+            Ok(len) => len as usize,
+            // The data source closed the connection.
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return,
+            Err(err) => {
+                warn!("Failed to read frame from Unix socket: {}", err);
+                return;
+            }
+        };
+
+        // The rest of an oversized frame is never read, so the connection can't be used past it.
+        if len > max_frame_size {
+            let _ = respond(&mut stream, Err(ManagerAndDataError::PayloadTooLarge(max_frame_size))).await;
+            return;
+        }
+
+        let mut body = vec![0; len];
+        if let Err(err) = stream.read_exact(&mut body).await {
+            warn!("Failed to read frame from Unix socket: {}", err);
+            return;
+        }
+
+        let result = match Frame::parse(&body) {
+            Ok(frame) => dispatch(&state, frame).await,
+            Err(err) => Err(ManagerAndDataError::InvalidUpload(err.to_string()))
+        };
+
+        if let Err(err) = respond(&mut stream, result).await {
+            warn!("Failed to respond on Unix socket: {}", err);
+            return;
+        }
+    }
+}
+
+/// Passes `frame` on to its manager, the same way as if it was sent over HTTP.
+async fn dispatch(state: &FlorustState, frame: Frame<'_>) -> manager_and_data::Result<()> {
+    let max_upload_size = state.max_upload_size(frame.manager_id);
+    if frame.payload.len() > max_upload_size {
+        return Err(ManagerAndDataError::PayloadTooLarge(max_upload_size));
+    }
+
+    // Registrations and deregistrations without a payload are sent without data.
+    let data = Some(frame.payload).filter(|payload| !payload.is_empty());
+    match frame.operation {
+        Operation::Register => state.register_data_source(frame.manager_id, frame.data_source_id, data, None, BTreeSet::new()).await,
+        // Updates are checked against the secret their data source registered with the same way as over HTTP,
+        // so a data source that signs its updates can't be spoofed over the socket either.
+        Operation::Update | Operation::SignedUpdate => {
+            state.check_signature(frame.manager_id, &frame.data_source_id, frame.payload, frame.signature)?;
+            state.update_data(frame.manager_id, &frame.data_source_id, frame.payload, None, false).await.map(|_| ())
+        },
+        Operation::Deregister => state.deregister_data_source(frame.manager_id, &frame.data_source_id, data, false).await
+    }
+}
+
+/// Sends the outcome of a frame, as its status followed by the error's message if it failed, prefixed by its
+/// length as a big endian `u32`.
+async fn respond(stream: &mut UnixStream, result: manager_and_data::Result<()>) -> io::Result<()> {
+    let (status, message) = match result {
+        Ok(()) => (STATUS_OK, String::new()),
+        Err(err) => (STATUS_ERROR, err.to_string())
+    };
+
+    let mut response = Vec::with_capacity(4 + 1 + message.len());
+    response.extend_from_slice(&(1 + message.len() as u32).to_be_bytes());
+    response.push(status);
+    response.extend_from_slice(message.as_bytes());
+    stream.write_all(&response).await
+}
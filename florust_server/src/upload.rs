@@ -0,0 +1,217 @@
+use std::{io::{self, Read}, pin::Pin, task::{Context, Poll}};
+
+use flate2::read::GzDecoder;
+use florust_common::UploadedData;
+use rocket::{
+    Request, async_trait,
+    data::{Data, DataStream, FromData, Outcome, ToByteUnit},
+    form::Form,
+    http::{RawStr, Status},
+    tokio::io::{AsyncRead, ReadBuf}
+};
+use thiserror::Error;
+
+use crate::{FlorustState, manager_and_data::ManagerAndDataError};
+
+/// How many bytes the body of an upload may be, unless configured otherwise.
+pub const DEFAULT_MAX_UPLOAD_SIZE: usize = 1024 * 1024;
+
+/// How many bytes a compressed upload may decompress to, unless configured otherwise.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum UploadError {
+    #[error("Upload body couldn't be read: {0}")]
+    Read(String),
+    #[error("Upload body exceeds {0} bytes")]
+    PayloadTooLarge(usize),
+    #[error("Upload body couldn't be decompressed: {0}")]
+    Decompress(String),
+    #[error("Upload body decompresses to more than {0} bytes")]
+    TooLarge(usize),
+    #[error("Upload body couldn't be parsed: {0}")]
+    Parse(String),
+    #[error("Upload body uses an unsupported content encoding: {0}")]
+    UnsupportedEncoding(String),
+    #[error("Upload body is empty")]
+    Empty
+}
+
+impl From<UploadError> for ManagerAndDataError {
+    fn from(value: UploadError) -> Self {
+        match value {
+            UploadError::PayloadTooLarge(limit) => ManagerAndDataError::PayloadTooLarge(limit),
+            err => ManagerAndDataError::InvalidUpload(err.to_string())
+        }
+    }
+}
+
+/// The data uploaded by a data source, as either JSON or a form. Bodies may be at most the `max_upload_size`
+/// of the manager they're sent to. Bodies sent with `Content-Encoding: gzip` are decompressed before they're
+/// parsed, up to the server's `max_decompressed_size`.
+pub struct UploadBody(pub UploadedData);
+
+/// Returns the data uploaded by a data source if it sent any, for endpoints where uploading data is optional.
+/// Empty bodies are treated as if no data was sent, but bodies that can't be read or parsed are still
+/// rejected, so a malformed body can't silently drop what was sent with it, like a signing secret.
+pub fn optional_upload(data: Result<UploadBody, UploadError>) -> Result<Option<UploadedData>, ManagerAndDataError> {
+    match data {
+        Ok(data) => Ok(Some(data.0)),
+        Err(UploadError::Empty) => Ok(None),
+        Err(err) => Err(err.into())
+    }
+}
+
+#[async_trait]
+impl<'r> FromData<'r> for UploadBody {
+    type Error = UploadError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let is_json = request.content_type().is_some_and(|content_type| content_type.is_json());
+
+        let encoding = request.headers().get_one("Content-Encoding").map(str::to_ascii_lowercase);
+        let compressed = match encoding.as_deref() {
+            None | Some("identity") => false,
+            Some("gzip") => true,
+            Some(encoding) => {
+                return Outcome::Error((Status::UnsupportedMediaType, UploadError::UnsupportedEncoding(encoding.to_string())));
+            }
+        };
+
+        let state = request.rocket().state::<FlorustState>();
+        let max_upload_size = match (state, manager_id(request)) {
+            (Some(state), Some(manager_id)) => state.max_upload_size(manager_id),
+            _ => DEFAULT_MAX_UPLOAD_SIZE
+        };
+
+        let body = match data.open(max_upload_size.bytes()).into_bytes().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => return Outcome::Error((Status::PayloadTooLarge, UploadError::PayloadTooLarge(max_upload_size))),
+            Err(err) => return Outcome::Error((Status::BadRequest, UploadError::Read(err.to_string())))
+        };
+
+        let body = if compressed {
+            let max_decompressed_size = state
+                .map(|state| state.max_decompressed_size)
+                .unwrap_or(DEFAULT_MAX_DECOMPRESSED_SIZE);
+
+            match decompress(&body, max_decompressed_size) {
+                Ok(body) => body,
+                Err(err) => return Outcome::Error((Status::BadRequest, err))
+            }
+        }
+        else {
+            body
+        };
+
+        if body.is_empty() {
+            return Outcome::Error((Status::BadRequest, UploadError::Empty));
+        }
+
+        let parsed = if is_json {
+            serde_json::from_slice::<UploadedData>(&body).map_err(|err| err.to_string())
+        }
+        else {
+            String::from_utf8(body)
+                .map_err(|err| err.to_string())
+                .and_then(|body| Form::<UploadedData>::parse_encoded(RawStr::new(&body)).map_err(|err| err.to_string()))
+        };
+
+        match parsed {
+            Ok(uploaded) => Outcome::Success(UploadBody(uploaded)),
+            Err(err) => Outcome::Error((Status::BadRequest, UploadError::Parse(err)))
+        }
+    }
+}
+
+/// The raw body of an update streamed by a data source, read as the manager parses it rather than all at once.
+/// Bodies may be at most the `max_upload_size` of the manager they're sent to, reading fails once they exceed
+/// it. Compressed bodies aren't supported, as managers are handed the body exactly as it's sent.
+pub struct StreamBody<'r> {
+    stream: DataStream<'r>,
+    limit: usize,
+    read: usize,
+    exceeded: bool
+}
+
+impl StreamBody<'_> {
+    /// Returns the size limit of the body if it turned out to exceed it while it was being read.
+    pub fn exceeded_limit(&self) -> Option<usize> {
+        self.exceeded.then_some(self.limit)
+    }
+}
+
+#[async_trait]
+impl<'r> FromData<'r> for StreamBody<'r> {
+    type Error = UploadError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        match request.headers().get_one("Content-Encoding").map(str::to_ascii_lowercase).as_deref() {
+            None | Some("identity") => {},
+            Some(encoding) => {
+                return Outcome::Error((Status::UnsupportedMediaType, UploadError::UnsupportedEncoding(encoding.to_string())));
+            }
+        }
+
+        let limit = match (request.rocket().state::<FlorustState>(), manager_id(request)) {
+            (Some(state), Some(manager_id)) => state.max_upload_size(manager_id),
+            _ => DEFAULT_MAX_UPLOAD_SIZE
+        };
+
+        // One byte past the limit is let through, so bodies that exceed it can be told apart from bodies that
+        // are exactly the limit.
+        Outcome::Success(StreamBody {
+            stream: data.open((limit + 1).bytes()),
+            limit,
+            read: 0,
+            exceeded: false
+        })
+    }
+}
+
+impl AsyncRead for StreamBody<'_> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut self.stream).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(())) = result {
+            self.read += buf.filled().len() - filled;
+            if self.read > self.limit {
+                // Reads that fail mustn't leave anything in the buffer.
+                buf.set_filled(filled);
+                self.exceeded = true;
+                return Poll::Ready(Err(io::Error::other(UploadError::PayloadTooLarge(self.limit))));
+            }
+        }
+
+        result
+    }
+}
+
+/// Returns the id of the manager an upload is sent to, taken from the `<manager_id>` parameter of the route
+/// that's handling it.
+pub fn manager_id<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    let index = request.route()?
+        .uri
+        .unmounted_origin
+        .path()
+        .segments()
+        .position(|segment| segment == "<manager_id>")?;
+
+    request.routed_segment(index)
+}
+
+/// Decompresses the gzip compressed `compressed`, failing if it decompresses to more than `max_size` bytes.
+pub fn decompress(compressed: &[u8], max_size: usize) -> Result<Vec<u8>, UploadError> {
+    let mut body = Vec::new();
+    GzDecoder::new(compressed)
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| UploadError::Decompress(err.to_string()))?;
+
+    if body.len() > max_size {
+        return Err(UploadError::TooLarge(max_size));
+    }
+
+    Ok(body)
+}
@@ -0,0 +1,178 @@
+use std::{mem, sync::{Arc, Mutex}, time::Duration};
+
+use chrono::{DateTime, Utc};
+use rocket::{async_trait, tokio::{self, sync::Mutex as AsyncMutex, time::{self, MissedTickBehavior}}};
+use tracing::warn;
+
+use crate::{manager_and_data::NamespacedId, storage::{Result, StorageBackend, StorageError, StoredRow, StoredValue}};
+
+/// How many values are buffered by default before they're written to storage.
+pub const DEFAULT_MAX_BUFFERED_ROWS: usize = 1000;
+
+/// A [`StorageBackend`] that buffers values before passing them on to another backend, so values are written
+/// in batches instead of one at a time. The buffer is written once it holds `max_rows` values, or every
+/// `flush_interval`, whichever comes first, as well as when the server shuts down.
+#[cfg_attr(not(feature = "sqlite_storage"), allow(dead_code))]
+pub struct WriteBuffer {
+    inner: Arc<WriteBufferInner>,
+    flush_interval: Duration
+}
+
+struct WriteBufferInner {
+    backend: Box<dyn StorageBackend>,
+    buffered: Mutex<Vec<StoredRow>>,
+    /// Held while buffered values are written, so a flush only returns once every value buffered before it,
+    /// including ones taken by a flush that was already running, has been written.
+    flushing: AsyncMutex<()>,
+    max_rows: usize
+}
+
+impl WriteBufferInner {
+    /// Writes every buffered value to the backend. Values that fail to be written are dropped, so a backend
+    /// that stays unavailable can't grow the buffer without bound.
+    async fn flush(&self) -> Result<()> {
+        let _flushing = self.flushing.lock().await;
+        let rows = mem::take(&mut *self.buffered.lock().map_err(|err| StorageError::Store(err.to_string()))?);
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let count = rows.len();
+        self.backend.store_batch(rows).await
+            .map_err(|err| StorageError::Store(format!("{} buffered values were dropped: {}", count, err)))
+    }
+}
+
+#[cfg_attr(not(feature = "sqlite_storage"), allow(dead_code))]
+impl WriteBuffer {
+    /// Buffers the values stored to `backend`. Returns `backend` as is if `flush_interval` isn't a positive
+    /// number of seconds, in which case values are written as soon as they're stored.
+    pub fn wrap(backend: Box<dyn StorageBackend>, flush_interval: f64, max_rows: usize) -> Box<dyn StorageBackend> {
+        match Duration::try_from_secs_f64(flush_interval) {
+            Ok(flush_interval) if !flush_interval.is_zero() => Box::new(WriteBuffer {
+                inner: Arc::new(WriteBufferInner {
+                    backend,
+                    buffered: Mutex::new(Vec::new()),
+                    flushing: AsyncMutex::new(()),
+                    max_rows: max_rows.max(1)
+                }),
+                flush_interval
+            }),
+            _ => backend
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for WriteBuffer {
+    async fn store(&self, manager_id: &str, source: &NamespacedId, value: StoredValue, ts: DateTime<Utc>) -> Result<()> {
+        self.store_batch(vec![StoredRow {
+            manager_id: manager_id.to_string(),
+            source: source.clone(),
+            value,
+            ts
+        }]).await
+    }
+
+    async fn store_batch(&self, rows: Vec<StoredRow>) -> Result<()> {
+        let full = {
+            let mut buffered = self.inner.buffered.lock().map_err(|err| StorageError::Store(err.to_string()))?;
+            buffered.extend(rows);
+            buffered.len() >= self.inner.max_rows
+        };
+
+        if full {
+            self.inner.flush().await?;
+        }
+        Ok(())
+    }
+
+    fn start(&self) {
+        self.inner.backend.start();
+
+        let inner = self.inner.clone();
+        let mut interval = time::interval(self.flush_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                if let Err(err) = inner.flush().await {
+                    warn!("Failed to write buffered values to storage: {}", err);
+                }
+            }
+        });
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.inner.flush().await?;
+        self.inner.backend.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::tokio::sync::Notify;
+
+    use super::*;
+
+    /// A backend that takes a while to write, recording every value once it's written.
+    #[derive(Clone, Default)]
+    struct SlowBackend {
+        stored: Arc<Mutex<Vec<StoredRow>>>,
+        writing: Arc<Notify>
+    }
+
+    #[async_trait]
+    impl StorageBackend for SlowBackend {
+        async fn store(&self, manager_id: &str, source: &NamespacedId, value: StoredValue, ts: DateTime<Utc>) -> Result<()> {
+            self.writing.notify_one();
+            time::sleep(Duration::from_millis(50)).await;
+            self.stored.lock().unwrap().push(StoredRow {
+                manager_id: manager_id.to_string(),
+                source: source.clone(),
+                value,
+                ts
+            });
+            Ok(())
+        }
+    }
+
+    fn row(value: f64) -> StoredRow {
+        StoredRow {
+            manager_id: "manager".to_string(),
+            source: NamespacedId::new("", "kitchen"),
+            value: StoredValue::Float(value),
+            ts: Utc::now()
+        }
+    }
+
+    #[rocket::async_test]
+    async fn flush_on_shutdown_waits_for_the_running_flush() {
+        let backend = SlowBackend::default();
+        let buffer = WriteBuffer::wrap(Box::new(backend.clone()), 60.0, 2);
+
+        buffer.store_batch(vec![row(1.0)]).await.unwrap();
+        // Fills the buffer, so its values are being written while the server shuts down.
+        let buffer = Arc::new(buffer);
+        let filling = tokio::spawn({
+            let buffer = buffer.clone();
+            async move { buffer.store_batch(vec![row(2.0)]).await }
+        });
+        backend.writing.notified().await;
+
+        buffer.flush().await.unwrap();
+        assert_eq!(backend.stored.lock().unwrap().len(), 2);
+        filling.await.unwrap().unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn flush_writes_values_below_the_limit() {
+        let backend = SlowBackend::default();
+        let buffer = WriteBuffer::wrap(Box::new(backend.clone()), 60.0, 10);
+
+        buffer.store_batch(vec![row(1.0), row(2.0)]).await.unwrap();
+        assert!(backend.stored.lock().unwrap().is_empty());
+        buffer.flush().await.unwrap();
+        assert_eq!(backend.stored.lock().unwrap().len(), 2);
+    }
+}